@@ -0,0 +1,85 @@
+//! Lexing and evaluation benchmarks; run them with `cargo bench`.
+//!
+//! A `while` loop benchmark belongs here once the language has loops.
+
+mod programs;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use proglang::{Lexer, LineReader, Runner};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts allocations, so benchmarks can report how many a run makes as well
+/// as how long it takes.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// How many allocations `f` makes.
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// A runner that prints nowhere and reads nothing.
+fn quiet_runner() -> Runner {
+    Runner::new()
+        .with_output(Box::new(io::sink()))
+        .with_input(Box::new(LineReader::new(io::empty())))
+}
+
+fn lex(source: &str) -> Vec<proglang::Token> {
+    Lexer::new(String::from(source)).tokenize().unwrap()
+}
+
+fn lexing(c: &mut Criterion) {
+    let source = programs::token_soup(100_000);
+
+    c.bench_function("lex 100k tokens", |b| b.iter(|| lex(black_box(&source))));
+}
+
+fn evaluation(c: &mut Criterion) {
+    let additions = programs::addition_chain(10_000);
+    let strings = programs::strings(10_000);
+
+    c.bench_function("run 10k additions", |b| {
+        b.iter(|| quiet_runner().run(lex(black_box(&additions))).unwrap())
+    });
+    c.bench_function("run 10k strings", |b| {
+        b.iter(|| quiet_runner().run(lex(black_box(&strings))).unwrap())
+    });
+}
+
+fn names(c: &mut Criterion) {
+    let calls = programs::word_calls(1_000);
+    // Counting is on in the REPL, so measure with it on here too.
+    let run = || {
+        quiet_runner()
+            .with_stats()
+            .run(lex(black_box(&calls)))
+            .unwrap()
+    };
+
+    println!("run 1k word calls: {} allocations", allocations(run));
+    c.bench_function("run 1k word calls", |b| b.iter(run));
+}
+
+criterion_group!(benches, lexing, evaluation, names);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+//! Generates the benchmark programs. The output depends only on the size
+//! asked for, so every run measures exactly the same source.
+
+/// Every kind of token the lexer knows, for [`token_soup`] to cycle through.
+const FRAGMENTS: &[&str] = &[
+    "12", "3.25", "\"text\"", "+", "*", "==", "<=", ">>", "puts", "collect", "{", "}",
+];
+
+/// `tokens` tokens of every kind, separated by spaces and the odd newline.
+/// Only meant for lexing; running it would fail early.
+pub fn token_soup(tokens: usize) -> String {
+    let mut source = String::new();
+    for i in 0..tokens {
+        source += FRAGMENTS[i % FRAGMENTS.len()];
+        source.push(if i % 16 == 15 { '\n' } else { ' ' });
+    }
+    source
+}
+
+/// Adds 1 to 0 `additions` times and prints the total.
+pub fn addition_chain(additions: usize) -> String {
+    let mut source = String::from("0");
+    for _ in 0..additions {
+        source += " 1 +";
+    }
+    source += " puts";
+    source
+}
+
+/// Pushes `strings` strings of varying length, measures each, then collects
+/// the lengths and prints them.
+pub fn strings(strings: usize) -> String {
+    let mut source = String::new();
+    for i in 0..strings {
+        source += &format!("\"{}\" len ", "héllo wörld ".repeat(i % 8 + 1));
+    }
+    source += "collect puts";
+    source
+}
+
+/// Calls a word `calls` times, each call looking up a global and the word's
+/// two parameters by name.
+pub fn word_calls(calls: usize) -> String {
+    let mut source = String::from("1 let x def step a b { a b + } 0");
+    for _ in 0..calls {
+        source += " x step";
+    }
+    source += " puts";
+    source
+}
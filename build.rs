@@ -0,0 +1,24 @@
+//! Records which commit the interpreter is built from, for `--version`.
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(hash) = hash {
+        println!("cargo:rustc-env=PROGLANG_GIT_HASH={}", hash.trim());
+    }
+
+    // Only look again once there's a new commit, where there's a repository
+    // to have one.
+    for path in [".git/HEAD", ".git/refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Exposes a host function to scripts: `"key" fetch_config` pushes the value
+//! stored under that key.
+//!
+//! Run it with `cargo run --example embed`.
+
+use proglang::{Runner, Token, TokenType};
+use std::collections::HashMap;
+use std::io;
+
+fn main() {
+    let config = HashMap::from([("greeting", "Hello"), ("name", "embedder")]);
+
+    let runner = Runner::new()
+        .register_fn("fetch_config", 1, move |args| {
+            let key = &*args[0].token_value;
+            match config.get(key) {
+                Some(value) => Ok(Token::new(TokenType::String, *value)),
+                None => Err(format!("No config value for {}", key)),
+            }
+        })
+        .expect("fetch_config is free to register");
+
+    let script = "\"greeting\" fetch_config puts \"name\" fetch_config puts \"port\" fetch_config";
+    if proglang::run_on(script, runner, Box::new(io::stderr())).is_err() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,313 @@
+//! The stack effect of every operator and keyword: how many values it pops
+//! and how many it pushes, for tools that reason about programs without
+//! running them.
+
+use crate::keyword::Keyword;
+use crate::token::TokenType;
+
+/// The `(inputs, outputs)` of running a token of type `t`, e.g. `(2, 1)` for
+/// [`Plus`](TokenType::Plus).
+///
+/// Values have no inputs and push themselves. A
+/// [`Keyword`](TokenType::Keyword) token's effect depends on which keyword
+/// it is, as given by [`keyword_arity`], or on the word or variable it names,
+/// so for the type alone it is `(0, 0)`. So are braces, which only mean
+/// something after `def`.
+pub fn arity(t: &TokenType) -> (usize, usize) {
+    match t {
+        TokenType::Int
+        | TokenType::Float
+        | TokenType::String
+        | TokenType::Bool
+        | TokenType::Nil
+        | TokenType::List
+        | TokenType::Function => (0, 1),
+        TokenType::Plus
+        | TokenType::Multiply
+        | TokenType::Equal
+        | TokenType::EqualEqual
+        | TokenType::NotEqual
+        | TokenType::Less
+        | TokenType::LessEqual
+        | TokenType::Greater
+        | TokenType::GreaterEqual
+        | TokenType::ShiftLeft
+        | TokenType::ShiftRight => (2, 1),
+        TokenType::Keyword | TokenType::LeftBrace | TokenType::RightBrace => (0, 0),
+    }
+}
+
+/// The `(inputs, outputs)` of `keyword`, e.g. `(1, 0)` for `puts`, or `None`
+/// for `collect`, which takes however many values are on the stack, for
+/// `format`, which takes as many as its template has placeholders, for
+/// `dupn`, which pushes as many copies as its int says, for
+/// `include`, `eval` and `call`, whose effect is that of whatever code they
+/// run, for `time`, whose effect is its block's plus the int it pushes, and
+/// for `if`, `else`, `match`, `try`, `catch` and `end`, whose effect is that
+/// of the code between them.
+///
+/// `def`, `let` and `fn` also read names from the program after them,
+/// which this doesn't count, and `map`, `filter` and `count` take one value
+/// fewer when a block follows them.
+pub fn keyword_arity(keyword: Keyword) -> Option<(usize, usize)> {
+    let effect = match keyword {
+        Keyword::Puts => (1, 0),
+        Keyword::Gets => (0, 1),
+        Keyword::Slurp | Keyword::ReadFile => (1, 1),
+        Keyword::Spit | Keyword::Append | Keyword::WriteFile | Keyword::AppendFile => (2, 0),
+        Keyword::Now | Keyword::Args | Keyword::LastStatus => (0, 1),
+        Keyword::Exec => (1, 1),
+        Keyword::Pick => (1, 1),
+        Keyword::Roll => (1, 0),
+        Keyword::Dupn => return None,
+        Keyword::Empty => (0, 1),
+        Keyword::Getenv | Keyword::Env => (1, 1),
+        Keyword::Fdiv | Keyword::Divisible | Keyword::Gcd | Keyword::Lcm | Keyword::Repeat => {
+            (2, 1)
+        }
+        Keyword::Floor | Keyword::Ceil | Keyword::Round | Keyword::Sign | Keyword::Abs => (1, 1),
+        Keyword::IsPrime | Keyword::Fact => (1, 1),
+        Keyword::Clamp => (3, 1),
+        Keyword::Sin | Keyword::Cos | Keyword::Tan => (1, 1),
+        Keyword::Ln | Keyword::Log10 | Keyword::Exp => (1, 1),
+        Keyword::Pi | Keyword::E | Keyword::Rand => (0, 1),
+        Keyword::Randint => (2, 1),
+        Keyword::Seed => (1, 0),
+        Keyword::Contains | Keyword::StartsWith | Keyword::EndsWith => (2, 1),
+        Keyword::Indexof => (2, 1),
+        Keyword::True | Keyword::False => (0, 1),
+        Keyword::Collect | Keyword::Include | Keyword::Eval | Keyword::Format => return None,
+        Keyword::Time => return None,
+        Keyword::If | Keyword::Else | Keyword::Match => return None,
+        Keyword::Try | Keyword::Catch | Keyword::End => return None,
+        Keyword::Len | Keyword::Sort | Keyword::Unique => (1, 1),
+        Keyword::Sum | Keyword::Product => (1, 1),
+        Keyword::Trim | Keyword::TrimStart | Keyword::TrimEnd => (1, 1),
+        Keyword::Select | Keyword::Replace => (3, 1),
+        Keyword::Def => (0, 0),
+        Keyword::Let => (1, 0),
+        Keyword::Fn => (0, 1),
+        Keyword::Call => return None,
+        Keyword::Count | Keyword::Map | Keyword::Filter => (2, 1),
+        Keyword::Throw => (1, 0),
+        Keyword::Raise => (2, 0),
+    };
+    Some(effect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::LineReader;
+    use crate::lexer::Lexer;
+    use crate::output::SharedBuffer;
+    use crate::runner::Runner;
+    use std::io;
+    use strum::IntoEnumIterator;
+
+    /// Arguments each keyword accepts, so running it shows its real effect.
+    /// Keywords that touch files, always fail, or need a list or a word after
+    /// them are checked by hand instead.
+    fn arguments_for(keyword: Keyword) -> Option<&'static str> {
+        match keyword {
+            Keyword::Puts | Keyword::Let | Keyword::Seed => Some("1"),
+            Keyword::Getenv | Keyword::Env => Some("\"PATH\""),
+            Keyword::Len | Keyword::Trim | Keyword::TrimStart | Keyword::TrimEnd => Some("\"abc\""),
+            Keyword::Fdiv | Keyword::Divisible | Keyword::Gcd | Keyword::Lcm => Some("6 3"),
+            Keyword::Randint => Some("1 6"),
+            Keyword::Floor | Keyword::Ceil | Keyword::Round | Keyword::Sign => Some("2.5"),
+            Keyword::Abs => Some("-5"),
+            Keyword::Clamp => Some("15 0 10"),
+            Keyword::IsPrime | Keyword::Fact => Some("7"),
+            Keyword::Sin | Keyword::Cos | Keyword::Tan => Some("1"),
+            Keyword::Ln | Keyword::Log10 | Keyword::Exp => Some("1"),
+            Keyword::Select => Some("true 1 2"),
+            Keyword::Replace => Some("\"ab\" \"a\" \"b\""),
+            Keyword::Repeat => Some("\"ab\" 2"),
+            Keyword::Contains | Keyword::StartsWith | Keyword::EndsWith => Some("\"ab\" \"a\""),
+            Keyword::Indexof => Some("\"ab\" \"b\""),
+            Keyword::Gets | Keyword::Now | Keyword::True | Keyword::False => Some(""),
+            Keyword::Args | Keyword::LastStatus => Some(""),
+            Keyword::Pick => Some("5 0"),
+            Keyword::Roll => Some("5 1"),
+            Keyword::Empty => Some(""),
+            Keyword::Pi | Keyword::E | Keyword::Rand => Some(""),
+            Keyword::Def | Keyword::Fn => Some(""),
+            Keyword::Dupn
+            | Keyword::Exec
+            | Keyword::Slurp
+            | Keyword::Spit
+            | Keyword::Append
+            | Keyword::ReadFile
+            | Keyword::WriteFile
+            | Keyword::AppendFile
+            | Keyword::Collect
+            | Keyword::Include
+            | Keyword::Eval
+            | Keyword::Time
+            | Keyword::Format
+            | Keyword::Count
+            | Keyword::Map
+            | Keyword::Filter
+            | Keyword::Call
+            | Keyword::Sort
+            | Keyword::Unique
+            | Keyword::Sum
+            | Keyword::Product
+            | Keyword::Throw
+            | Keyword::Raise
+            | Keyword::If
+            | Keyword::Else
+            | Keyword::Match
+            | Keyword::Try
+            | Keyword::Catch
+            | Keyword::End => None,
+        }
+    }
+
+    /// The program running `keyword` on `arguments`.
+    fn source_for(keyword: Keyword, arguments: &str) -> String {
+        match keyword {
+            Keyword::Def => String::from("def f { }"),
+            Keyword::Fn => String::from("def f { } fn f"),
+            Keyword::Let => format!("{} let x", arguments),
+            _ => format!("{} {}", arguments, keyword),
+        }
+    }
+
+    /// How much deeper the stack is after running `source` on top of a
+    /// marker value, which stops anything from popping more than it should
+    /// unnoticed.
+    fn stack_growth(source: &str) -> isize {
+        let mut runner = Runner::new()
+            .with_output(Box::new(io::sink()))
+            .with_input(Box::new(LineReader::new(io::empty())));
+        let source = format!("0 {}", source);
+        runner.run(Lexer::new(source).tokenize().unwrap()).unwrap();
+
+        runner.stack().len() as isize - 1
+    }
+
+    fn growth_of(effect: (usize, usize)) -> isize {
+        effect.1 as isize - effect.0 as isize
+    }
+
+    #[test]
+    fn plus_pops_two_and_pushes_one() {
+        assert_eq!(arity(&TokenType::Plus), (2, 1));
+        assert_eq!(keyword_arity(Keyword::Puts), Some((1, 0)));
+    }
+
+    #[test]
+    fn every_operator_matches_what_the_runner_does() {
+        for t_type in TokenType::iter() {
+            let (inputs, outputs) = arity(&t_type);
+            if inputs == 0 {
+                continue;
+            }
+
+            let operator = match t_type {
+                TokenType::Plus => "+",
+                TokenType::Multiply => "*",
+                TokenType::Equal => "=",
+                TokenType::EqualEqual => "==",
+                TokenType::NotEqual => "!=",
+                TokenType::Less => "<",
+                TokenType::LessEqual => "<=",
+                TokenType::Greater => ">",
+                TokenType::GreaterEqual => ">=",
+                TokenType::ShiftLeft => "<<",
+                TokenType::ShiftRight => ">>",
+                t_type => panic!("{} has inputs but no operator to test", t_type),
+            };
+            let effect = (inputs, outputs);
+
+            assert_eq!(
+                stack_growth(&format!("1 2 {}", operator)),
+                2 + growth_of(effect),
+                "{}",
+                t_type
+            );
+        }
+    }
+
+    #[test]
+    fn values_push_themselves() {
+        assert_eq!(stack_growth("1 2.5 \"three\" true"), 4);
+        for t_type in [TokenType::Int, TokenType::Float, TokenType::String] {
+            assert_eq!(arity(&t_type), (0, 1));
+        }
+    }
+
+    #[test]
+    fn every_keyword_matches_what_the_runner_does() {
+        for keyword in Keyword::iter() {
+            let Some(arguments) = arguments_for(keyword) else {
+                continue;
+            };
+            let effect = keyword_arity(keyword).unwrap();
+            let source = source_for(keyword, arguments);
+            let pushed = arguments.split_whitespace().count() as isize;
+
+            assert_eq!(
+                stack_growth(&source),
+                pushed + growth_of(effect),
+                "{}",
+                keyword
+            );
+        }
+    }
+
+    #[test]
+    fn dupn_pushes_as_many_copies_as_its_int_says() {
+        for n in 0..=2 {
+            assert_eq!(stack_growth(&format!("5 6 {} dupn", n)), 2 + n, "{}", n);
+        }
+    }
+
+    #[test]
+    fn only_puts_prints() {
+        for keyword in Keyword::iter().filter(|&keyword| keyword != Keyword::Puts) {
+            let Some(arguments) = arguments_for(keyword) else {
+                continue;
+            };
+            let out = SharedBuffer::default();
+            let mut runner = Runner::new()
+                .with_output(Box::new(out.clone()))
+                .with_input(Box::new(LineReader::new(io::empty())));
+            let source = source_for(keyword, arguments);
+            runner.run(Lexer::new(source).tokenize().unwrap()).unwrap();
+
+            assert_eq!(out.contents(), "", "{}", keyword);
+        }
+    }
+
+    #[test]
+    fn keywords_that_cannot_run_here_are_listed_by_hand() {
+        assert_eq!(keyword_arity(Keyword::Slurp), Some((1, 1)));
+        assert_eq!(keyword_arity(Keyword::Spit), Some((2, 0)));
+        assert_eq!(keyword_arity(Keyword::Append), Some((2, 0)));
+        assert_eq!(keyword_arity(Keyword::ReadFile), Some((1, 1)));
+        assert_eq!(keyword_arity(Keyword::WriteFile), Some((2, 0)));
+        assert_eq!(keyword_arity(Keyword::AppendFile), Some((2, 0)));
+        assert_eq!(keyword_arity(Keyword::Exec), Some((1, 1)));
+        assert_eq!(keyword_arity(Keyword::Collect), None);
+        assert_eq!(keyword_arity(Keyword::Dupn), None);
+        assert_eq!(keyword_arity(Keyword::Include), None);
+        assert_eq!(keyword_arity(Keyword::Eval), None);
+        assert_eq!(keyword_arity(Keyword::Time), None);
+        assert_eq!(keyword_arity(Keyword::Format), None);
+        assert_eq!(keyword_arity(Keyword::Count), Some((2, 1)));
+        assert_eq!(keyword_arity(Keyword::Map), Some((2, 1)));
+        assert_eq!(keyword_arity(Keyword::Filter), Some((2, 1)));
+        assert_eq!(keyword_arity(Keyword::Call), None);
+        assert_eq!(keyword_arity(Keyword::Sort), Some((1, 1)));
+        assert_eq!(keyword_arity(Keyword::Unique), Some((1, 1)));
+        assert_eq!(keyword_arity(Keyword::Sum), Some((1, 1)));
+        assert_eq!(keyword_arity(Keyword::Throw), Some((1, 0)));
+        assert_eq!(keyword_arity(Keyword::Raise), Some((2, 0)));
+        assert_eq!(keyword_arity(Keyword::Try), None);
+        assert_eq!(keyword_arity(Keyword::If), None);
+        assert_eq!(keyword_arity(Keyword::Match), None);
+    }
+}
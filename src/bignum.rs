@@ -0,0 +1,196 @@
+//! Arithmetic on whole numbers of any size, written out as decimal digits
+//! with a `-` in front of negative ones.
+//!
+//! This is what a [`Runner`](crate::runner::Runner) made
+//! [`with_big_ints`](crate::runner::Runner::with_big_ints) falls back on when
+//! a number doesn't fit in an `i64`. It works digit by digit the way it is
+//! done on paper, so it is much slower than the machine's own arithmetic.
+
+use std::cmp::Ordering;
+
+/// The sum of two numbers.
+pub(crate) fn add(a: &str, b: &str) -> String {
+    let (a_negative, a) = split(a);
+    let (b_negative, b) = split(b);
+
+    if a_negative == b_negative {
+        return signed(a_negative, add_digits(&a, &b));
+    }
+    // With different signs, the smaller size comes off the larger one, and
+    // the larger one's sign is kept.
+    match compare_digits(&a, &b) {
+        Ordering::Less => signed(b_negative, subtract_digits(&b, &a)),
+        _ => signed(a_negative, subtract_digits(&a, &b)),
+    }
+}
+
+/// The product of two numbers.
+pub(crate) fn multiply(a: &str, b: &str) -> String {
+    let (a_negative, a) = split(a);
+    let (b_negative, b) = split(b);
+    let mut product = vec![0; a.len() + b.len()];
+
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, y) in b.iter().enumerate() {
+            let total = product[i + j] + x * y + carry;
+            product[i + j] = total % 10;
+            carry = total / 10;
+        }
+        product[i + b.len()] += carry;
+    }
+
+    signed(a_negative != b_negative, product)
+}
+
+/// How two numbers order, ignoring any leading zeros.
+pub(crate) fn compare(a: &str, b: &str) -> Ordering {
+    let (a_negative, a) = split(a);
+    let (b_negative, b) = split(b);
+    let a_negative = a_negative && !is_zero(&a);
+    let b_negative = b_negative && !is_zero(&b);
+
+    match (a_negative, b_negative) {
+        (false, false) => compare_digits(&a, &b),
+        (true, true) => compare_digits(&b, &a),
+        (false, true) => Ordering::Greater,
+        (true, false) => Ordering::Less,
+    }
+}
+
+/// The number without its sign.
+pub(crate) fn abs(number: &str) -> &str {
+    number.strip_prefix('-').unwrap_or(number)
+}
+
+/// Whether a number is negative, and its digits, least significant first.
+fn split(number: &str) -> (bool, Vec<u32>) {
+    let (negative, number) = match number.strip_prefix('-') {
+        Some(number) => (true, number),
+        None => (false, number),
+    };
+    let digits = number
+        .bytes()
+        .rev()
+        .map(|digit| u32::from(digit - b'0'))
+        .collect();
+    (negative, digits)
+}
+
+fn is_zero(digits: &[u32]) -> bool {
+    digits.iter().all(|&digit| digit == 0)
+}
+
+fn add_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+
+    for i in 0..a.len().max(b.len()) {
+        let total = a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0) + carry;
+        sum.push(total % 10);
+        carry = total / 10;
+    }
+    if carry > 0 {
+        sum.push(carry);
+    }
+    sum
+}
+
+/// `a` less `b`, where `a` is at least as large.
+fn subtract_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+
+    for (i, &digit) in a.iter().enumerate() {
+        let taken = b.get(i).unwrap_or(&0) + borrow;
+        if digit >= taken {
+            difference.push(digit - taken);
+            borrow = 0;
+        } else {
+            difference.push(digit + 10 - taken);
+            borrow = 1;
+        }
+    }
+    difference
+}
+
+/// How two sizes, least significant digit first, order.
+fn compare_digits(a: &[u32], b: &[u32]) -> Ordering {
+    let significant = |digits: &[u32]| digits.iter().rposition(|&digit| digit != 0);
+    let (a_top, b_top) = (significant(a), significant(b));
+
+    a_top.cmp(&b_top).then_with(|| {
+        let top = a_top.map_or(0, |top| top + 1);
+        a[..top].iter().rev().cmp(b[..top].iter().rev())
+    })
+}
+
+/// Writes digits, least significant first, back out without leading zeros.
+/// 0 has no sign, even if it came from negative numbers.
+fn signed(negative: bool, digits: Vec<u32>) -> String {
+    let number: String = digits
+        .iter()
+        .rev()
+        .skip_while(|&&digit| digit == 0)
+        .map(|&digit| char::from_digit(digit, 10).unwrap())
+        .collect();
+
+    match (number.is_empty(), negative) {
+        (true, _) => String::from("0"),
+        (false, true) => format!("-{}", number),
+        (false, false) => number,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_with_carries() {
+        assert_eq!(add("999999999999999999999", "1"), "1000000000000000000000");
+        assert_eq!(add("0", "0"), "0");
+        assert_eq!(add("12", "30"), "42");
+    }
+
+    #[test]
+    fn multiplies_past_the_size_of_a_usize() {
+        assert_eq!(
+            multiply("18446744073709551615", "18446744073709551615"),
+            "340282366920938463426481119284349108225"
+        );
+        assert_eq!(multiply("123", "0"), "0");
+    }
+
+    #[test]
+    fn compares_by_value() {
+        assert_eq!(compare("100000000000000000000", "99"), Ordering::Greater);
+        assert_eq!(compare("007", "7"), Ordering::Equal);
+        assert_eq!(compare("123", "124"), Ordering::Less);
+    }
+
+    #[test]
+    fn adds_and_multiplies_negative_numbers() {
+        assert_eq!(
+            add("-999999999999999999999", "-1"),
+            "-1000000000000000000000"
+        );
+        assert_eq!(add("1000000000000000000000", "-1"), "999999999999999999999");
+        assert_eq!(
+            add("-1000000000000000000000", "1"),
+            "-999999999999999999999"
+        );
+        assert_eq!(add("-5", "5"), "0");
+        assert_eq!(multiply("-12", "30"), "-360");
+        assert_eq!(multiply("-12", "-30"), "360");
+        assert_eq!(multiply("-12", "0"), "0");
+    }
+
+    #[test]
+    fn compares_negative_numbers_below_the_rest() {
+        assert_eq!(compare("-100000000000000000000", "-99"), Ordering::Less);
+        assert_eq!(compare("-1", "0"), Ordering::Less);
+        assert_eq!(compare("-0", "0"), Ordering::Equal);
+        assert_eq!(compare("5", "-500"), Ordering::Greater);
+    }
+}
@@ -0,0 +1,335 @@
+//! Finding problems in a program without running it, for `--check`.
+
+use crate::config::Config;
+use crate::error::{Error, ErrorKind};
+use crate::lexer::Lexer;
+use crate::parser;
+use crate::runner::Runner;
+use crate::span::Span;
+use crate::token::{Token, TokenType};
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Every problem that can be found in `src` without running it, in the order
+/// they appear: lex errors, braces, `try`s, `if`s and `match`es without
+/// partners, and names that nothing defines.
+///
+/// Names count as defined if `runner` knows them, e.g. from its prelude, or
+/// if `src` defines them anywhere with `def` or `let`. A program that
+/// `include`s another file could get names from it, so its names aren't
+/// checked.
+///
+/// A lex error stops lexing of the line it's on, but checking carries on
+/// from the next line, except after a string that is never closed.
+///
+/// ```
+/// use proglang::{check, Runner};
+///
+/// let problems = check::check("1.2.3\n2 bogus", &Runner::new());
+///
+/// assert_eq!(problems.len(), 2);
+/// assert_eq!(problems[1].message, "No such keyword: bogus");
+/// ```
+pub fn check(src: &str, runner: &Runner) -> Vec<Error> {
+    let mut problems = Vec::new();
+    let tokens = lex(src, &runner.config(), &mut problems);
+    problems.extend(unpartnered(&tokens));
+    problems.extend(undefined_names(&tokens, runner));
+
+    problems.sort_by_key(|problem| (problem.span.line, problem.span.column));
+    problems
+}
+
+/// Lexes as much of `src` as possible, adding each lex error to `problems`.
+fn lex(src: &str, config: &Config, problems: &mut Vec<Error>) -> Vec<Token> {
+    let lexer = |src: String, line: usize| Lexer::from_config(src, config).starting_at_line(line);
+    let lines: Vec<&str> = src.split('\n').collect();
+    let mut tokens = Vec::new();
+    let mut from = 1;
+
+    while from <= lines.len() {
+        let error = match lexer(lines[from - 1..].join("\n"), from).tokenize() {
+            Ok(found) => {
+                tokens.extend(found);
+                break;
+            }
+            Err(error) => error,
+        };
+        // Keep the lines before the bad one, if they make sense on their own.
+        let before = lines[from - 1..error.span.line - 1].join("\n");
+        if let Ok(found) = lexer(before, from).tokenize() {
+            tokens.extend(found);
+        }
+
+        from = error.span.line + 1;
+        let unterminated = error.kind == ErrorKind::UnterminatedString;
+        problems.push(error);
+        if unterminated {
+            // Everything after it is part of the string.
+            break;
+        }
+    }
+    tokens
+}
+
+/// Braces and the parts of a `try`, an `if` or a `match` that are missing
+/// their partners.
+fn unpartnered(tokens: &[Token]) -> Vec<Error> {
+    let mut problems = Vec::new();
+    let problem = |message: &str, span: Span| Error::new(ErrorKind::Syntax, message, span);
+    let mut braces = Vec::new();
+    // Each open try, if or match, the keyword between it and its end, and
+    // whether that keyword has been seen. A match has none to wait for.
+    let mut blocks: Vec<(&str, &str, Span, bool)> = Vec::new();
+    // Where the catches of tries with a block for a body are.
+    let mut block_catches = HashSet::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        match token.token_type {
+            TokenType::LeftBrace => braces.push(token.span),
+            TokenType::RightBrace if braces.pop().is_none() => {
+                problems.push(problem("Found a } with no {", token.span));
+            }
+            TokenType::Keyword => match &*token.token_value {
+                // A body without its } is reported with the braces.
+                "try" if parser::starts_block(&tokens[index + 1..]) => {
+                    if let Some(length) = parser::block_end(&tokens[index + 1..]) {
+                        let catch = index + 1 + length;
+                        match tokens.get(catch) {
+                            Some(next) if &*next.token_value == "catch" => {
+                                block_catches.insert(catch);
+                            }
+                            _ => problems
+                                .push(problem("This try needs a catch after its body", token.span)),
+                        }
+                    }
+                }
+                "try" => blocks.push(("try", "catch", token.span, false)),
+                "catch" if block_catches.contains(&index) => {
+                    let rest = &tokens[index + 1..];
+                    let named = rest
+                        .first()
+                        .is_some_and(|name| name.token_type == TokenType::Keyword);
+                    if !parser::starts_block(&rest[usize::from(named)..]) {
+                        problems.push(problem("This catch needs a { handler }", token.span));
+                    }
+                }
+                "if" => blocks.push(("if", "else", token.span, false)),
+                "match" => blocks.push(("match", "", token.span, true)),
+                middle @ ("catch" | "else") => match blocks.last_mut() {
+                    Some((_, expected, _, seen)) if *expected == middle && !*seen => *seen = true,
+                    _ => {
+                        let opener = if middle == "catch" { "try" } else { "if" };
+                        problems.push(problem(
+                            &format!("{} can only follow {}", middle, opener),
+                            token.span,
+                        ))
+                    }
+                },
+                "end" => match blocks.pop() {
+                    Some((_, _, _, true)) => {}
+                    Some((opener, middle, span, false)) => {
+                        let needed = if middle == "else" {
+                            "an else"
+                        } else {
+                            "a catch"
+                        };
+                        problems.push(problem(
+                            &format!("This {} needs {} before its end", opener, needed),
+                            span,
+                        ))
+                    }
+                    None => problems.push(problem(
+                        "end can only close a try, an if or a match",
+                        token.span,
+                    )),
+                },
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    for span in braces {
+        problems.push(problem("This { is never closed", span));
+    }
+    for (opener, middle, span, seen) in blocks {
+        let closer = if seen { "end" } else { middle };
+        problems.push(problem(
+            &format!("This {} is missing its {}", opener, closer),
+            span,
+        ));
+    }
+    problems
+}
+
+/// Uses of names that neither `runner` nor the program defines.
+fn undefined_names(tokens: &[Token], runner: &Runner) -> Vec<Error> {
+    let is = |token: &Token, name: &str| {
+        token.token_type == TokenType::Keyword && &*token.token_value == name
+    };
+    if tokens.iter().any(|token| is(token, "include")) {
+        return Vec::new();
+    }
+
+    let mut known: HashSet<Rc<str>> = runner.names().into_iter().collect();
+    // Where each name being defined is, so it isn't taken for a use.
+    let mut definitions = HashSet::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let names = if is(token, "def") {
+            // The word's name, then its parameters.
+            tokens[i + 1..]
+                .iter()
+                .take_while(|token| token.token_type == TokenType::Keyword)
+                .count()
+        } else if is(token, "let") {
+            1
+        } else if is(token, "catch") && tokens.len() > i + 2 {
+            // The name a handler block gets the message in.
+            usize::from(parser::starts_block(&tokens[i + 2..]))
+        } else {
+            continue;
+        };
+        for (j, name) in tokens.iter().enumerate().skip(i + 1).take(names) {
+            if name.token_type == TokenType::Keyword {
+                known.insert(Rc::clone(&name.token_value));
+                definitions.insert(j);
+            }
+        }
+    }
+
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(i, token)| {
+            // `_` is the pattern in a match that matches anything.
+            token.token_type == TokenType::Keyword
+                && &*token.token_value != "_"
+                && !definitions.contains(i)
+                && !known.contains(&token.token_value)
+        })
+        .map(|(_, token)| {
+            Error::new(
+                ErrorKind::NameError,
+                &format!("No such keyword: {}", token.token_value),
+                token.span,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(src: &str) -> Vec<(usize, String)> {
+        check(src, &Runner::new())
+            .into_iter()
+            .map(|problem| (problem.span.line, problem.message))
+            .collect()
+    }
+
+    #[test]
+    fn clean_programs_have_no_problems() {
+        assert!(problems("def square n { n n * }\n3 square let x\nx puts").is_empty());
+        assert!(problems("try 1 catch puts end").is_empty());
+        assert!(problems("true if try 1 catch end else 2 end").is_empty());
+        assert!(problems("3 match 1 { 2 } _ { 3 } end puts").is_empty());
+        assert!(problems("try { 1 } catch err { err puts }").is_empty());
+        assert!(problems("try { try 1 catch end } catch { puts }").is_empty());
+        assert_eq!(
+            problems("3 match 1 { 2 }"),
+            vec![(1, String::from("This match is missing its end"))]
+        );
+    }
+
+    #[test]
+    fn finds_lex_errors_on_separate_lines() {
+        assert_eq!(
+            problems("1.2.3\n\"fine\" puts\n4.5.6"),
+            vec![
+                (1, String::from("Found an extra dot")),
+                (3, String::from("Found an extra dot")),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_braces_and_tries_without_partners() {
+        assert_eq!(
+            problems("}\ndef f { 1\ntry 2 end\ncatch"),
+            vec![
+                (1, String::from("Found a } with no {")),
+                (2, String::from("This { is never closed")),
+                (3, String::from("This try needs a catch before its end")),
+                (4, String::from("catch can only follow try")),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_block_tries_without_catches() {
+        assert_eq!(
+            problems("try { 1 } 2\ntry { 3 } catch 4"),
+            vec![
+                (1, String::from("This try needs a catch after its body")),
+                (2, String::from("This catch needs a { handler }")),
+            ]
+        );
+        assert_eq!(
+            problems("try { 1"),
+            vec![(1, String::from("This { is never closed"))]
+        );
+    }
+
+    #[test]
+    fn finds_ifs_without_partners() {
+        assert_eq!(
+            problems("true if 1 end\nelse\ntry 1 else 2 end\nfalse if 1 else"),
+            vec![
+                (1, String::from("This if needs an else before its end")),
+                (2, String::from("else can only follow if")),
+                (3, String::from("This try needs a catch before its end")),
+                (3, String::from("else can only follow if")),
+                (4, String::from("This if is missing its end")),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_names_nothing_defines() {
+        assert_eq!(
+            problems("bogus puts\ndef f { missing }"),
+            vec![
+                (1, String::from("No such keyword: bogus")),
+                (2, String::from("No such keyword: missing")),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_can_be_defined_after_they_are_used() {
+        assert!(problems("def f { g } def g { 1 }").is_empty());
+    }
+
+    #[test]
+    fn names_the_runner_knows_are_defined() {
+        let runner = Runner::new().with_prelude();
+
+        assert!(check("3 7 max puts", &runner).is_empty());
+        assert_eq!(problems("3 7 max puts").len(), 1);
+    }
+
+    #[test]
+    fn programs_that_include_files_keep_their_names_unchecked() {
+        assert!(problems("\"lib.plang\" include helper").is_empty());
+    }
+
+    #[test]
+    fn nothing_is_checked_past_an_unclosed_string() {
+        assert_eq!(
+            problems("\"open\nbogus"),
+            vec![(1, String::from("Missing the closing quote of a string"))]
+        );
+    }
+}
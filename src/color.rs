@@ -0,0 +1,227 @@
+//! Coloring diagnostics for terminals. Every ANSI escape code behind it is
+//! in this module, along with the one the REPL clears the screen with;
+//! [`report`](crate::report) asks a [`Style`] to paint each part of what it
+//! renders.
+
+use crate::error::Error;
+use crate::report;
+use crate::warning::Warning;
+use std::env;
+use std::str::FromStr;
+
+/// When to color diagnostics, as chosen with `--color`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    Always,
+    Never,
+    /// Color if the output is a terminal and the `NO_COLOR` environment
+    /// variable isn't set to something.
+    #[default]
+    Auto,
+}
+
+impl ColorChoice {
+    /// Whether to color output going to a terminal if `is_terminal`.
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        self.decide(is_terminal, no_color)
+    }
+
+    fn decide(self, is_terminal: bool, no_color: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => is_terminal && !no_color,
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(choice: &str) -> Result<ColorChoice, String> {
+        match choice {
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            "auto" => Ok(ColorChoice::Auto),
+            _ => Err(format!(
+                "Expected always, never or auto for the color, not {:?}",
+                choice
+            )),
+        }
+    }
+}
+
+/// Renders `error` like [`report::render`], with its name in red, its
+/// position in bold and the underline in red.
+pub fn render(error: &Error, source: &str) -> String {
+    report::render_styled(error, source, Style::colored(Tone::Error))
+}
+
+/// Renders `warning` like [`report::render_warning`], in yellow where an
+/// error would be red.
+pub fn render_warning(warning: &Warning, source: &str) -> String {
+    report::render_warning_styled(warning, source, Style::colored(Tone::Warning))
+}
+
+/// Renders `error` in color if `colored`, and plainly otherwise.
+pub fn render_if(colored: bool, error: &Error, source: &str) -> String {
+    if colored {
+        render(error, source)
+    } else {
+        report::render(error, source)
+    }
+}
+
+/// Renders `warning` in color if `colored`, and plainly otherwise.
+pub fn render_warning_if(colored: bool, warning: &Warning, source: &str) -> String {
+    if colored {
+        render_warning(warning, source)
+    } else {
+        report::render_warning(warning, source)
+    }
+}
+
+/// Whether a diagnostic is an error or a warning, which picks its color.
+#[derive(Clone, Copy)]
+pub(crate) enum Tone {
+    Error,
+    Warning,
+}
+
+/// How to paint the parts of a diagnostic.
+#[derive(Clone, Copy)]
+pub(crate) struct Style {
+    tone: Tone,
+    colored: bool,
+}
+
+/// Clears the screen, then moves the cursor to the top left, for `:clear`.
+pub(crate) const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+const RED: &str = "1;31";
+const YELLOW: &str = "1;33";
+const BOLD: &str = "1";
+
+impl Style {
+    /// Leaves everything as it is.
+    pub(crate) fn plain(tone: Tone) -> Style {
+        Style {
+            tone,
+            colored: false,
+        }
+    }
+
+    pub(crate) fn colored(tone: Tone) -> Style {
+        Style {
+            tone,
+            colored: true,
+        }
+    }
+
+    /// Paints the name of the error, or `warning`.
+    pub(crate) fn name(&self, text: &str) -> String {
+        self.paint(text, self.tone_code())
+    }
+
+    /// Paints where the diagnostic happened.
+    pub(crate) fn position(&self, text: &str) -> String {
+        self.paint(text, BOLD)
+    }
+
+    /// Paints the carets underlining the span.
+    pub(crate) fn underline(&self, text: &str) -> String {
+        self.paint(text, self.tone_code())
+    }
+
+    fn tone_code(&self) -> &'static str {
+        match self.tone {
+            Tone::Error => RED,
+            Tone::Warning => YELLOW,
+        }
+    }
+
+    fn paint(&self, text: &str, code: &str) -> String {
+        if self.colored {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            String::from(text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::span::Span;
+    use crate::warning::WarningKind;
+
+    fn error() -> Error {
+        Error::new(
+            ErrorKind::MismatchedTypes,
+            "Cannot add on 2 values of different types",
+            Span::new(6, 7, 1, 7),
+        )
+    }
+
+    #[test]
+    fn colors_the_name_position_and_underline_of_errors() {
+        assert_eq!(
+            render(&error(), "1 \"a\" +"),
+            concat!(
+                "\x1b[1;31mMismatched types\x1b[0m: Cannot add on 2 values of different types\n",
+                " --> \x1b[1mline 1, column 7\x1b[0m\n",
+                "  |\n",
+                "1 | 1 \"a\" +\n",
+                "  |       \x1b[1;31m^\x1b[0m",
+            )
+        );
+    }
+
+    #[test]
+    fn colors_warnings_yellow() {
+        let warning = Warning::new(
+            WarningKind::LeftoverValue,
+            "Value left on the stack when the program ended",
+            Span::new(0, 1, 1, 1),
+        );
+
+        assert_eq!(
+            render_warning(&warning, "1"),
+            concat!(
+                "\x1b[1;33mwarning\x1b[0m: Value left on the stack when the program ended\n",
+                " --> \x1b[1mline 1, column 1\x1b[0m\n",
+                "  |\n",
+                "1 | 1\n",
+                "  | \x1b[1;33m^\x1b[0m",
+            )
+        );
+    }
+
+    #[test]
+    fn plain_rendering_has_no_escapes() {
+        assert_eq!(
+            render_if(false, &error(), "1 \"a\" +"),
+            report::render(&error(), "1 \"a\" +")
+        );
+        assert!(!render_if(false, &error(), "1 \"a\" +").contains('\x1b'));
+    }
+
+    #[test]
+    fn auto_colors_only_terminals_without_no_color() {
+        assert!(ColorChoice::Auto.decide(true, false));
+        assert!(!ColorChoice::Auto.decide(false, false));
+        assert!(!ColorChoice::Auto.decide(true, true));
+        assert!(ColorChoice::Always.decide(false, true));
+        assert!(!ColorChoice::Never.decide(true, false));
+    }
+
+    #[test]
+    fn choices_parse_from_their_names() {
+        assert_eq!("always".parse(), Ok(ColorChoice::Always));
+        assert_eq!("never".parse(), Ok(ColorChoice::Never));
+        assert_eq!("auto".parse(), Ok(ColorChoice::Auto));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+}
@@ -0,0 +1,43 @@
+use crate::limits::ExecutionLimits;
+
+/// Every setting that changes how programs are lexed and run, so a
+/// [`Lexer`](crate::lexer::Lexer) and [`Runner`](crate::runner::Runner) can be
+/// set up the same way in one go.
+///
+/// The default is what [`Lexer::new`](crate::lexer::Lexer::new) and
+/// [`Runner::new`](crate::runner::Runner::new) use: small ints, no limits, no
+/// prelude and nothing counted.
+///
+/// ```
+/// use proglang::{Config, Lexer, Runner};
+///
+/// let config = Config {
+///     big_ints: true,
+///     prelude: true,
+///     ..Config::default()
+/// };
+/// let mut runner = Runner::from_config(config);
+/// let tokens = Lexer::from_config(String::from("99999999999999999999 square"), &config)
+///     .tokenize()
+///     .unwrap();
+/// runner.run(tokens).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Whether ints are exact at any size, as with
+    /// [`Runner::with_big_ints`](crate::runner::Runner::with_big_ints).
+    pub big_ints: bool,
+    /// What programs are held to, as with
+    /// [`Runner::with_limits`](crate::runner::Runner::with_limits).
+    pub limits: ExecutionLimits,
+    /// Whether the prelude's words are defined, as with
+    /// [`Runner::with_prelude`](crate::runner::Runner::with_prelude).
+    pub prelude: bool,
+    /// Whether operations are counted, as with
+    /// [`Runner::with_stats`](crate::runner::Runner::with_stats).
+    pub stats: bool,
+    /// How many digits after the point floats are printed with, as with
+    /// [`Runner::with_float_precision`](crate::runner::Runner::with_float_precision),
+    /// or `None` for as many as they need.
+    pub float_precision: Option<usize>,
+}
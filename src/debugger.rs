@@ -0,0 +1,160 @@
+//! Running a program one step at a time, for `--debug` and the REPL's
+//! `:debug on`.
+
+use crate::span::Span;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+/// What the debugger can be told to do while a program is paused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Command {
+    /// Run the next step, then pause again.
+    Step,
+    /// Run until a breakpoint's line is reached, or the program ends.
+    Continue,
+    /// Pause whenever the line is reached from now on.
+    Break(usize),
+    /// Show the variable with this name.
+    Print(String),
+    /// Show the whole stack.
+    Stack,
+    /// Stop the program.
+    Quit,
+    /// List the commands.
+    Help,
+}
+
+/// What each [`Command`] is typed as, for `help`.
+pub(crate) const HELP: &str = "\
+s, step       run the next step (so does an empty line)
+c, continue   run until a breakpoint, or the end
+b LINE        pause whenever LINE is reached
+p NAME        show the variable NAME
+stack         show the stack
+q, quit       stop the program
+h, help       list these commands";
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Command, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [] | ["s" | "step"] => Ok(Command::Step),
+            ["c" | "continue"] => Ok(Command::Continue),
+            ["b" | "break", line] => match line.parse() {
+                Ok(line) => Ok(Command::Break(line)),
+                Err(_) => Err(format!("Expected a line number, not {:?}", line)),
+            },
+            ["p" | "print", name] => Ok(Command::Print(String::from(name))),
+            ["stack"] => Ok(Command::Stack),
+            ["q" | "quit"] => Ok(Command::Quit),
+            ["h" | "help"] => Ok(Command::Help),
+            _ => Err(format!("Unknown debugger command: {}", line.trim())),
+        }
+    }
+}
+
+/// Decides which steps of a program to pause before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Debugger {
+    /// Whether to pause before the next step, whatever its line.
+    stepping: bool,
+    breakpoints: BTreeSet<usize>,
+    /// The line of the last step, so a breakpoint pauses when its line is
+    /// reached rather than before every token on it.
+    line: Option<usize>,
+}
+
+impl Debugger {
+    /// A debugger that pauses before the first step.
+    pub(crate) fn new() -> Debugger {
+        Debugger {
+            stepping: true,
+            breakpoints: BTreeSet::new(),
+            line: None,
+        }
+    }
+
+    /// Gets ready for another program, pausing before its first step. The
+    /// breakpoints are kept.
+    pub(crate) fn restart(&mut self) {
+        self.stepping = true;
+        self.line = None;
+    }
+
+    /// Whether to pause before the step at `span`.
+    pub(crate) fn pauses_at(&mut self, span: Span) -> bool {
+        let reached = self.line != Some(span.line);
+        self.line = Some(span.line);
+        self.stepping || (reached && self.breakpoints.contains(&span.line))
+    }
+
+    /// Pauses again before the next step.
+    pub(crate) fn step(&mut self) {
+        self.stepping = true;
+    }
+
+    /// Pauses again only at a breakpoint.
+    pub(crate) fn resume(&mut self) {
+        self.stepping = false;
+    }
+
+    pub(crate) fn break_at(&mut self, line: usize) {
+        self.breakpoints.insert(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(line: usize, column: usize) -> Span {
+        Span::new(0, 0, line, column)
+    }
+
+    #[test]
+    fn commands_parse_from_their_names() {
+        assert_eq!("".parse(), Ok(Command::Step));
+        assert_eq!("s".parse(), Ok(Command::Step));
+        assert_eq!(" continue ".parse(), Ok(Command::Continue));
+        assert_eq!("b 12".parse(), Ok(Command::Break(12)));
+        assert_eq!("p total".parse(), Ok(Command::Print(String::from("total"))));
+        assert_eq!("stack".parse(), Ok(Command::Stack));
+        assert_eq!("q".parse(), Ok(Command::Quit));
+    }
+
+    #[test]
+    fn bad_commands_say_what_is_wrong() {
+        assert_eq!(
+            "b twelve".parse::<Command>(),
+            Err(String::from("Expected a line number, not \"twelve\""))
+        );
+        assert_eq!(
+            "jump 3".parse::<Command>(),
+            Err(String::from("Unknown debugger command: jump 3"))
+        );
+        assert!("p".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn pauses_before_every_step_while_stepping() {
+        let mut debugger = Debugger::new();
+
+        assert!(debugger.pauses_at(at(1, 1)));
+        assert!(debugger.pauses_at(at(1, 3)));
+    }
+
+    #[test]
+    fn breakpoints_pause_once_each_time_their_line_is_reached() {
+        let mut debugger = Debugger::new();
+        debugger.break_at(2);
+        debugger.resume();
+
+        let paused: Vec<bool> = [at(1, 1), at(2, 1), at(2, 3), at(1, 5), at(2, 1)]
+            .into_iter()
+            .map(|span| debugger.pauses_at(span))
+            .collect();
+        assert_eq!(paused, [false, true, false, false, true]);
+    }
+}
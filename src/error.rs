@@ -0,0 +1,214 @@
+use crate::span::Span;
+use std::fmt;
+use std::rc::Rc;
+
+/// What went wrong, independent of the message describing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    // Lexing
+    IllegalChar,
+    UnterminatedString,
+    // Running
+    Syntax,
+    MismatchedTypes,
+    /// A value of the right type that still can't be used, e.g. an empty
+    /// string to replace.
+    Value,
+    StackUnderflow,
+    NameError,
+    Overflow,
+    LimitExceeded,
+    Recursion,
+    Native,
+    Io,
+    Include,
+    User,
+    Env,
+    Time,
+    /// The program was stopped on purpose, from the debugger.
+    Stopped,
+    /// Something the runner doesn't allow programs to do, such as using
+    /// files.
+    Capability,
+}
+
+impl ErrorKind {
+    /// The name users see in front of the message.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::IllegalChar => "IllegalCharError",
+            ErrorKind::UnterminatedString => "UnterminatedStringError",
+            ErrorKind::Syntax => "SyntaxError",
+            ErrorKind::MismatchedTypes => "Mismatched types",
+            ErrorKind::Value => "ValueError",
+            ErrorKind::StackUnderflow => "StackUnderflowError",
+            ErrorKind::NameError => "Unknown keyword error",
+            ErrorKind::Overflow => "OverflowError",
+            ErrorKind::LimitExceeded => "LimitExceededError",
+            ErrorKind::Recursion => "RecursionError",
+            ErrorKind::Native => "NativeFunctionError",
+            ErrorKind::Io => "IOError",
+            ErrorKind::Include => "IncludeError",
+            ErrorKind::User => "UserError",
+            ErrorKind::Env => "EnvError",
+            ErrorKind::Time => "TimeError",
+            ErrorKind::Stopped => "StoppedError",
+            ErrorKind::Capability => "CapabilityError",
+        }
+    }
+}
+
+/// A single problem found while lexing or running a program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Where in the source the problem is.
+    pub span: Span,
+    /// The calls to words that were running when it happened, innermost
+    /// first. Empty for errors outside any word.
+    pub trace: Vec<Frame>,
+    /// The name a program gave the error with `raise`, shown instead of the
+    /// kind's.
+    pub label: Option<Rc<str>>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: &str, span: Span) -> Error {
+        Error {
+            kind,
+            message: String::from(message),
+            span,
+            trace: Vec::new(),
+            label: None,
+        }
+    }
+
+    /// The name users see in front of the message: the one it was raised
+    /// with if it has one, or else its kind's.
+    pub fn name(&self) -> &str {
+        match &self.label {
+            Some(label) => label,
+            None => self.kind.name(),
+        }
+    }
+}
+
+/// One call in an [`Error`]'s trace: the word called and where it was called
+/// from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub name: Rc<str>,
+    pub span: Span,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name(), self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An [`Error`] tagged with the stage of the interpreter that raised it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgLangError {
+    LexError(Error),
+    ParseError(Error),
+    RuntimeError(Error),
+}
+
+impl ProgLangError {
+    /// The underlying error, whatever stage raised it.
+    pub fn error(&self) -> &Error {
+        match self {
+            ProgLangError::LexError(error)
+            | ProgLangError::ParseError(error)
+            | ProgLangError::RuntimeError(error) => error,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.error().kind
+    }
+
+    pub fn span(&self) -> Span {
+        self.error().span
+    }
+
+    /// The exit code for a process stopped by this error: 2 if the program
+    /// couldn't be lexed or parsed, 1 if it failed while running.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ProgLangError::LexError(_) | ProgLangError::ParseError(_) => 2,
+            ProgLangError::RuntimeError(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for ProgLangError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.error().fmt(f)
+    }
+}
+
+impl std::error::Error for ProgLangError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extra_dot() -> Error {
+        Error::new(
+            ErrorKind::IllegalChar,
+            "Found an extra dot",
+            Span::new(3, 4, 1, 4),
+        )
+    }
+
+    #[test]
+    fn new_stores_kind_message_and_span() {
+        let error = extra_dot();
+
+        assert_eq!(error.kind, ErrorKind::IllegalChar);
+        assert_eq!(error.message, "Found an extra dot");
+        assert_eq!(error.span, Span::new(3, 4, 1, 4));
+    }
+
+    #[test]
+    fn displays_as_name_and_message() {
+        assert_eq!(
+            extra_dot().to_string(),
+            "IllegalCharError: Found an extra dot"
+        );
+    }
+
+    #[test]
+    fn a_label_replaces_the_kind_name() {
+        let mut error = Error::new(ErrorKind::User, "config missing", Span::default());
+        error.label = Some(Rc::from("ConfigError"));
+
+        assert_eq!(error.name(), "ConfigError");
+        assert_eq!(error.to_string(), "ConfigError: config missing");
+    }
+
+    #[test]
+    fn stages_share_the_underlying_error() {
+        let error = ProgLangError::LexError(extra_dot());
+
+        assert_eq!(error.kind(), ErrorKind::IllegalChar);
+        assert_eq!(error.span(), Span::new(3, 4, 1, 4));
+        assert_eq!(error.to_string(), "IllegalCharError: Found an extra dot");
+    }
+
+    #[test]
+    fn exit_codes_follow_the_stage() {
+        assert_eq!(ProgLangError::LexError(extra_dot()).exit_code(), 2);
+        assert_eq!(ProgLangError::ParseError(extra_dot()).exit_code(), 2);
+        assert_eq!(ProgLangError::RuntimeError(extra_dot()).exit_code(), 1);
+    }
+}
@@ -0,0 +1,82 @@
+//! A string-in, string-out way to run programs, for hosts with no terminal
+//! such as a web page. With the `wasm` feature it is exported to JavaScript.
+
+use crate::output::SharedBuffer;
+use crate::{detached_runner, report, run_on};
+use std::io;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Enough steps for any reasonable program, while still stopping a runaway
+/// one before it freezes the page.
+const STEP_LIMIT: usize = 10_000_000;
+
+/// Everything a program printed, and the report of the error that stopped it
+/// if one did.
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalResult {
+    pub output: String,
+    pub error: Option<String>,
+}
+
+/// Runs `src` with nothing to read from and returns what it printed.
+///
+/// ```
+/// let result = proglang::eval("1 2 + puts bogus");
+///
+/// assert_eq!(result.output, "3\n");
+/// assert!(result.error.unwrap().starts_with("Unknown keyword error"));
+/// ```
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn eval(src: &str) -> EvalResult {
+    let output = SharedBuffer::default();
+    let runner = detached_runner(output.clone()).with_step_limit(STEP_LIMIT);
+
+    let error = run_on(src, runner, Box::new(io::sink()))
+        .err()
+        .map(|error| report::render(error.error(), src));
+
+    EvalResult {
+        output: output.contents(),
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_the_output_of_programs_that_succeed() {
+        assert_eq!(
+            eval("\"hi\" puts"),
+            EvalResult {
+                output: String::from("hi\n"),
+                error: None,
+            }
+        );
+    }
+
+    #[test]
+    fn returns_the_rendered_error_alongside_earlier_output() {
+        let result = eval("1 puts\n\"a\" 1 +");
+
+        assert_eq!(result.output, "1\n");
+        assert_eq!(
+            result.error.unwrap(),
+            concat!(
+                "Mismatched types: Cannot add on 2 values of different types\n",
+                " --> line 2, column 7\n",
+                "  |\n",
+                "2 | \"a\" 1 +\n",
+                "  |       ^",
+            )
+        );
+    }
+
+    #[test]
+    fn gets_sees_the_end_of_input() {
+        assert_eq!(eval("gets puts").output, "nil\n");
+    }
+}
@@ -0,0 +1,362 @@
+//! What the REPL's `:help` and `:types` say about everything built into the
+//! language.
+//!
+//! The listings go through every [`Keyword`] and [`TokenType`], so anything
+//! added to either has to be described here before the crate builds.
+
+use crate::arity::{arity, keyword_arity};
+use crate::keyword::Keyword;
+use crate::token::TokenType;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+
+/// One line on what `keyword` does.
+fn keyword_summary(keyword: Keyword) -> &'static str {
+    match keyword {
+        Keyword::Puts => "Pops a value and prints it",
+        Keyword::Gets => "Reads a line of input, or pushes nil at the end of it",
+        Keyword::Slurp => "Pops a path and pushes the contents of that file",
+        Keyword::Spit => "Pops a string and a path and writes the string to that file",
+        Keyword::Append => "Pops a string and a path and adds the string to that file",
+        Keyword::ReadFile => "The same as slurp",
+        Keyword::WriteFile => "The same as spit",
+        Keyword::AppendFile => "The same as append",
+        Keyword::Now => "Pushes the time in seconds since 1970",
+        Keyword::Time => "Runs a block and pushes how many milliseconds it took",
+        Keyword::Getenv => "Pops a name and pushes that environment variable",
+        Keyword::Env => "Like getenv, but pushes nil if the variable isn't set",
+        Keyword::Args => "Pushes the program's arguments as a list of strings",
+        Keyword::Exec => "Runs a command and pushes what it printed",
+        Keyword::LastStatus => "Pushes the exit status of the last command exec ran",
+        Keyword::Fdiv => "Divides two numbers, always giving a float",
+        Keyword::Divisible => "Pushes whether the second int divides the first",
+        Keyword::Gcd => "Pushes the greatest common divisor of two ints",
+        Keyword::Lcm => "Pushes the least common multiple of two ints",
+        Keyword::IsPrime => "Pushes whether an int is prime",
+        Keyword::Fact => "Pushes the factorial of an int",
+        Keyword::Floor => "Rounds a number down to a whole float",
+        Keyword::Ceil => "Rounds a number up to a whole float",
+        Keyword::Round => "Rounds a number to the nearest whole float",
+        Keyword::Sign => "Pushes 1, 0 or -1 for a number above, at or below 0",
+        Keyword::Abs => "Pushes a number without its sign",
+        Keyword::Clamp => "Moves a number into the range between two others",
+        Keyword::Sin => "Pushes the sine of an angle in radians",
+        Keyword::Cos => "Pushes the cosine of an angle in radians",
+        Keyword::Tan => "Pushes the tangent of an angle in radians",
+        Keyword::Ln => "Pushes the natural logarithm of a number above 0",
+        Keyword::Log10 => "Pushes the base 10 logarithm of a number above 0",
+        Keyword::Exp => "Pushes e to the power of a number",
+        Keyword::Pi => "Pushes pi, the ratio of a circle's circumference to its diameter",
+        Keyword::E => "Pushes e, the base of the natural logarithm",
+        Keyword::Rand => "Pushes a random float from 0 up to 1",
+        Keyword::Randint => "Pushes a random int between two ints, both included",
+        Keyword::Seed => "Pops an int to start rand and randint from",
+        Keyword::True => "Pushes true",
+        Keyword::False => "Pushes false",
+        Keyword::Collect => "Gathers the whole stack into a list",
+        Keyword::Pick => "Copies the value an int's depth down the stack onto the top",
+        Keyword::Roll => "Moves the value an int's count down the stack to the top",
+        Keyword::Dupn => "Copies an int's count of values from the top of the stack",
+        Keyword::Empty => "Pushes whether the stack is empty",
+        Keyword::Len => "Pushes how long a list or string is",
+        Keyword::Count => "Counts the values of a list a function is true of",
+        Keyword::Map => "Calls a function on each value of a list and lists the results",
+        Keyword::Filter => "Lists the values of a list a function is true of",
+        Keyword::Repeat => "Pushes a string repeated some number of times",
+        Keyword::Trim => "Strips whitespace from both ends of a string",
+        Keyword::TrimStart => "Strips whitespace from the start of a string",
+        Keyword::TrimEnd => "Strips whitespace from the end of a string",
+        Keyword::Contains => "Pushes whether the second string is in the first",
+        Keyword::StartsWith => "Pushes whether the first string starts with the second",
+        Keyword::EndsWith => "Pushes whether the first string ends with the second",
+        Keyword::Replace => "Replaces every match of a string in another",
+        Keyword::Indexof => "Pushes where a string is in another, or -1",
+        Keyword::Format => "Fills in each {} of a string with a value",
+        Keyword::Sort => "Sorts a list of numbers or of strings",
+        Keyword::Unique => "Drops repeated values from a list",
+        Keyword::Sum => "Adds up a list of numbers",
+        Keyword::Product => "Multiplies together a list of numbers",
+        Keyword::Select => "Pushes one of two values depending on a bool",
+        Keyword::If => "Runs the code up to its else if a bool is true",
+        Keyword::Else => "Starts the code an if runs if its bool is false",
+        Keyword::Match => "Runs the block of the first pattern a value equals",
+        Keyword::Def => "Defines a word: def NAME PARAMS... { BODY }",
+        Keyword::Let => "Pops a value into the variable named after it",
+        Keyword::Fn => "Pushes the word named after it as a function",
+        Keyword::Call => "Pops a function and calls it",
+        Keyword::Include => "Pops a path and runs that file in place",
+        Keyword::Eval => "Pops a string and runs it as code in place",
+        Keyword::Throw => "Pops a message and stops with it as an error",
+        Keyword::Raise => "Pops a message and the name under it and stops with that error",
+        Keyword::Try => "Runs code, then its catch if that code fails",
+        Keyword::Catch => "Starts the code a try runs if it fails",
+        Keyword::End => "Ends a try, an if or a match",
+    }
+}
+
+/// How an operator of type `t` is written and one line on what it does, or
+/// `None` for types that aren't operators.
+fn operator_summary(t: TokenType) -> Option<(&'static str, &'static str)> {
+    let summary = match t {
+        TokenType::Plus => ("+", "Adds two numbers"),
+        TokenType::Multiply => ("*", "Multiplies two numbers"),
+        TokenType::Equal => ("=", "Pushes whether two values are equal"),
+        TokenType::EqualEqual => ("==", "Pushes whether two values are equal"),
+        TokenType::NotEqual => ("!=", "Pushes whether two values differ"),
+        TokenType::Less => ("<", "Pushes whether the first value is smaller"),
+        TokenType::LessEqual => ("<=", "Pushes whether the first value is no bigger"),
+        TokenType::Greater => (">", "Pushes whether the first value is bigger"),
+        TokenType::GreaterEqual => (">=", "Pushes whether the first value is no smaller"),
+        TokenType::ShiftLeft => ("<<", "Shifts an int left by some bits"),
+        TokenType::ShiftRight => (">>", "Shifts an int right by some bits"),
+        TokenType::Int
+        | TokenType::Float
+        | TokenType::String
+        | TokenType::Bool
+        | TokenType::Nil
+        | TokenType::List
+        | TokenType::Function
+        | TokenType::Keyword
+        | TokenType::LeftBrace
+        | TokenType::RightBrace => return None,
+    };
+    Some(summary)
+}
+
+/// How an operator of type `t` is written, or `None` for types that aren't
+/// operators.
+pub(crate) fn operator(t: TokenType) -> Option<&'static str> {
+    operator_summary(t).map(|(operator, _)| operator)
+}
+
+/// One line on what a token of type `t` is.
+fn type_summary(t: TokenType) -> &'static str {
+    match t {
+        TokenType::Int => "A whole number, e.g. 42",
+        TokenType::Float => "A number with a fractional part, e.g. 2.5",
+        TokenType::String => "Text between double quotes, e.g. \"hi\"",
+        TokenType::Bool => "true or false",
+        TokenType::Nil => "No value, e.g. what gets pushes at the end of input",
+        TokenType::List => "A sequence of values, e.g. what collect pushes",
+        TokenType::Function => "A word to call later, e.g. what fn pushes",
+        TokenType::Keyword => "A keyword, or the name of a word or variable",
+        TokenType::Plus => "The + operator",
+        TokenType::Multiply => "The * operator",
+        TokenType::Equal => "The = operator",
+        TokenType::EqualEqual => "The == operator",
+        TokenType::NotEqual => "The != operator",
+        TokenType::Less => "The < operator",
+        TokenType::LessEqual => "The <= operator",
+        TokenType::Greater => "The > operator",
+        TokenType::GreaterEqual => "The >= operator",
+        TokenType::ShiftLeft => "The << operator",
+        TokenType::ShiftRight => "The >> operator",
+        TokenType::LeftBrace => "{, which starts the body of a def",
+        TokenType::RightBrace => "}, which ends the body of a def",
+    }
+}
+
+/// Every type of token with a line on what it is.
+pub fn types() -> String {
+    let width = TokenType::iter()
+        .map(|t| t.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    let mut listing = String::from("types:\n");
+    for t in TokenType::iter() {
+        listing += &format!(
+            "  {:width$}  {}\n",
+            t.to_string(),
+            type_summary(t),
+            width = width
+        );
+    }
+    listing
+}
+
+/// Every keyword and operator with a line on what it does.
+pub fn listing() -> String {
+    let keywords: Vec<(String, &str)> = Keyword::iter()
+        .map(|keyword| (keyword.to_string(), keyword_summary(keyword)))
+        .collect();
+    let operators: Vec<(String, &str)> = TokenType::iter()
+        .filter_map(operator_summary)
+        .map(|(operator, summary)| (String::from(operator), summary))
+        .collect();
+    let width = keywords
+        .iter()
+        .chain(&operators)
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut listing = String::from("keywords:\n");
+    for (name, summary) in &keywords {
+        listing += &format!("  {:width$}  {}\n", name, summary, width = width);
+    }
+    listing += "operators:\n";
+    for (name, summary) in &operators {
+        listing += &format!("  {:width$}  {}\n", name, summary, width = width);
+    }
+    listing += "Use :help NAME for more on one, or :words for the words defined so far.\n";
+    listing
+}
+
+/// What one keyword or operator does and its effect on the stack, or `None`
+/// if `name` is neither.
+pub fn entry(name: &str) -> Option<String> {
+    let (summary, effect) = match Keyword::from_str(name) {
+        Ok(keyword) => (keyword_summary(keyword), keyword_arity(keyword)),
+        Err(_) => {
+            let t = TokenType::iter()
+                .find(|&t| operator_summary(t).is_some_and(|(operator, _)| operator == name))?;
+            (operator_summary(t)?.1, Some(arity(&t)))
+        }
+    };
+
+    let effect = match effect {
+        Some((inputs, outputs)) => format!("pops {}, pushes {}", inputs, outputs),
+        None => String::from("varies"),
+    };
+    Some(format!("{}: {}\n  stack: {}\n", name, summary, effect))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_keyword_and_operator() {
+        assert_eq!(
+            listing(),
+            concat!(
+                "keywords:\n",
+                "  puts         Pops a value and prints it\n",
+                "  gets         Reads a line of input, or pushes nil at the end of it\n",
+                "  slurp        Pops a path and pushes the contents of that file\n",
+                "  spit         Pops a string and a path and writes the string to that file\n",
+                "  append       Pops a string and a path and adds the string to that file\n",
+                "  read_file    The same as slurp\n",
+                "  write_file   The same as spit\n",
+                "  append_file  The same as append\n",
+                "  now          Pushes the time in seconds since 1970\n",
+                "  time         Runs a block and pushes how many milliseconds it took\n",
+                "  getenv       Pops a name and pushes that environment variable\n",
+                "  env          Like getenv, but pushes nil if the variable isn't set\n",
+                "  args         Pushes the program's arguments as a list of strings\n",
+                "  exec         Runs a command and pushes what it printed\n",
+                "  last_status  Pushes the exit status of the last command exec ran\n",
+                "  fdiv         Divides two numbers, always giving a float\n",
+                "  divisible    Pushes whether the second int divides the first\n",
+                "  gcd          Pushes the greatest common divisor of two ints\n",
+                "  lcm          Pushes the least common multiple of two ints\n",
+                "  is_prime     Pushes whether an int is prime\n",
+                "  fact         Pushes the factorial of an int\n",
+                "  floor        Rounds a number down to a whole float\n",
+                "  ceil         Rounds a number up to a whole float\n",
+                "  round        Rounds a number to the nearest whole float\n",
+                "  sign         Pushes 1, 0 or -1 for a number above, at or below 0\n",
+                "  abs          Pushes a number without its sign\n",
+                "  clamp        Moves a number into the range between two others\n",
+                "  sin          Pushes the sine of an angle in radians\n",
+                "  cos          Pushes the cosine of an angle in radians\n",
+                "  tan          Pushes the tangent of an angle in radians\n",
+                "  ln           Pushes the natural logarithm of a number above 0\n",
+                "  log10        Pushes the base 10 logarithm of a number above 0\n",
+                "  exp          Pushes e to the power of a number\n",
+                "  pi           Pushes pi, the ratio of a circle's circumference to its diameter\n",
+                "  e            Pushes e, the base of the natural logarithm\n",
+                "  rand         Pushes a random float from 0 up to 1\n",
+                "  randint      Pushes a random int between two ints, both included\n",
+                "  seed         Pops an int to start rand and randint from\n",
+                "  true         Pushes true\n",
+                "  false        Pushes false\n",
+                "  collect      Gathers the whole stack into a list\n",
+                "  pick         Copies the value an int's depth down the stack onto the top\n",
+                "  roll         Moves the value an int's count down the stack to the top\n",
+                "  dupn         Copies an int's count of values from the top of the stack\n",
+                "  empty?       Pushes whether the stack is empty\n",
+                "  len          Pushes how long a list or string is\n",
+                "  count        Counts the values of a list a function is true of\n",
+                "  map          Calls a function on each value of a list and lists the results\n",
+                "  filter       Lists the values of a list a function is true of\n",
+                "  repeat       Pushes a string repeated some number of times\n",
+                "  trim         Strips whitespace from both ends of a string\n",
+                "  trim_start   Strips whitespace from the start of a string\n",
+                "  trim_end     Strips whitespace from the end of a string\n",
+                "  contains     Pushes whether the second string is in the first\n",
+                "  starts_with  Pushes whether the first string starts with the second\n",
+                "  ends_with    Pushes whether the first string ends with the second\n",
+                "  replace      Replaces every match of a string in another\n",
+                "  indexof      Pushes where a string is in another, or -1\n",
+                "  format       Fills in each {} of a string with a value\n",
+                "  sort         Sorts a list of numbers or of strings\n",
+                "  unique       Drops repeated values from a list\n",
+                "  sum          Adds up a list of numbers\n",
+                "  product      Multiplies together a list of numbers\n",
+                "  select       Pushes one of two values depending on a bool\n",
+                "  if           Runs the code up to its else if a bool is true\n",
+                "  else         Starts the code an if runs if its bool is false\n",
+                "  match        Runs the block of the first pattern a value equals\n",
+                "  def          Defines a word: def NAME PARAMS... { BODY }\n",
+                "  let          Pops a value into the variable named after it\n",
+                "  fn           Pushes the word named after it as a function\n",
+                "  call         Pops a function and calls it\n",
+                "  include      Pops a path and runs that file in place\n",
+                "  eval         Pops a string and runs it as code in place\n",
+                "  throw        Pops a message and stops with it as an error\n",
+                "  raise        Pops a message and the name under it and stops with that error\n",
+                "  try          Runs code, then its catch if that code fails\n",
+                "  catch        Starts the code a try runs if it fails\n",
+                "  end          Ends a try, an if or a match\n",
+                "operators:\n",
+                "  +            Adds two numbers\n",
+                "  *            Multiplies two numbers\n",
+                "  =            Pushes whether two values are equal\n",
+                "  ==           Pushes whether two values are equal\n",
+                "  !=           Pushes whether two values differ\n",
+                "  <            Pushes whether the first value is smaller\n",
+                "  <=           Pushes whether the first value is no bigger\n",
+                "  >            Pushes whether the first value is bigger\n",
+                "  >=           Pushes whether the first value is no smaller\n",
+                "  <<           Shifts an int left by some bits\n",
+                "  >>           Shifts an int right by some bits\n",
+                "Use :help NAME for more on one, or :words for the words defined so far.\n",
+            )
+        );
+    }
+
+    #[test]
+    fn entries_show_the_stack_effect() {
+        assert_eq!(
+            entry("puts").unwrap(),
+            "puts: Pops a value and prints it\n  stack: pops 1, pushes 0\n"
+        );
+        assert_eq!(
+            entry("<=").unwrap(),
+            "<=: Pushes whether the first value is no bigger\n  stack: pops 2, pushes 1\n"
+        );
+        assert_eq!(
+            entry("collect").unwrap(),
+            "collect: Gathers the whole stack into a list\n  stack: varies\n"
+        );
+    }
+
+    #[test]
+    fn types_lists_every_token_type() {
+        let listing = types();
+
+        assert!(listing.starts_with("types:\n  Int           A whole number, e.g. 42\n"));
+        for t in TokenType::iter() {
+            assert!(listing.contains(&format!("\n  {} ", t)), "{}", t);
+        }
+        assert_eq!(listing.lines().count(), TokenType::iter().count() + 1);
+    }
+
+    #[test]
+    fn unknown_names_have_no_entry() {
+        assert_eq!(entry("bogus"), None);
+        assert_eq!(entry("Int"), None);
+    }
+}
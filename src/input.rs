@@ -0,0 +1,102 @@
+use std::io::{self, BufRead, Stdin, Write};
+use std::rc::Rc;
+
+/// A source of input lines for `gets` and the REPL.
+pub trait Input {
+    /// Reads the next line without its line ending, or `None` once there is
+    /// nothing left to read.
+    fn read_line(&mut self) -> io::Result<Option<String>>;
+
+    /// Shows `prompt` before the next [`read_line`](Input::read_line).
+    ///
+    /// The default writes it to `out`; sources that draw their own prompt can
+    /// override this.
+    fn prompt(&mut self, prompt: &str, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "{}", prompt)?;
+        out.flush()
+    }
+
+    /// Hears every name a program could use so far, sorted, before each
+    /// [`prompt`](Input::prompt). The default ignores them; sources that
+    /// complete names can offer these.
+    fn offer_names(&mut self, names: Vec<Rc<str>>) {
+        let _ = names;
+    }
+}
+
+/// Reads lines from anything implementing [`BufRead`], such as a locked
+/// stdin or a byte string.
+pub struct LineReader<R> {
+    reader: R,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(reader: R) -> LineReader<R> {
+        LineReader { reader }
+    }
+}
+
+impl<R: BufRead> Input for LineReader<R> {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        let read = self.reader.read_line(&mut line)?;
+        Ok(finish_line(line, read))
+    }
+}
+
+/// Reads from the process's stdin, locking it only for each line.
+impl Input for Stdin {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        let read = Stdin::read_line(self, &mut line)?;
+        Ok(finish_line(line, read))
+    }
+}
+
+/// Strips the line ending from a line that took `read` bytes to read.
+fn finish_line(mut line: String, read: usize) -> Option<String> {
+    if read == 0 {
+        return None;
+    }
+
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Some(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_lines_without_their_endings() {
+        let mut input = LineReader::new("one\ntwo\r\nthree".as_bytes());
+
+        assert_eq!(input.read_line().unwrap(), Some(String::from("one")));
+        assert_eq!(input.read_line().unwrap(), Some(String::from("two")));
+        assert_eq!(input.read_line().unwrap(), Some(String::from("three")));
+        assert_eq!(input.read_line().unwrap(), None);
+    }
+
+    #[test]
+    fn empty_lines_are_not_the_end_of_input() {
+        let mut input = LineReader::new("\n".as_bytes());
+
+        assert_eq!(input.read_line().unwrap(), Some(String::new()));
+        assert_eq!(input.read_line().unwrap(), None);
+    }
+
+    #[test]
+    fn prompt_is_written_to_the_output() {
+        let mut input = LineReader::new(io::empty());
+        let mut out = Vec::new();
+
+        input.prompt("> ", &mut out).unwrap();
+
+        assert_eq!(out, b"> ");
+    }
+}
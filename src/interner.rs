@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A name stored once in an [`Interner`], cheap to copy, hash and compare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+/// Gives every distinct name one [`Symbol`] and one shared copy of its text.
+///
+/// The [`Runner`](crate::runner::Runner) interns the names in a program as it
+/// receives it, so looking up a word or variable never has to hash its text
+/// again, and every use of a name shares one string.
+#[derive(Debug, Default)]
+pub struct Interner {
+    names: Vec<Rc<str>>,
+    symbols: HashMap<Rc<str>, Symbol>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// The symbol for `name`, giving it a new one if it hasn't been seen.
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(name) {
+            return symbol;
+        }
+
+        let symbol = Symbol(self.names.len() as u32);
+        let name: Rc<str> = Rc::from(name);
+        self.names.push(Rc::clone(&name));
+        self.symbols.insert(name, symbol);
+        symbol
+    }
+
+    /// The symbol for `name`, if it has been interned.
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).copied()
+    }
+
+    /// The text of `symbol`, shared with every token using it.
+    pub fn name(&self, symbol: Symbol) -> &Rc<str> {
+        &self.names[symbol.0 as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_name_gets_the_same_symbol() {
+        let mut interner = Interner::new();
+        let first = interner.intern("square");
+        let other = interner.intern("cube");
+
+        assert_eq!(interner.intern("square"), first);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn symbols_lead_back_to_their_name() {
+        let mut interner = Interner::new();
+        let symbol = interner.intern("square");
+
+        assert_eq!(&**interner.name(symbol), "square");
+        assert!(Rc::ptr_eq(interner.name(symbol), interner.name(symbol)));
+    }
+
+    #[test]
+    fn get_does_not_intern() {
+        let mut interner = Interner::new();
+
+        assert_eq!(interner.get("x"), None);
+        let symbol = interner.intern("x");
+        assert_eq!(interner.get("x"), Some(symbol));
+    }
+}
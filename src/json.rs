@@ -0,0 +1,129 @@
+//! Machine-readable forms of a program for editor tooling, behind the `serde`
+//! feature.
+//!
+//! Every document is a JSON object with a `version` field, currently
+//! [`SCHEMA_VERSION`], next to either the `tokens` the lexer produced or the
+//! `ast` the parser built from them. Tokens have a `type`, a `value`, the
+//! `items` of a list and a `position`; nodes are tagged with a `kind` of
+//! `Token` or `Block`, and blocks have a `position` too. Positions hold the
+//! `start` and `end` character offsets and the 1-based `line` and `column`
+//! of the start. Any change to this layout bumps the version.
+
+use crate::error::ProgLangError;
+use crate::parser::Node;
+use crate::token::Token;
+use serde::{Deserialize, Serialize};
+
+/// The version of the document layout described above.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// The tokens of a program.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TokensDocument {
+    pub version: u32,
+    pub tokens: Vec<Token>,
+}
+
+/// The parsed nodes of a program.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AstDocument {
+    pub version: u32,
+    pub ast: Vec<Node>,
+}
+
+/// Lexes `src` and describes its tokens as a JSON document.
+pub fn tokens_json(src: &str) -> Result<String, ProgLangError> {
+    let tokens = crate::lex_source(src)?;
+    Ok(to_json(&TokensDocument {
+        version: SCHEMA_VERSION,
+        tokens,
+    }))
+}
+
+/// Lexes and parses `src` and describes its nodes as a JSON document.
+pub fn ast_json(src: &str) -> Result<String, ProgLangError> {
+    let ast = crate::parse_source(src)?;
+    Ok(to_json(&AstDocument {
+        version: SCHEMA_VERSION,
+        ast,
+    }))
+}
+
+fn to_json(document: &impl Serialize) -> String {
+    // Documents hold only strings, numbers and lists, which always serialize.
+    serde_json::to_string_pretty(document).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn tokens_round_trip() {
+        let json = tokens_json("12 \"hi\" +\nputs").unwrap();
+        let document: TokensDocument = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(document.version, SCHEMA_VERSION);
+        assert_eq!(
+            document.tokens,
+            crate::lex_source("12 \"hi\" +\nputs").unwrap()
+        );
+    }
+
+    #[test]
+    fn ast_round_trips() {
+        let source = "def f n { n { 1 } } 2 f";
+        let document: AstDocument = serde_json::from_str(&ast_json(source).unwrap()).unwrap();
+
+        assert_eq!(document.ast, crate::parse_source(source).unwrap());
+    }
+
+    #[test]
+    fn documents_record_positions() {
+        let json = tokens_json("1\n  puts").unwrap();
+
+        assert!(json.contains("\"version\": 2"));
+        assert!(json.contains("\"line\": 2"));
+        assert!(json.contains("\"column\": 3"));
+    }
+
+    #[test]
+    fn tokens_have_a_type_value_and_position() {
+        let json = tokens_json("puts").unwrap();
+
+        assert!(json.contains("\"type\": \"Keyword\""));
+        assert!(json.contains("\"value\": \"puts\""));
+        assert!(json.contains("\"position\": {"));
+        assert!(!json.contains("token_type"));
+        assert!(!json.contains("\"span\""));
+    }
+
+    #[test]
+    fn nodes_are_tagged_with_their_kind() {
+        let json = ast_json("{ 1 }").unwrap();
+
+        assert!(json.contains("\"kind\": \"Block\""));
+        assert!(json.contains("\"kind\": \"Token\""));
+    }
+
+    #[test]
+    fn lists_round_trip_with_their_items() {
+        let list = Token::list(vec![Token::new(crate::TokenType::Int, "1")]);
+        let json = serde_json::to_string(&list).unwrap();
+
+        assert_eq!(serde_json::from_str::<Token>(&json).unwrap(), list);
+    }
+
+    #[test]
+    fn errors_are_reported_by_stage() {
+        assert_eq!(
+            tokens_json("1.2.3").unwrap_err().kind(),
+            ErrorKind::IllegalChar
+        );
+        assert!(matches!(
+            ast_json("{").unwrap_err(),
+            ProgLangError::ParseError(_)
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+use strum_macros::{Display, EnumIter, EnumString};
+
+/// The keywords built into the language.
+///
+/// Each one is written in lowercase in source, e.g. `Keyword::Puts` is
+/// `puts`, with an underscore between words, e.g. `trim_start`. Words and variables can't reuse these names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Keyword {
+    // Output and input
+    Puts,
+    Gets,
+    Slurp,
+    Spit,
+    Append,
+    #[strum(serialize = "read_file")]
+    ReadFile,
+    #[strum(serialize = "write_file")]
+    WriteFile,
+    #[strum(serialize = "append_file")]
+    AppendFile,
+    // System
+    Now,
+    Time,
+    Getenv,
+    Env,
+    Args,
+    Exec,
+    #[strum(serialize = "last_status")]
+    LastStatus,
+    // Arithmetic
+    Fdiv,
+    Divisible,
+    Gcd,
+    Lcm,
+    #[strum(serialize = "is_prime")]
+    IsPrime,
+    Fact,
+    Floor,
+    Ceil,
+    Round,
+    Sign,
+    Abs,
+    Clamp,
+    Sin,
+    Cos,
+    Tan,
+    Ln,
+    Log10,
+    Exp,
+    Pi,
+    E,
+    Rand,
+    Randint,
+    Seed,
+    // Values
+    True,
+    False,
+    Collect,
+    Pick,
+    Roll,
+    Dupn,
+    #[strum(serialize = "empty?")]
+    Empty,
+    Len,
+    Count,
+    Map,
+    Filter,
+    Repeat,
+    Trim,
+    #[strum(serialize = "trim_start")]
+    TrimStart,
+    #[strum(serialize = "trim_end")]
+    TrimEnd,
+    Contains,
+    #[strum(serialize = "starts_with")]
+    StartsWith,
+    #[strum(serialize = "ends_with")]
+    EndsWith,
+    Replace,
+    Indexof,
+    Format,
+    Sort,
+    Unique,
+    Sum,
+    Product,
+    Select,
+    If,
+    Else,
+    Match,
+    // Definitions
+    Def,
+    Let,
+    Fn,
+    Call,
+    Include,
+    Eval,
+    // Errors
+    Throw,
+    Raise,
+    Try,
+    Catch,
+    End,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn keywords_are_written_in_lowercase() {
+        assert_eq!(Keyword::from_str("puts"), Ok(Keyword::Puts));
+        assert_eq!(Keyword::Getenv.to_string(), "getenv");
+        assert!(Keyword::from_str("Puts").is_err());
+    }
+
+    #[test]
+    fn keywords_can_keep_underscores() {
+        assert_eq!(Keyword::from_str("trim_start"), Ok(Keyword::TrimStart));
+        assert_eq!(Keyword::TrimEnd.to_string(), "trim_end");
+    }
+
+    #[test]
+    fn every_keyword_round_trips_through_its_name() {
+        for keyword in Keyword::iter() {
+            assert_eq!(Keyword::from_str(&keyword.to_string()), Ok(keyword));
+        }
+    }
+}
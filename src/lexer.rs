@@ -0,0 +1,607 @@
+use crate::config::Config;
+use crate::error::{Error, ErrorKind};
+use crate::span::Span;
+use crate::token::{Token, TokenType};
+
+/// What [`Lexer::tokenize_incremental`] made of its input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexStatus {
+    /// All of the input was lexed.
+    Complete(Vec<Token>),
+    /// The input stopped partway through a token, e.g. inside a string, so
+    /// reading more of it could finish the token. The error is what to report
+    /// if no more input comes.
+    Incomplete(Error),
+    /// The input has a mistake that no further input can fix.
+    Invalid(Error),
+}
+
+/// Turns source text into a list of [`Token`]s.
+pub struct Lexer {
+    src: Vec<char>,
+    current_position: usize,
+    current_character: char,
+    line: usize,
+    column: usize,
+    big_ints: bool,
+}
+
+impl Lexer {
+    /// Lexes `source` with the [default](Config::default) settings.
+    pub fn new(source: String) -> Lexer {
+        Lexer::from_config(source, &Config::default())
+    }
+
+    /// Lexes `source` the way a runner made from `config` expects, e.g.
+    /// accepting int literals of any size if it has big ints.
+    pub fn from_config(source: String, config: &Config) -> Lexer {
+        let src: Vec<char> = source.chars().collect();
+        let first_character = src.first().copied().unwrap_or('\0');
+        Lexer {
+            src,
+            current_position: 0,
+            current_character: first_character,
+            line: 1,
+            column: 1,
+            big_ints: config.big_ints,
+        }
+    }
+
+    /// Numbers lines from `line` instead of 1, for source that continues
+    /// earlier input.
+    pub fn starting_at_line(mut self, line: usize) -> Lexer {
+        self.line = line;
+        self
+    }
+
+    /// Accepts int literals too large for an `i64`, for a runner made
+    /// [`with_big_ints`](crate::runner::Runner::with_big_ints).
+    pub fn with_big_ints(mut self) -> Lexer {
+        self.big_ints = true;
+        self
+    }
+
+    /// Lexes the whole source into tokens.
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, Error> {
+        let mut tokens: Vec<Token> = Vec::new();
+        while self.current_character != '\0' {
+            if self.at_number() {
+                tokens.push(self.match_number()?);
+            }
+            if self.current_character == '.' {
+                return Err(Error::new(
+                    ErrorKind::IllegalChar,
+                    "A dot must be followed by digits, as in .5",
+                    self.span(),
+                ));
+            }
+            if self.current_character == '+' {
+                tokens.push(Token::new(TokenType::Plus, "").at(self.span()));
+            }
+            if self.current_character == '*' {
+                tokens.push(Token::new(TokenType::Multiply, "").at(self.span()));
+            }
+            if self.current_character == '"' {
+                tokens.push(self.match_string()?);
+            }
+            if self.at_comparison() {
+                tokens.push(self.match_comparison());
+            }
+            if self.current_character == '{' {
+                tokens.push(Token::new(TokenType::LeftBrace, "").at(self.span()));
+            }
+            if self.current_character == '}' {
+                tokens.push(Token::new(TokenType::RightBrace, "").at(self.span()));
+            }
+            if (self.current_character != '+'
+                && self.current_character != '*'
+                && self.current_character != '"'
+                && self.current_character != '<'
+                && self.current_character != '>'
+                && self.current_character != '='
+                && self.current_character != '{'
+                && self.current_character != '}'
+                && self.current_character != '\0'
+                && !self.current_character.is_ascii_digit())
+                && !self.current_character.is_whitespace()
+            {
+                tokens.push(self.match_keyword());
+            }
+            self.advance();
+        }
+
+        Ok(tokens)
+    }
+
+    /// Lexes the whole source like [`tokenize`](Lexer::tokenize), but tells
+    /// input that is merely unfinished apart from input that is wrong, for
+    /// callers that can read more before giving up.
+    pub fn tokenize_incremental(&mut self) -> LexStatus {
+        match self.tokenize() {
+            Ok(tokens) => LexStatus::Complete(tokens),
+            Err(error) if error.kind == ErrorKind::UnterminatedString => {
+                LexStatus::Incomplete(error)
+            }
+            Err(error) => LexStatus::Invalid(error),
+        }
+    }
+
+    fn peek(&mut self, offset: usize) -> char {
+        self.src
+            .get(self.current_position + offset)
+            .copied()
+            .unwrap_or('\0')
+    }
+
+    fn advance(&mut self) {
+        if self.current_character == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        self.current_position += 1;
+        self.current_character = self.src.get(self.current_position).copied().unwrap_or('\0');
+    }
+
+    /// The span of the current character.
+    fn span(&self) -> Span {
+        Span::new(
+            self.current_position,
+            self.current_position + 1,
+            self.line,
+            self.column,
+        )
+    }
+
+    /// Extends `start` up to and including the current character.
+    fn span_from(&self, start: Span) -> Span {
+        Span {
+            end: self.current_position + 1,
+            ..start
+        }
+    }
+
+    /// Whether a number starts here: a digit, or a dot or `-` right before
+    /// one. A `-` on its own or before anything else is part of a keyword.
+    fn at_number(&mut self) -> bool {
+        match (self.current_character, self.peek(1)) {
+            ('-', '.') => self.peek(2).is_ascii_digit(),
+            ('-' | '.', next) => next.is_ascii_digit(),
+            (c, _) => c.is_ascii_digit(),
+        }
+    }
+
+    /// Lexes an int or a float, either of which may start with a `-`. A float
+    /// may start or end with its dot, in which case it gets a zero on that
+    /// side: `.5` is `0.5` and `5.` is `5.0`.
+    fn match_number(&mut self) -> Result<Token, Error> {
+        let start = self.span();
+        let mut number: String = String::new();
+
+        if self.current_character == '-' {
+            number.push('-');
+            self.advance();
+        }
+        let mut has_dot: bool = self.current_character == '.';
+        if has_dot {
+            number.push('0');
+        }
+        number.push(self.current_character);
+
+        while self.peek(1).is_ascii_digit() || self.peek(1) == '.' {
+            self.advance();
+            if has_dot && self.current_character == '.' {
+                return Err(Error::new(
+                    ErrorKind::IllegalChar,
+                    "Found an extra dot",
+                    self.span(),
+                ));
+            } else if self.current_character == '.' {
+                has_dot = true;
+            }
+            number.push(self.current_character);
+        }
+
+        let span = self.span_from(start);
+        self.advance();
+
+        if has_dot {
+            if number.ends_with('.') {
+                number.push('0');
+            }
+            return Ok(Token::new(TokenType::Float, number).at(span));
+        }
+        if !self.big_ints && number.parse::<i64>().is_err() {
+            return Err(Error::new(
+                ErrorKind::Overflow,
+                "Integer literal is too large",
+                span,
+            ));
+        }
+        Ok(Token::new(TokenType::Int, number).at(span))
+    }
+
+    fn match_string(&mut self) -> Result<Token, Error> {
+        let start = self.span();
+        let mut string = String::new();
+
+        while self.peek(1) != '"' && self.peek(1) != '\0' {
+            self.advance();
+            string.push(self.current_character);
+        }
+
+        self.advance();
+
+        if self.current_character != '"' {
+            return Err(Error::new(
+                ErrorKind::UnterminatedString,
+                "Missing the closing quote of a string",
+                start,
+            ));
+        }
+
+        Ok(Token::new(TokenType::String, string).at(self.span_from(start)))
+    }
+
+    /// Whether a comparison or shift operator starts here. A `!` only starts
+    /// one as part of `!=`.
+    fn at_comparison(&mut self) -> bool {
+        match self.current_character {
+            '<' | '>' | '=' => true,
+            '!' => self.peek(1) == '=',
+            _ => false,
+        }
+    }
+
+    /// Lexes a one- or two-character operator, leaving the lexer on its last
+    /// character.
+    fn match_comparison(&mut self) -> Token {
+        let start = self.span();
+        let two_char = match (self.current_character, self.peek(1)) {
+            ('=', '=') => Some(TokenType::EqualEqual),
+            ('!', '=') => Some(TokenType::NotEqual),
+            ('<', '=') => Some(TokenType::LessEqual),
+            ('>', '=') => Some(TokenType::GreaterEqual),
+            ('<', '<') => Some(TokenType::ShiftLeft),
+            ('>', '>') => Some(TokenType::ShiftRight),
+            _ => None,
+        };
+
+        let t_type = match two_char {
+            Some(t_type) => {
+                self.advance();
+                t_type
+            }
+            None => match self.current_character {
+                '<' => TokenType::Less,
+                '>' => TokenType::Greater,
+                _ => TokenType::Equal,
+            },
+        };
+
+        Token::new(t_type, "").at(self.span_from(start))
+    }
+
+    fn match_keyword(&mut self) -> Token {
+        let start = self.span();
+        let mut keyword: String = String::from(self.current_character);
+
+        while !self.peek(1).is_whitespace() && !['\0', '{', '}'].contains(&self.peek(1)) {
+            self.advance();
+            keyword.push(self.current_character);
+        }
+
+        Token::new(TokenType::Keyword, keyword).at(self.span_from(start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(source: &str) -> Vec<Token> {
+        Lexer::new(String::from(source)).tokenize().unwrap()
+    }
+
+    /// A token on the first line, covering the characters `start..end`.
+    fn token(t_type: TokenType, value: &str, start: usize, end: usize) -> Token {
+        Token::new(t_type, value).at(Span::new(start, end, 1, start + 1))
+    }
+
+    #[test]
+    fn lexes_integers_and_plus() {
+        assert_eq!(
+            lex("12 13 +"),
+            vec![
+                token(TokenType::Int, "12", 0, 2),
+                token(TokenType::Int, "13", 3, 5),
+                token(TokenType::Plus, "", 6, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_multiply_as_a_single_token() {
+        assert_eq!(
+            lex("2 3 *"),
+            vec![
+                token(TokenType::Int, "2", 0, 1),
+                token(TokenType::Int, "3", 2, 3),
+                token(TokenType::Multiply, "", 4, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_floats() {
+        assert_eq!(lex("1.5"), vec![token(TokenType::Float, "1.5", 0, 3)]);
+    }
+
+    #[test]
+    fn lexes_strings_and_keywords() {
+        assert_eq!(
+            lex("\"Hello, world!\" puts"),
+            vec![
+                token(TokenType::String, "Hello, world!", 0, 15),
+                token(TokenType::Keyword, "puts", 16, 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_single_character_comparisons() {
+        assert_eq!(
+            lex("< > ="),
+            vec![
+                token(TokenType::Less, "", 0, 1),
+                token(TokenType::Greater, "", 2, 3),
+                token(TokenType::Equal, "", 4, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_two_character_operators_spanning_both_characters() {
+        assert_eq!(
+            lex("== != <= >= << >>"),
+            vec![
+                token(TokenType::EqualEqual, "", 0, 2),
+                token(TokenType::NotEqual, "", 3, 5),
+                token(TokenType::LessEqual, "", 6, 8),
+                token(TokenType::GreaterEqual, "", 9, 11),
+                token(TokenType::ShiftLeft, "", 12, 14),
+                token(TokenType::ShiftRight, "", 15, 17),
+            ]
+        );
+    }
+
+    #[test]
+    fn operators_need_no_spaces_around_them() {
+        assert_eq!(
+            lex("1<2 3>=4"),
+            vec![
+                token(TokenType::Int, "1", 0, 1),
+                token(TokenType::Less, "", 1, 2),
+                token(TokenType::Int, "2", 2, 3),
+                token(TokenType::Int, "3", 4, 5),
+                token(TokenType::GreaterEqual, "", 5, 7),
+                token(TokenType::Int, "4", 7, 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_lone_bang_is_a_keyword() {
+        assert_eq!(
+            lex("! !="),
+            vec![
+                token(TokenType::Keyword, "!", 0, 1),
+                token(TokenType::NotEqual, "", 2, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn braces_are_tokens_of_their_own() {
+        assert_eq!(
+            lex("def sq{x}"),
+            vec![
+                token(TokenType::Keyword, "def", 0, 3),
+                token(TokenType::Keyword, "sq", 4, 6),
+                token(TokenType::LeftBrace, "", 6, 7),
+                token(TokenType::Keyword, "x", 7, 8),
+                token(TokenType::RightBrace, "", 8, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_empty_source() {
+        assert_eq!(lex(""), vec![]);
+    }
+
+    #[test]
+    fn records_spans_across_lines() {
+        assert_eq!(
+            lex("12 +\n  puts"),
+            vec![
+                token(TokenType::Int, "12", 0, 2),
+                token(TokenType::Plus, "", 3, 4),
+                Token::new(TokenType::Keyword, "puts").at(Span::new(7, 11, 2, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_lines_from_the_starting_line() {
+        let tokens = Lexer::new(String::from("1\n2"))
+            .starting_at_line(5)
+            .tokenize()
+            .unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenType::Int, "1").at(Span::new(0, 1, 5, 1)),
+                Token::new(TokenType::Int, "2").at(Span::new(2, 3, 6, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn extra_dot_is_an_error_at_the_dot() {
+        let error = Lexer::new(String::from("1.2.3")).tokenize().unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::IllegalChar);
+        assert_eq!(error.span, Span::new(3, 4, 1, 4));
+    }
+
+    #[test]
+    fn integer_literals_must_fit_in_an_int() {
+        let error = Lexer::new(String::from("1 99999999999999999999999"))
+            .tokenize()
+            .unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Overflow);
+        assert_eq!(error.span, Span::new(2, 25, 1, 3));
+    }
+
+    #[test]
+    fn big_ints_allow_integer_literals_of_any_size() {
+        let tokens = Lexer::new(String::from("99999999999999999999999"))
+            .with_big_ints()
+            .tokenize()
+            .unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![token(TokenType::Int, "99999999999999999999999", 0, 23)]
+        );
+    }
+
+    #[test]
+    fn config_can_turn_on_big_ints() {
+        let big = Config {
+            big_ints: true,
+            ..Config::default()
+        };
+        let source = String::from("99999999999999999999999");
+
+        assert!(Lexer::from_config(source.clone(), &big).tokenize().is_ok());
+        assert!(Lexer::from_config(source, &Config::default())
+            .tokenize()
+            .is_err());
+    }
+
+    #[test]
+    fn only_ascii_digits_start_numbers() {
+        assert_eq!(lex("٣"), vec![token(TokenType::Keyword, "٣", 0, 1)]);
+    }
+
+    #[test]
+    fn leading_dot_floats_get_a_zero() {
+        assert_eq!(
+            lex(".5 1 .25"),
+            vec![
+                token(TokenType::Float, "0.5", 0, 2),
+                token(TokenType::Int, "1", 3, 4),
+                token(TokenType::Float, "0.25", 5, 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_dot_floats_get_a_zero() {
+        assert_eq!(
+            lex("5. 5.0"),
+            vec![
+                token(TokenType::Float, "5.0", 0, 2),
+                token(TokenType::Float, "5.0", 3, 6)
+            ]
+        );
+    }
+
+    #[test]
+    fn a_minus_before_digits_makes_a_negative_number() {
+        assert_eq!(
+            lex("-3 -2.5 -.5"),
+            vec![
+                token(TokenType::Int, "-3", 0, 2),
+                token(TokenType::Float, "-2.5", 3, 7),
+                token(TokenType::Float, "-0.5", 8, 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_minus_before_anything_else_is_a_keyword() {
+        assert_eq!(
+            lex("- -x"),
+            vec![
+                token(TokenType::Keyword, "-", 0, 1),
+                token(TokenType::Keyword, "-x", 2, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn negative_integer_literals_must_fit_in_an_int() {
+        assert!(Lexer::new(String::from("-9223372036854775808"))
+            .tokenize()
+            .is_ok());
+        let error = Lexer::new(String::from("-9223372036854775809"))
+            .tokenize()
+            .unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Overflow);
+    }
+
+    #[test]
+    fn a_lone_dot_is_an_error() {
+        for source in [".", "1 . 2", ".x"] {
+            let error = Lexer::new(String::from(source)).tokenize().unwrap_err();
+
+            assert_eq!(error.kind, ErrorKind::IllegalChar, "{}", source);
+        }
+    }
+
+    fn status_of(source: &str) -> LexStatus {
+        Lexer::new(String::from(source)).tokenize_incremental()
+    }
+
+    #[test]
+    fn finished_input_is_complete() {
+        assert_eq!(
+            status_of("\"hi\" puts"),
+            LexStatus::Complete(vec![
+                token(TokenType::String, "hi", 0, 4),
+                token(TokenType::Keyword, "puts", 5, 9),
+            ])
+        );
+    }
+
+    #[test]
+    fn an_open_string_is_incomplete() {
+        match status_of("\"first line") {
+            LexStatus::Incomplete(error) => {
+                assert_eq!(error.kind, ErrorKind::UnterminatedString)
+            }
+            status => panic!("expected incomplete input, found {:?}", status),
+        }
+    }
+
+    #[test]
+    fn a_stray_character_is_invalid_however_much_follows() {
+        assert!(matches!(status_of("1 . 2"), LexStatus::Invalid(_)));
+        assert!(matches!(status_of("1.2.3 \"open"), LexStatus::Invalid(_)));
+    }
+
+    #[test]
+    fn unterminated_string_is_an_error_at_the_quote() {
+        let error = Lexer::new(String::from("1 \"abc")).tokenize().unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::UnterminatedString);
+        assert_eq!(error.span, Span::new(2, 3, 1, 3));
+    }
+}
@@ -0,0 +1,205 @@
+//! A small stack-based programming language inspired by Forth.
+//!
+//! Source text is turned into [`Token`]s by the [`Lexer`] and evaluated by the
+//! [`Runner`]. For most uses [`run_source`] does both in one call:
+//!
+//! ```
+//! # fn main() -> Result<(), proglang::ProgLangError> {
+//! assert_eq!(proglang::run_source("1 2 + puts")?, "3\n");
+//! # Ok(())
+//! # }
+//! ```
+
+// Warning silencing
+#![allow(non_snake_case)]
+
+pub mod arity;
+mod bignum;
+pub mod check;
+pub mod color;
+pub mod config;
+mod debugger;
+pub mod error;
+pub mod eval;
+mod help;
+pub mod input;
+pub mod interner;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod keyword;
+pub mod lexer;
+pub mod limits;
+#[cfg(all(feature = "readline", not(target_arch = "wasm32")))]
+pub mod line_editor;
+mod output;
+pub mod parser;
+mod random;
+pub mod repl;
+pub mod report;
+pub mod runner;
+pub mod span;
+mod template;
+pub mod timing;
+pub mod token;
+pub mod trace;
+pub mod warning;
+
+pub use color::ColorChoice;
+pub use config::Config;
+pub use error::{Error, ErrorKind, ProgLangError};
+pub use eval::{eval, EvalResult};
+pub use input::{Input, LineReader};
+pub use interner::{Interner, Symbol};
+pub use keyword::Keyword;
+pub use lexer::{LexStatus, Lexer};
+pub use limits::ExecutionLimits;
+#[cfg(all(feature = "readline", not(target_arch = "wasm32")))]
+pub use line_editor::LineEditor;
+pub use parser::Node;
+pub use repl::Repl;
+pub use runner::{Runner, Word};
+pub use span::Span;
+pub use timing::Timings;
+pub use token::{Token, TokenType};
+pub use trace::TraceLevel;
+pub use warning::{Warning, WarningKind};
+
+use output::SharedBuffer;
+use std::io::{self, Write};
+
+/// Lexes and runs `src`, writing anything it prints to `out` and reporting
+/// any error that stops it to `err`, rendered with [`report::render`].
+///
+/// Output written before an error is raised stays written. The error is also
+/// returned so the caller can decide what to do next.
+pub fn run(src: &str, out: Box<dyn Write>, err: Box<dyn Write>) -> Result<(), ProgLangError> {
+    run_on(src, Runner::new().with_output(out), err)
+}
+
+/// Like [`run`], but on a runner set up by the caller, e.g. one given its own
+/// [input](Runner::with_input).
+pub fn run_on(src: &str, mut runner: Runner, mut err: Box<dyn Write>) -> Result<(), ProgLangError> {
+    let result = lex_and_run(Lexer::new(String::from(src)), &mut runner);
+
+    if let Err(error) = &result {
+        let _ = writeln!(err, "{}", report::render(error.error(), src));
+    }
+    result
+}
+
+/// Lexes `src` into tokens.
+pub fn lex_source(src: &str) -> Result<Vec<Token>, ProgLangError> {
+    Lexer::new(String::from(src))
+        .tokenize()
+        .map_err(ProgLangError::LexError)
+}
+
+/// Lexes `src` and parses the tokens into [`Node`]s, without running anything.
+pub fn parse_source(src: &str) -> Result<Vec<Node>, ProgLangError> {
+    parser::parse(&lex_source(src)?).map_err(ProgLangError::ParseError)
+}
+
+/// Lexes the source of `lexer` and runs it on `runner`, tagging any error with
+/// the stage that raised it. Int literals may be of any size if the runner has
+/// big ints.
+pub(crate) fn lex_and_run(lexer: Lexer, runner: &mut Runner) -> Result<(), ProgLangError> {
+    timing::timed_run(lexer, runner).0
+}
+
+/// A runner that prints to `out` and has nothing for `gets` to read.
+fn detached_runner(out: SharedBuffer) -> Runner {
+    Runner::new()
+        .with_output(Box::new(out))
+        .with_input(Box::new(LineReader::new(io::empty())))
+}
+
+/// Lexes and runs `src`, returning everything it printed.
+///
+/// Nothing is read from stdin (`gets` always sees the end of the input) or
+/// written to stdout, and errors are returned rather than ending the process:
+///
+/// ```
+/// let error = proglang::run_source("1 \"one\" +").unwrap_err();
+///
+/// assert!(matches!(error, proglang::ProgLangError::RuntimeError(_)));
+/// assert_eq!(error.kind(), proglang::ErrorKind::MismatchedTypes);
+/// assert_eq!(error.span().column, 9);
+/// ```
+pub fn run_source(src: &str) -> Result<String, ProgLangError> {
+    let buffer = SharedBuffer::default();
+    run_on(src, detached_runner(buffer.clone()), Box::new(io::sink()))?;
+    Ok(buffer.contents())
+}
+
+/// Runs `src` and returns what a user would see: everything it printed,
+/// followed by the error message if it failed.
+#[cfg(test)]
+pub(crate) fn eval_to_string(src: &str) -> String {
+    let buffer = SharedBuffer::default();
+    let _ = run_on(
+        src,
+        detached_runner(buffer.clone()),
+        Box::new(buffer.clone()),
+    );
+    buffer.contents()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_source_returns_printed_output() {
+        assert_eq!(run_source("\"a\" puts 2 3 * puts").unwrap(), "a\n6\n");
+    }
+
+    #[test]
+    fn run_source_returns_lex_errors() {
+        let error = run_source("1.2.3").unwrap_err();
+
+        assert!(matches!(error, ProgLangError::LexError(_)));
+        assert_eq!(error.kind(), ErrorKind::IllegalChar);
+    }
+
+    #[test]
+    fn run_reports_errors_to_the_error_sink_only() {
+        let out = SharedBuffer::default();
+        let err = SharedBuffer::default();
+
+        let result = run("1 puts bogus", Box::new(out.clone()), Box::new(err.clone()));
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::NameError);
+        assert_eq!(out.contents(), "1\n");
+        assert!(err
+            .contents()
+            .starts_with("Unknown keyword error: No such keyword: bogus\n"));
+    }
+
+    #[test]
+    fn eval_to_string_includes_errors() {
+        assert!(eval_to_string("1.2.3").starts_with("IllegalCharError: Found an extra dot\n"));
+    }
+
+    #[test]
+    fn errors_in_multi_line_programs_point_at_their_line() {
+        let src = "\"total:\" puts\n1 2 + puts\n\t3 \"x\" *\n\"unreachable\" puts\n";
+
+        assert_eq!(
+            eval_to_string(src),
+            concat!(
+                "total:\n",
+                "3\n",
+                "Mismatched types: Cannot multiply on 2 values of different types\n",
+                " --> line 3, column 8\n",
+                "  |\n",
+                "3 | \t3 \"x\" *\n",
+                "  | \t      ^\n",
+            )
+        );
+    }
+
+    #[test]
+    fn run_source_of_nothing_prints_nothing() {
+        assert_eq!(run_source("").unwrap(), "");
+    }
+}
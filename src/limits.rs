@@ -0,0 +1,31 @@
+/// Bounds on how much one [`Runner`](crate::runner::Runner) may do, for
+/// running programs that can't be trusted. Every limit is off by default.
+///
+/// Going past any of them stops the run with an
+/// [`ErrorKind::LimitExceeded`](crate::error::ErrorKind::LimitExceeded) error
+/// at the token responsible; whatever the program did before that stays done.
+///
+/// ```
+/// use proglang::{ExecutionLimits, Runner};
+///
+/// let runner = Runner::new().with_limits(ExecutionLimits {
+///     max_steps: Some(10_000),
+///     max_value_size: Some(1024),
+///     ..ExecutionLimits::default()
+/// });
+/// # let _ = runner;
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    /// How many tokens a single [`run`](crate::runner::Runner::run) may
+    /// execute, counting every token of every word it calls.
+    pub max_steps: Option<usize>,
+    /// How many values the stack may hold at once.
+    pub max_stack_depth: Option<usize>,
+    /// How deeply words may call each other. Calls never nest more than 250
+    /// deep whatever this says, to keep the interpreter's own stack safe.
+    pub max_call_depth: Option<usize>,
+    /// How many bytes any one value may take to write out, e.g. the text of a
+    /// string or the printed form of a list.
+    pub max_value_size: Option<usize>,
+}
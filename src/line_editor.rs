@@ -0,0 +1,172 @@
+use crate::input::Input;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Reads lines from a terminal with line editing: arrow keys move around the
+/// line and through earlier lines, and the usual shortcuts like Ctrl-A and
+/// Ctrl-E work.
+///
+/// Lines typed at a prompt are remembered, and kept in a history file across
+/// sessions if [given one](LineEditor::with_history_file). Lines read for
+/// `gets`, which has no prompt, aren't. Ctrl-C throws away the line being
+/// typed rather than ending the process.
+///
+/// Tab completes the name being typed from those the runner
+/// [offers](Input::offer_names): keywords, registered functions, and the
+/// words and variables defined so far.
+pub struct LineEditor {
+    editor: Editor<NameCompleter, DefaultHistory>,
+    prompt: String,
+    history_file: Option<PathBuf>,
+}
+
+impl LineEditor {
+    pub fn new() -> io::Result<LineEditor> {
+        let mut editor = Editor::new().map_err(io::Error::other)?;
+        editor.set_helper(Some(NameCompleter::default()));
+        Ok(LineEditor {
+            editor,
+            prompt: String::new(),
+            history_file: None,
+        })
+    }
+
+    /// Loads history from the file at `path`, if there is one, and saves each
+    /// new line to it.
+    pub fn with_history_file(mut self, path: impl Into<PathBuf>) -> LineEditor {
+        let path = path.into();
+        // A missing or unreadable file just means starting afresh.
+        let _ = self.editor.load_history(&path);
+        self.history_file = Some(path);
+        self
+    }
+}
+
+impl Input for LineEditor {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let prompt = std::mem::take(&mut self.prompt);
+        let line = match self.editor.readline(&prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => return Ok(Some(String::new())),
+            Err(ReadlineError::Eof) => return Ok(None),
+            Err(e) => return Err(io::Error::other(e)),
+        };
+
+        if !prompt.is_empty() && !line.trim().is_empty() {
+            let _ = self.editor.add_history_entry(line.as_str());
+            if let Some(path) = &self.history_file {
+                // Saved every line, as the process may exit without warning.
+                let _ = self.editor.save_history(path);
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// Keeps `prompt` for the editor to draw, so editing never erases it.
+    fn prompt(&mut self, prompt: &str, out: &mut dyn Write) -> io::Result<()> {
+        self.prompt = String::from(prompt);
+        out.flush()
+    }
+
+    fn offer_names(&mut self, names: Vec<Rc<str>>) {
+        if let Some(completer) = self.editor.helper_mut() {
+            completer.names = names;
+        }
+    }
+}
+
+/// Completes names from the last ones offered, unless the cursor is inside a
+/// string.
+#[derive(Default)]
+struct NameCompleter {
+    names: Vec<Rc<str>>,
+}
+
+impl Completer for NameCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let before = &line[..pos];
+        // Strings can't contain quotes, so an odd number of them means one is
+        // still open.
+        if before.matches('"').count() % 2 == 1 {
+            return Ok((pos, Vec::new()));
+        }
+
+        let start = before
+            .rfind(|c: char| c.is_whitespace() || c == '{' || c == '}')
+            .map_or(0, |i| i + 1);
+        let typed = &before[start..];
+        if typed.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+        let candidates = self
+            .names
+            .iter()
+            .filter(|name| name.starts_with(typed))
+            .map(|name| String::from(&**name))
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for NameCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for NameCompleter {}
+
+impl Validator for NameCompleter {}
+
+impl Helper for NameCompleter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complete(line: &str) -> (usize, Vec<String>) {
+        let completer = NameCompleter {
+            names: ["puts", "push_all", "replace", "x"].map(Rc::from).to_vec(),
+        };
+        let history = DefaultHistory::new();
+
+        completer
+            .complete(line, line.len(), &Context::new(&history))
+            .unwrap()
+    }
+
+    #[test]
+    fn completes_the_name_before_the_cursor() {
+        assert_eq!(
+            complete("pu"),
+            (0, vec![String::from("puts"), String::from("push_all")])
+        );
+        assert_eq!(complete("1 2 rep"), (4, vec![String::from("replace")]));
+        assert_eq!(complete("def f {x"), (7, vec![String::from("x")]));
+    }
+
+    #[test]
+    fn nothing_is_completed_inside_a_string() {
+        assert_eq!(complete("\"pu"), (3, Vec::new()));
+        assert_eq!(complete("\"a\" pu").1.len(), 2);
+    }
+
+    #[test]
+    fn nothing_is_completed_without_a_start() {
+        assert_eq!(complete("1 "), (2, Vec::new()));
+        assert_eq!(complete("zz"), (0, Vec::new()));
+    }
+}
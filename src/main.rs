@@ -1,311 +1,337 @@
-// Warning silencing
-#![allow(dead_code, non_snake_case)]
+use proglang::runner::MAX_FLOAT_PRECISION;
+use proglang::{check, color, timing, ColorChoice, Config, Lexer, Repl, Runner, TraceLevel};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::process;
 
-use std::collections::VecDeque;
-use std::fmt;
-use std::io::{stdin, stdout, Write};
-use strum_macros::EnumIter;
-
-#[derive(Debug, PartialEq, EnumIter)]
-enum TokenType {
-    // Types
-    Int,
-    Float,
-    String,
-    Keyword,
-    // Arithmetic Operators
-    Plus,
-    Multiply,
-}
-
-impl fmt::Display for TokenType {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
-#[derive(Debug)]
-struct Token {
-    token_type: TokenType,
-    token_value: String,
-}
-
-impl Token {
-    fn new(t_type: TokenType, t_value: String) -> Token {
-        Token {
-            token_type: t_type,
-            token_value: t_value,
-        }
-    }
-
-    fn print(&self) {
-        println!("{:?}:{}", self.token_type, self.token_value);
-    }
-}
-
-struct Error {
-    name: String,
-    description: String,
-}
-
-impl Error {
-    fn new(name: &str, description: &str) -> Error {
-        Error {
-            name: String::from(name),
-            description: String::from(description),
-        }
-    }
-
-    fn throw(&self) {
-        println!("{}: {}", self.name, self.description);
-        std::process::exit(1);
-    }
-}
-
-struct Lexer {
-    src: String,
-    current_position: usize,
-    current_character: char,
-}
-
-impl Lexer {
-    fn new(source: String) -> Lexer {
-        let first_character = source.chars().nth(0).unwrap();
-        Lexer {
-            src: source,
-            current_position: 0,
-            current_character: first_character,
-        }
-    }
-
-    fn tokenize(&mut self) -> Vec<Token> {
-        let mut tokens: Vec<Token> = Vec::new();
-        while self.current_character != '\0' {
-            if self.current_character.is_numeric() {
-                tokens.push(self.match_number());
-            }
-            if self.current_character == '+' {
-                tokens.push(Token {
-                    token_type: TokenType::Plus,
-                    token_value: String::new(),
-                });
+fn main() {
+    let mut show_warnings = true;
+    let mut warnings_as_errors = false;
+    let mut config = Config {
+        prelude: true,
+        ..Config::default()
+    };
+    let mut interactive = false;
+    let mut history = true;
+    let mut color = ColorChoice::Auto;
+    let mut check_only = false;
+    let mut time = false;
+    let mut trace = None;
+    let mut debug = false;
+    let mut quiet = false;
+    let mut file = None;
+    let mut script_args = Vec::new();
+    let mut evals = Vec::new();
+    #[cfg(feature = "serde")]
+    let mut dump = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--no-warnings" => show_warnings = false,
+            "--warnings-as-errors" => warnings_as_errors = true,
+            "--big-ints" => config.big_ints = true,
+            "--no-prelude" => config.prelude = false,
+            "-i" | "--interactive" => interactive = true,
+            "--no-history" => history = false,
+            "--no-color" => color = ColorChoice::Never,
+            "--check" => check_only = true,
+            "--time" => time = true,
+            "--stats" => config.stats = true,
+            "--trace" => trace = Some(TraceLevel::All),
+            "--debug" => debug = true,
+            "--quiet" => quiet = true,
+            "--version" => {
+                println!("{}", version());
+                process::exit(0);
             }
-            if self.current_character == '*' {
-                tokens.push(Token {
-                    token_type: TokenType::Multiply,
-                    token_value: String::new(),
-                });
+            _ if arg.starts_with("--trace=") => match arg["--trace=".len()..].parse() {
+                Ok(level) => trace = Some(level),
+                Err(message) => {
+                    eprintln!("{}", message);
+                    process::exit(2);
+                }
+            },
+            _ if arg.starts_with("--color=") => match arg["--color=".len()..].parse() {
+                Ok(choice) => color = choice,
+                Err(message) => {
+                    eprintln!("{}", message);
+                    process::exit(2);
+                }
+            },
+            "--max-steps" => config.limits.max_steps = Some(limit(&arg, args.next())),
+            "--max-stack" => config.limits.max_stack_depth = Some(limit(&arg, args.next())),
+            "--max-call-depth" => config.limits.max_call_depth = Some(limit(&arg, args.next())),
+            "--max-value-size" => config.limits.max_value_size = Some(limit(&arg, args.next())),
+            "--float-precision" => match limit(&arg, args.next()) {
+                digits if digits <= MAX_FLOAT_PRECISION => config.float_precision = Some(digits),
+                _ => {
+                    eprintln!("{} can be at most {}", arg, MAX_FLOAT_PRECISION);
+                    process::exit(2);
+                }
+            },
+            "-e" | "--eval" => match args.next() {
+                Some(src) => evals.push(src),
+                None => {
+                    eprintln!("{} needs a program after it", arg);
+                    process::exit(2);
+                }
+            },
+            #[cfg(feature = "serde")]
+            "--tokens-json" => dump = Some(proglang::json::tokens_json as Describe),
+            #[cfg(feature = "serde")]
+            "--ast-json" => dump = Some(proglang::json::ast_json as Describe),
+            // Everything after -- is the program file and its arguments,
+            // even if it looks like an option.
+            "--" => {
+                file = args.next();
+                script_args.extend(args.by_ref());
             }
-            if self.current_character == '"' {
-                tokens.push(self.match_string());
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option: {}", arg);
+                process::exit(2);
             }
-            if (self.current_character != '+'
-                && self.current_character != '"'
-                && !self.current_character.is_numeric())
-                && !self.current_character.is_whitespace()
-            {
-                tokens.push(self.match_keyword());
+            // Everything after the file is for the program.
+            _ => {
+                file = Some(arg);
+                script_args.extend(args.by_ref());
             }
-            self.advance();
         }
-
-        return tokens;
     }
 
-    fn peek(&mut self, offset: usize) -> char {
-        return match self
-            .src
-            .chars()
-            .nth((self.current_position + offset) as usize)
-        {
-            Some(character) => character,
-            None => '\0',
-        };
+    if file.is_some() && !evals.is_empty() {
+        eprintln!("-e can't be used with a program file");
+        process::exit(2);
     }
-
-    fn advance(&mut self) {
-        self.current_position += 1;
-        self.current_character = match self.src.chars().nth(self.current_position as usize) {
-            Some(character) => character,
-            None => '\0',
+    // Several -e programs run as one, a line each. Without a program, one
+    // piped in runs as a whole rather than line by line in the REPL.
+    let program = match file {
+        Some(path) => Some(read_file(path)),
+        None if !evals.is_empty() => Some(Program {
+            src: evals.join("\n"),
+            path: None,
+        }),
+        None if !interactive && !io::stdin().is_terminal() => Some(Program {
+            src: read_stdin(),
+            path: None,
+        }),
+        None => None,
+    };
+
+    let colored = color.enabled(io::stderr().is_terminal());
+
+    #[cfg(feature = "serde")]
+    if let Some(describe) = dump {
+        let src = match program {
+            Some(program) => program.src,
+            None => read_stdin(),
         };
+        print_json(describe, &src, colored);
     }
 
-    fn match_number(&mut self) -> Token {
-        let mut has_dot: bool = false;
-        let mut number: String = String::new();
-
-        number += &*self.current_character.to_string();
-
-        while self.peek(1).is_numeric() || self.peek(1) == '.' {
-            self.advance();
-            if has_dot && self.current_character == '.' {
-                Error::new("IllegalCharError", "Found an extra dot").throw()
-            } else if self.current_character == '.' {
-                has_dot = true;
-            }
-            number += &*self.current_character.to_string();
-        }
-
-        self.advance();
+    let mut runner = Runner::from_config(config)
+        .with_args(script_args)
+        .with_process_access(true);
+    if let Some(level) = trace {
+        runner = runner.with_trace(level, Box::new(io::stderr()));
+    }
+    if debug {
+        runner = runner.with_debugger();
+    }
 
-        return if has_dot {
-            Token::new(TokenType::Float, number)
-        } else {
-            Token::new(TokenType::Int, number)
+    if check_only {
+        let src = match program {
+            Some(program) => program.src,
+            None => read_stdin(),
         };
+        check_program(&src, &runner, colored);
+    }
+    if let Some(program) = program {
+        run_program(
+            program,
+            runner,
+            show_warnings,
+            warnings_as_errors,
+            colored,
+            time,
+        );
     }
 
-    fn match_string(&mut self) -> Token {
-        let mut string = String::new();
-
-        while self.peek(1).is_ascii() && self.peek(1) != '"' {
-            self.advance();
-            string += &*self.current_character.to_string();
-        }
-
-        self.advance();
-
-        return Token::new(TokenType::String, string);
+    // Only someone at a terminal needs greeting; pipelines get just the
+    // output.
+    if !quiet && io::stdin().is_terminal() && io::stdout().is_terminal() {
+        println!(
+            "ProgLang {} \u{2014} type :help for help",
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+    let mut repl = Repl::new(with_line_editor(runner, history))
+        .with_warnings(show_warnings)
+        .with_color(colored);
+    if let Err(error) = repl.run() {
+        process::exit(error.exit_code());
     }
+    if warnings_as_errors && repl.warning_count() > 0 {
+        process::exit(1);
+    }
+}
 
-    fn match_keyword(&mut self) -> Token {
-        let mut keyword: String = String::from(self.current_character);
+/// The interpreter's version, and the commit it was built from if that was
+/// known at build time.
+fn version() -> String {
+    match option_env!("PROGLANG_GIT_HASH") {
+        Some(hash) => format!("ProgLang {} ({})", env!("CARGO_PKG_VERSION"), hash),
+        None => format!("ProgLang {}", env!("CARGO_PKG_VERSION")),
+    }
+}
 
-        while !self.peek(1).is_whitespace() {
-            self.advance();
-            keyword += &*self.current_character.to_string();
-        }
+/// Gives `runner` a line editor to read the REPL's input with if stdin is a
+/// terminal. Unless `history` is off, lines are kept in the file named by
+/// `PROGLANG_HISTORY`, or `~/.proglang_history` if that isn't set; setting it
+/// empty keeps none.
+#[cfg(all(feature = "readline", not(target_arch = "wasm32")))]
+fn with_line_editor(runner: Runner, history: bool) -> Runner {
+    use std::path::PathBuf;
 
-        return Token::new(TokenType::Keyword, keyword);
+    if !io::stdin().is_terminal() {
+        return runner;
     }
+    let editor = match proglang::LineEditor::new() {
+        Ok(editor) => editor,
+        Err(_) => return runner,
+    };
+
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+    let file = match env::var_os("PROGLANG_HISTORY") {
+        Some(file) => Some(PathBuf::from(file)),
+        None => home.map(|home| PathBuf::from(home).join(".proglang_history")),
+    };
+    let editor = match file {
+        Some(file) if history && !file.as_os_str().is_empty() => editor.with_history_file(file),
+        _ => editor,
+    };
+    runner.with_input(Box::new(editor))
 }
 
-struct Runner {
-    token_stack: VecDeque<Token>,
+#[cfg(not(all(feature = "readline", not(target_arch = "wasm32"))))]
+fn with_line_editor(runner: Runner, _history: bool) -> Runner {
+    runner
 }
 
-impl Runner {
-    fn new(stack: VecDeque<Token>) -> Runner {
-        Runner { token_stack: stack }
-    }
-
-    fn start(&mut self) {
-        while !self.token_stack.is_empty() {
-            let token = self.token_stack.pop_back().unwrap();
+/// A program to run as a whole: a file, `-e` programs or piped-in source.
+struct Program {
+    src: String,
+    /// The file it came from, if any.
+    path: Option<String>,
+}
 
-            match token.token_type {
-                Some(TokenType::Plus) => self.add(),
-                Some(TokenType::Multiply) => self.multiply(),
-                Some(TokenType::Keyword) => self.handle_keyword(token),
-                None => Error::new(
-                    "Unknown token error",
-                    "Unrecognised token {:?}"
-                )
-            }
+/// Reads the program in the file at `path`, exiting with an error if it
+/// can't be read.
+fn read_file(path: String) -> Program {
+    match fs::read_to_string(&path) {
+        Ok(src) => Program {
+            src,
+            path: Some(path),
+        },
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path, e);
+            process::exit(1);
         }
     }
+}
 
-    fn handle_keyword(&mut self, token: Token) {
-        let keyword = token.token_value;
-        match &keyword[..] {
-            "puts" => self.puts(),
-            _ => Error::new(
-                "Unknown keyword error",
-                &format!("No such keyword: {}", keyword)[..],
-            )
-            .throw(),
-        }
+/// Reads a whole program from stdin.
+fn read_stdin() -> String {
+    let mut src = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut src) {
+        eprintln!("Could not read the program: {}", e);
+        process::exit(1);
     }
+    src
+}
 
-    fn puts(&mut self) {
-        let valueToPrint: String = self.token_stack.pop_back().unwrap().token_value;
-
-        println!("{}", valueToPrint);
+/// Runs `program` as a whole, reporting any error and warnings to stderr,
+/// colored if `colored`, then how long it took if `time` and how many times
+/// each operation ran if the runner counts them, and exits with the code
+/// [`Repl`] would have.
+fn run_program(
+    program: Program,
+    mut runner: Runner,
+    show_warnings: bool,
+    warnings_as_errors: bool,
+    colored: bool,
+    time: bool,
+) -> ! {
+    let src = program.src;
+    if let Some(path) = &program.path {
+        runner = runner.with_source_file(path);
     }
+    let (result, timings) = timing::timed_run(Lexer::new(src.clone()), &mut runner);
 
-    fn add(&mut self) -> Token {
-        let first = self.token_stack.pop_back().unwrap();
-        let mut second = self.token_stack.pop_back().unwrap();
-
-        if second.token_type == TokenType::Plus {
-            second = self.add();
-        }
-
-        if first.token_type != second.token_type {
-            println!("{:?}", self.token_stack);
-            println!(
-                "First arg: {:?}\nSecond arg: {:?}",
-                first.token_type, second.token_type
-            );
-            Error::new(
-                "Mismatched types",
-                "Cannot add on 2 values of different types",
-            )
-            .throw();
-        }
-
-        let first_num = first.token_value;
-        let second_num = second.token_value;
-
-        let result: usize =
-            first_num.parse::<usize>().unwrap() + second_num.parse::<usize>().unwrap();
-
-        println!("{}", result);
-
-        self.token_stack.push_front(Token {
-            token_type: TokenType::Int,
-            token_value: result.to_string(),
-        });
-        Token::new(first.token_type, result.to_string())
+    if let Err(error) = &result {
+        eprintln!("{}", color::render_if(colored, error.error(), &src));
+    } else {
+        runner.warn_about_leftovers();
     }
-
-    fn multiply(&mut self) -> Token {
-        let first = self.token_stack.pop_front().unwrap();
-        let mut second = self.token_stack.pop_front().unwrap();
-
-        if second.token_type == TokenType::Multiply {
-            second = self.multiply();
+    let warnings = runner.take_warnings();
+    if show_warnings {
+        for warning in &warnings {
+            eprintln!("{}", color::render_warning_if(colored, warning, &src));
         }
+    }
+    if time {
+        eprintln!("{}", timings);
+    }
+    let mut stats: Vec<(String, usize)> = runner.stats().into_iter().collect();
+    stats.sort();
+    for (operation, count) in stats {
+        eprintln!("{}: {}", operation, count);
+    }
 
-        if first.token_type != second.token_type {
-            Error::new(
-                "Mismatched types",
-                "Cannot add on 2 values of different types",
-            )
-            .throw();
-        }
-
-        let first_num = first.token_value;
-        let second_num = second.token_value;
-
-        let result: usize =
-            first_num.parse::<usize>().unwrap() + second_num.parse::<usize>().unwrap();
-
-        println!("{}", result);
-
-        Token::new(first.token_type, result.to_string())
+    match result {
+        Err(error) => process::exit(error.exit_code()),
+        Ok(()) if warnings_as_errors && !warnings.is_empty() => process::exit(1),
+        Ok(()) => process::exit(0),
     }
 }
 
-fn get_input(msg: &str) -> String {
-    print!("{}", msg);
-    let mut s = String::new();
-    let _ = stdout().flush();
-    stdin()
-        .read_line(&mut s)
-        .expect("How on earth did you mess up a string");
-
-    return s;
+/// Reports every problem [`check`](check::check) finds in `src` to stderr,
+/// without running it, and exits with 2 if there were any.
+fn check_program(src: &str, runner: &Runner, colored: bool) -> ! {
+    let problems = check::check(src, runner);
+    for problem in &problems {
+        eprintln!("{}", color::render_if(colored, problem, src));
+    }
+    process::exit(if problems.is_empty() { 0 } else { 2 });
 }
 
-fn main() {
-    loop {
-        let input = get_input("> ");
-        let mut lexer: Lexer = Lexer::new(String::from(input));
-        let tokens: VecDeque<Token> = VecDeque::from(lexer.tokenize());
+/// The number given after `option`, exiting with an error if there isn't one.
+fn limit(option: &str, value: Option<String>) -> usize {
+    match value.as_deref().map(str::parse) {
+        Some(Ok(limit)) => limit,
+        _ => {
+            eprintln!("{} needs a whole number after it", option);
+            process::exit(2);
+        }
+    }
+}
 
-        let mut runner: Runner = Runner::new(tokens);
-        runner.start();
+/// Turns a program's source into a JSON document describing it.
+#[cfg(feature = "serde")]
+type Describe = fn(&str) -> Result<String, proglang::ProgLangError>;
+
+/// Prints the JSON document `describe` makes of `src`, and exits.
+#[cfg(feature = "serde")]
+fn print_json(describe: Describe, src: &str, colored: bool) -> ! {
+    match describe(src) {
+        Ok(json) => {
+            println!("{}", json);
+            process::exit(0);
+        }
+        Err(error) => {
+            eprintln!("{}", color::render_if(colored, error.error(), src));
+            process::exit(error.exit_code());
+        }
     }
 }
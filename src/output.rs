@@ -0,0 +1,41 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// An in-memory sink that can be handed to a [`Runner`](crate::runner::Runner)
+/// while a clone is kept around to read back what was written.
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Everything written so far, lossily decoded as UTF-8.
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_their_contents() {
+        let buffer = SharedBuffer::default();
+        let mut writer = buffer.clone();
+
+        write!(writer, "hello").unwrap();
+
+        assert_eq!(buffer.contents(), "hello");
+    }
+}
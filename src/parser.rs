@@ -0,0 +1,214 @@
+use crate::error::{Error, ErrorKind};
+use crate::span::Span;
+use crate::token::{Token, TokenType};
+
+/// A piece of a parsed program: either a single token or a `{ }` block of
+/// further nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "kind")
+)]
+pub enum Node {
+    Token(Token),
+    Block {
+        body: Vec<Node>,
+        /// From the opening brace up to and including the closing one.
+        #[cfg_attr(feature = "serde", serde(rename = "position"))]
+        span: Span,
+    },
+}
+
+/// Groups `tokens` into nodes, raising an error for any brace without a
+/// partner.
+pub fn parse(tokens: &[Token]) -> Result<Vec<Node>, Error> {
+    // The nodes of every block still open, outermost first, with the span of
+    // the brace that opened it.
+    let mut open: Vec<(Vec<Node>, Span)> = Vec::new();
+    let mut nodes = Vec::new();
+
+    for token in tokens {
+        match token.token_type {
+            TokenType::LeftBrace => {
+                open.push((std::mem::take(&mut nodes), token.span));
+            }
+            TokenType::RightBrace => {
+                let (outer, start) = open.pop().ok_or_else(|| {
+                    Error::new(ErrorKind::Syntax, "Found a } with no {", token.span)
+                })?;
+                let body = std::mem::replace(&mut nodes, outer);
+                nodes.push(Node::Block {
+                    body,
+                    span: Span {
+                        end: token.span.end,
+                        ..start
+                    },
+                });
+            }
+            _ => nodes.push(Node::Token(token.clone())),
+        }
+    }
+
+    match open.pop() {
+        Some((_, start)) => Err(Error::new(
+            ErrorKind::Syntax,
+            "This { is never closed",
+            start,
+        )),
+        None => Ok(nodes),
+    }
+}
+
+/// Whether `tokens` stop inside a `{ }` block, a `try`, an `if` or a `match`
+/// that more tokens could still close, e.g. because a program is being typed
+/// a line at a time.
+pub fn is_unfinished(tokens: &[Token]) -> bool {
+    let mut braces = 0;
+    // The `try`s, `if`s and `match`es still waiting for their `end`.
+    let mut opened = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token.token_type {
+            TokenType::LeftBrace => braces += 1,
+            // A stray } can't be fixed by more input.
+            TokenType::RightBrace if braces == 0 => return false,
+            TokenType::RightBrace => braces -= 1,
+            TokenType::Keyword => match &*token.token_value {
+                // Its braces say whether it's finished, except that after
+                // its body it still needs its catch.
+                "try" if starts_block(&tokens[index + 1..]) => {
+                    let rest = &tokens[index + 1..];
+                    if block_end(rest) == Some(rest.len()) {
+                        return true;
+                    }
+                }
+                "try" | "if" | "match" => opened += 1,
+                "end" if opened > 0 => opened -= 1,
+                // Waiting for the handler of a try, maybe after its name.
+                "catch" if awaits_handler(&tokens[index + 1..]) => return true,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    braces > 0 || opened > 0
+}
+
+/// Whether `rest`, everything after a `catch`, is at most the name its
+/// handler binds.
+fn awaits_handler(rest: &[Token]) -> bool {
+    match rest {
+        [] => true,
+        [name] => name.token_type == TokenType::Keyword && &*name.token_value != "end",
+        _ => false,
+    }
+}
+
+/// Whether `tokens` start with a `{`.
+pub(crate) fn starts_block(tokens: &[Token]) -> bool {
+    tokens
+        .first()
+        .is_some_and(|token| token.token_type == TokenType::LeftBrace)
+}
+
+/// How many of `tokens`, which start with a `{`, there are up to and including
+/// the `}` that closes it, or `None` if nothing does.
+pub(crate) fn block_end(tokens: &[Token]) -> Option<usize> {
+    let mut braces = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token.token_type {
+            TokenType::LeftBrace => braces += 1,
+            TokenType::RightBrace => {
+                braces -= 1;
+                if braces == 0 {
+                    return Some(index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_source(source: &str) -> Result<Vec<Node>, Error> {
+        parse(&Lexer::new(String::from(source)).tokenize().unwrap())
+    }
+
+    #[test]
+    fn tokens_outside_blocks_stay_flat() {
+        let nodes = parse_source("1 2 +").unwrap();
+
+        assert_eq!(nodes.len(), 3);
+        assert!(matches!(&nodes[2], Node::Token(token) if token.token_type == TokenType::Plus));
+    }
+
+    #[test]
+    fn braces_nest_blocks() {
+        let nodes = parse_source("def f { 1 { 2 } }").unwrap();
+
+        assert_eq!(nodes.len(), 3);
+        match &nodes[2] {
+            Node::Block { body, span } => {
+                assert_eq!(body.len(), 2);
+                assert!(matches!(&body[1], Node::Block { body, .. } if body.len() == 1));
+                assert_eq!(*span, Span::new(6, 17, 1, 7));
+            }
+            node => panic!("expected a block, found {:?}", node),
+        }
+    }
+
+    #[test]
+    fn unclosed_braces_are_errors_at_the_brace() {
+        let error = parse_source("{ 1 {").unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Syntax);
+        assert_eq!(error.span.column, 5);
+    }
+
+    #[test]
+    fn open_blocks_and_keywords_are_unfinished() {
+        let unfinished =
+            |source: &str| is_unfinished(&Lexer::new(String::from(source)).tokenize().unwrap());
+
+        assert!(unfinished("def f {"));
+        assert!(unfinished("def f { 1 { 2 }"));
+        assert!(unfinished("try 1 catch"));
+        assert!(!unfinished("def f { 1 } try 1 catch 2 end"));
+        assert!(unfinished("true if"));
+        assert!(unfinished("try true if 1 else 2 end"));
+        assert!(!unfinished("try true if 1 else 2 end catch end"));
+        assert!(unfinished("1 match"));
+        assert!(unfinished("1 match 1 { true if 1 else 2 end }"));
+        assert!(!unfinished("1 match _ { } end"));
+        assert!(!unfinished("1 } {"));
+        assert!(!unfinished("\"{\" puts"));
+    }
+
+    #[test]
+    fn block_tries_are_unfinished_until_their_handler_closes() {
+        let unfinished =
+            |source: &str| is_unfinished(&Lexer::new(String::from(source)).tokenize().unwrap());
+
+        assert!(unfinished("try {"));
+        assert!(unfinished("try { 1 }"));
+        assert!(unfinished("try { 1 } catch"));
+        assert!(unfinished("try { 1 } catch err"));
+        assert!(unfinished("try { 1 } catch err { err"));
+        assert!(!unfinished("try { 1 } catch err { err puts }"));
+        assert!(!unfinished("true if try { 1 } catch { } else 2 end"));
+        assert!(!unfinished("try 1 catch end"));
+    }
+
+    #[test]
+    fn closing_braces_need_an_opening_one() {
+        let error = parse_source("1 }").unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Syntax);
+        assert_eq!(error.span.column, 3);
+    }
+}
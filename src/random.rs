@@ -0,0 +1,93 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// A small, fast source of pseudo-random numbers for `rand` and `randint`,
+/// using SplitMix64. Given the same seed, it gives the same numbers.
+///
+/// Nowhere near good enough for anything secret.
+#[derive(Debug, Clone)]
+pub(crate) struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub(crate) fn seeded(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    /// A generator seeded differently each time. The seed comes from the
+    /// standard library's hash keys, which unlike the clock are there even
+    /// on bare WebAssembly.
+    pub(crate) fn unseeded() -> Random {
+        Random::seeded(RandomState::new().build_hasher().finish())
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A float from 0 up to but not including 1.
+    pub(crate) fn next_float(&mut self) -> f64 {
+        // The top 53 bits, as many as a float holds exactly.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A number from `low` to `high`, both included.
+    pub(crate) fn between(&mut self, low: i64, high: i64) -> i64 {
+        // How far apart they are always fits in a u64, even if not an i64.
+        let Some(count) = high.abs_diff(low).checked_add(1) else {
+            // Every i64 is in range.
+            return self.next_u64() as i64;
+        };
+        // Numbers past the last whole multiple of count would favor the
+        // smaller results, so draw again if one comes up.
+        let limit = u64::MAX - u64::MAX % count;
+        loop {
+            let n = self.next_u64();
+            if n < limit {
+                return low.wrapping_add((n % count) as i64);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_numbers() {
+        let mut first = Random::seeded(7);
+        let mut second = Random::seeded(7);
+
+        for _ in 0..10 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+        assert_ne!(Random::seeded(8).next_u64(), Random::seeded(7).next_u64());
+    }
+
+    #[test]
+    fn floats_are_below_one() {
+        let mut random = Random::seeded(1);
+
+        assert!((0..1000)
+            .map(|_| random.next_float())
+            .all(|f| (0.0..1.0).contains(&f)));
+    }
+
+    #[test]
+    fn between_includes_both_ends() {
+        let mut random = Random::seeded(1);
+        let drawn: Vec<i64> = (0..200).map(|_| random.between(-1, 1)).collect();
+
+        assert!(drawn.iter().all(|n| (-1..=1).contains(n)));
+        assert!([-1, 0, 1].iter().all(|n| drawn.contains(n)));
+        assert_eq!(random.between(9, 9), 9);
+        assert!(random.between(i64::MAX - 1, i64::MAX) >= i64::MAX - 1);
+        random.between(i64::MIN, i64::MAX);
+    }
+}
@@ -0,0 +1,879 @@
+use crate::color;
+use crate::error::ProgLangError;
+use crate::help;
+use crate::lexer::{LexStatus, Lexer};
+use crate::parser;
+use crate::runner::Runner;
+use crate::timing;
+use std::fs;
+use std::io::{stderr, Write};
+use std::path::Path;
+
+/// An interactive read-eval-print loop on top of a [`Runner`].
+///
+/// Lines are read from the runner's [input](Runner::with_input) after showing
+/// a `> ` prompt on its output, so a whole session can be scripted.
+///
+/// Lines are numbered across the whole session, so errors and warnings point
+/// at the line they came from even after more input has been read.
+///
+/// Lines starting with `:` are commands for the REPL itself rather than
+/// programs, and never reach the lexer:
+///
+/// - `:words` lists the words and variables defined so far.
+/// - `:clear` clears the terminal screen.
+/// - `:reset` forgets every value, word and variable.
+/// - `:save PATH` writes every program that has run without an error since
+///   the session began, or since the last `:reset`, to a file at `PATH`, so
+///   running it builds up the same words and variables. `:save! PATH`
+///   replaces a file that is already there.
+/// - `:load PATH` runs the file at `PATH`, keeping what it defines. Its
+///   errors point into the file, which can't `include` itself.
+/// - `:stats` shows how many times each operation has run, if the runner
+///   [counts them](Runner::with_stats).
+/// - `:help` lists the keywords and operators, and `:help NAME` describes
+///   one of them.
+/// - `:types` lists the types of token.
+/// - `:time on` reports how long each line takes to lex and run, and
+///   `:time off` stops.
+/// - `:debug on` runs each line [one step at a time](Runner::set_debugging),
+///   and `:debug off` stops.
+///
+/// A line that is just `exit` or `quit` ends the session.
+pub struct Repl {
+    runner: Runner,
+    err: Box<dyn Write>,
+    source: String,
+    lines_read: usize,
+    show_warnings: bool,
+    warning_count: usize,
+    colored: bool,
+    timing: bool,
+    /// The programs that have run without an error, for `:save`.
+    succeeded: Vec<String>,
+}
+
+impl Repl {
+    /// Creates a REPL that reports errors to stderr.
+    pub fn new(runner: Runner) -> Repl {
+        Repl {
+            runner,
+            err: Box::new(stderr()),
+            source: String::new(),
+            lines_read: 0,
+            show_warnings: true,
+            warning_count: 0,
+            colored: false,
+            timing: false,
+            succeeded: Vec::new(),
+        }
+    }
+
+    /// Reports errors to `err` instead of stderr.
+    pub fn with_errors(mut self, err: Box<dyn Write>) -> Repl {
+        self.err = err;
+        self
+    }
+
+    /// Reports warnings only if `show` is true. They are still counted either
+    /// way.
+    pub fn with_warnings(mut self, show: bool) -> Repl {
+        self.show_warnings = show;
+        self
+    }
+
+    /// Colors errors and warnings if `colored`, as when they go to a
+    /// terminal. They are plain by default.
+    pub fn with_color(mut self, colored: bool) -> Repl {
+        self.colored = colored;
+        self
+    }
+
+    /// How many warnings the session has raised so far.
+    pub fn warning_count(&self) -> usize {
+        self.warning_count
+    }
+
+    /// Runs lines until the input runs out or a line is just `exit` or
+    /// `quit`.
+    ///
+    /// One runner lives for the whole session, so values, words and variables
+    /// carry over from line to line. An error is reported but doesn't wipe
+    /// any of that, and the next line runs as usual; the first error is
+    /// returned once the session ends, unless it ends with `exit` or `quit`,
+    /// which always succeed. Values still on the stack then are
+    /// warned about. Blank lines are skipped.
+    ///
+    /// A line that leaves a string, a `{ }` block, a `try`, an `if` or a
+    /// `match` open is run together with the lines after it, read at a `... `
+    /// prompt, once they close it. Two blank lines in a row give up on it instead.
+    pub fn run(&mut self) -> Result<(), ProgLangError> {
+        let mut first_error = None;
+        // An unfinished program waiting for more lines, and the line it
+        // started on.
+        let mut pending = String::new();
+        let mut pending_from = 0;
+
+        loop {
+            let prompt = if pending.is_empty() { "> " } else { "... " };
+            let line = match self.read_line(prompt) {
+                Some(line) => line,
+                None => {
+                    // The input ran out at a prompt, e.g. with Ctrl-D, so end
+                    // its line.
+                    let _ = writeln!(self.runner.output());
+                    break;
+                }
+            };
+            self.lines_read += 1;
+            self.source += &line;
+            self.source.push('\n');
+
+            if pending.is_empty() {
+                match line.trim() {
+                    "" => continue,
+                    "exit" | "quit" => {
+                        first_error = None;
+                        break;
+                    }
+                    _ => {}
+                }
+                if line.trim_start().starts_with(':') {
+                    if let Err(error) = self.command(line.trim()) {
+                        first_error.get_or_insert(error);
+                    }
+                    continue;
+                }
+                pending_from = self.lines_read;
+            } else if line.trim().is_empty() && pending.ends_with('\n') {
+                // The line before was blank too.
+                pending.clear();
+                continue;
+            } else {
+                pending.push('\n');
+            }
+            pending += &line;
+
+            if self.is_unfinished(&pending) {
+                continue;
+            }
+            let program = std::mem::take(&mut pending);
+            match self.run_program(program.clone(), pending_from) {
+                Ok(()) => self.succeeded.push(program),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+
+        // Whatever was never finished still runs, to report what it's missing.
+        if !pending.is_empty() {
+            if let Err(error) = self.run_program(pending, pending_from) {
+                first_error.get_or_insert(error);
+            }
+        }
+
+        self.runner.warn_about_leftovers();
+        self.report_warnings(None);
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Whether `program` leaves something open that later lines could close.
+    fn is_unfinished(&self, program: &str) -> bool {
+        let config = self.runner.config();
+        match Lexer::from_config(String::from(program), &config).tokenize_incremental() {
+            LexStatus::Complete(tokens) => parser::is_unfinished(&tokens),
+            LexStatus::Incomplete(_) => true,
+            LexStatus::Invalid(_) => false,
+        }
+    }
+
+    /// Runs `program`, which starts on line `first_line` of the session,
+    /// reporting any error and warnings.
+    fn run_program(&mut self, program: String, first_line: usize) -> Result<(), ProgLangError> {
+        let lexer = Lexer::new(program).starting_at_line(first_line);
+        let (result, timings) = timing::timed_run(lexer, &mut self.runner);
+        if let Err(error) = &result {
+            let rendered = color::render_if(self.colored, error.error(), &self.source);
+            let _ = writeln!(self.err, "{}", rendered);
+        }
+        self.report_warnings(None);
+        if self.timing {
+            let _ = writeln!(self.err, "{}", timings);
+        }
+        result
+    }
+
+    /// Runs a `:` command, returning the error of any program it ran.
+    fn command(&mut self, command: &str) -> Result<(), ProgLangError> {
+        match command {
+            ":words" => self.list_words(),
+            ":clear" => {
+                let _ = write!(self.runner.output(), "{}", color::CLEAR_SCREEN);
+            }
+            ":reset" => {
+                self.runner.reset();
+                self.succeeded.clear();
+            }
+            ":stats" => self.show_stats(),
+            ":help" => {
+                let _ = write!(self.runner.output(), "{}", help::listing());
+            }
+            ":time on" => self.timing = true,
+            ":time off" => self.timing = false,
+            ":debug on" => self.runner.set_debugging(true),
+            ":debug off" => self.runner.set_debugging(false),
+            ":types" => {
+                let _ = write!(self.runner.output(), "{}", help::types());
+            }
+            _ if command.starts_with(":save ") => {
+                self.save(command[":save ".len()..].trim(), false)
+            }
+            _ if command.starts_with(":save! ") => {
+                self.save(command[":save! ".len()..].trim(), true)
+            }
+            _ if command.starts_with(":load ") => {
+                return self.load(command[":load ".len()..].trim());
+            }
+            _ if command.starts_with(":help ") => {
+                let name = command[":help ".len()..].trim();
+                match help::entry(name) {
+                    Some(entry) => {
+                        let _ = write!(self.runner.output(), "{}", entry);
+                    }
+                    None => {
+                        let _ = writeln!(self.err, "Nothing is built in called {}", name);
+                    }
+                }
+            }
+            _ => {
+                let _ = writeln!(self.err, "Unknown command: {}", command);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the programs that have run without an error to `path`, one
+    /// after another. A file already at `path` is only replaced if
+    /// `overwrite`.
+    fn save(&mut self, path: &str, overwrite: bool) {
+        if !overwrite && Path::new(path).exists() {
+            let _ = writeln!(
+                self.err,
+                "{} already exists; use :save! {} to replace it",
+                path, path
+            );
+            return;
+        }
+
+        let mut script = self.succeeded.join("\n");
+        if !script.is_empty() {
+            script.push('\n');
+        }
+        if let Err(e) = fs::write(path, script) {
+            let _ = writeln!(self.err, "Could not save {}: {}", path, e);
+        }
+    }
+
+    /// Runs the file at `path` as a whole, reporting any error and warnings
+    /// against its own lines. A file that can't be read is reported but
+    /// isn't an error of the session.
+    fn load(&mut self, path: &str) -> Result<(), ProgLangError> {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(e) => {
+                let _ = writeln!(self.err, "Could not load {}: {}", path, e);
+                return Ok(());
+            }
+        };
+
+        let config = self.runner.config();
+        let result = match Lexer::from_config(src.clone(), &config).tokenize() {
+            Ok(tokens) => self
+                .runner
+                .run_file(path, tokens)
+                .map_err(ProgLangError::RuntimeError),
+            Err(error) => Err(ProgLangError::LexError(error)),
+        };
+        if let Err(error) = &result {
+            let rendered = color::render_if(self.colored, error.error(), &src);
+            let _ = writeln!(self.err, "{}", rendered);
+        }
+        self.report_warnings(Some(&src));
+        result
+    }
+
+    fn list_words(&mut self) {
+        let mut listing = String::new();
+
+        if !self.runner.words().is_empty() {
+            listing += "words:\n";
+        }
+        for (name, word) in self.runner.words() {
+            listing += &format!("  {}", name);
+            for param in &word.params {
+                listing += &format!(" {}", param);
+            }
+            listing.push('\n');
+        }
+
+        if !self.runner.variables().is_empty() {
+            listing += "variables:\n";
+        }
+        for (name, value) in self.runner.variables() {
+            listing += &format!("  {}: {} = {}\n", name, value.token_type, value.token_value);
+        }
+
+        if listing.is_empty() {
+            listing += "Nothing is defined yet\n";
+        }
+        let _ = write!(self.runner.output(), "{}", listing);
+    }
+
+    fn show_stats(&mut self) {
+        if !self.runner.counts_stats() {
+            let _ = writeln!(self.runner.output(), "Operations aren't being counted");
+            return;
+        }
+
+        let mut stats: Vec<(String, usize)> = self.runner.stats().into_iter().collect();
+        stats.sort();
+
+        let mut listing = String::new();
+        for (operation, count) in stats {
+            listing += &format!("{}: {}\n", operation, count);
+        }
+        let _ = write!(self.runner.output(), "{}", listing);
+    }
+
+    /// Reports the warnings raised since the last call, which point into
+    /// `file` if given and into the session otherwise.
+    fn report_warnings(&mut self, file: Option<&str>) {
+        let source = file.unwrap_or(&self.source);
+        for warning in self.runner.take_warnings() {
+            self.warning_count += 1;
+            if self.show_warnings {
+                let rendered = color::render_warning_if(self.colored, &warning, source);
+                let _ = writeln!(self.err, "{}", rendered);
+            }
+        }
+    }
+
+    /// Prompts for and reads the next line, treating a failed read like the end
+    /// of the input.
+    fn read_line(&mut self, prompt: &str) -> Option<String> {
+        self.runner.prompt(prompt).ok()?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::input::LineReader;
+    use crate::output::SharedBuffer;
+    use std::env;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    fn repl(input: &str, transcript: &SharedBuffer) -> Repl {
+        let runner = Runner::new()
+            .with_output(Box::new(transcript.clone()))
+            .with_input(Box::new(LineReader::new(Cursor::new(input.to_owned()))));
+
+        Repl::new(runner).with_errors(Box::new(transcript.clone()))
+    }
+
+    /// Runs a scripted session and returns its whole transcript.
+    fn session(input: &str) -> String {
+        let transcript = SharedBuffer::default();
+        let _ = repl(input, &transcript).run();
+        transcript.contents()
+    }
+
+    #[test]
+    fn prompts_for_each_line_until_end_of_input() {
+        assert_eq!(session("1 2 + puts\n\"hi\" puts\n"), "> 3\n> hi\n> \n");
+    }
+
+    #[test]
+    fn ends_the_prompt_line_at_the_end_of_input() {
+        let transcript = SharedBuffer::default();
+
+        assert_eq!(repl("", &transcript).run(), Ok(()));
+        assert_eq!(transcript.contents(), "> \n");
+    }
+
+    #[test]
+    fn exit_and_quit_end_the_session() {
+        assert_eq!(session("1 puts\nexit\n2 puts\n"), "> 1\n> ");
+        assert_eq!(session("  quit  \n2 puts\n"), "> ");
+
+        let transcript = SharedBuffer::default();
+        assert_eq!(repl("exit\n", &transcript).run(), Ok(()));
+    }
+
+    #[test]
+    fn exit_succeeds_after_a_failed_line() {
+        let transcript = SharedBuffer::default();
+
+        assert_eq!(repl("bogus\nexit\n", &transcript).run(), Ok(()));
+        assert!(repl("bogus\n", &transcript).run().is_err());
+    }
+
+    #[test]
+    fn exit_only_counts_on_a_line_of_its_own() {
+        assert!(session("1 exit\n").contains("No such keyword: exit"));
+        assert_eq!(session("\"quit\" puts\n"), "> quit\n> \n");
+    }
+
+    #[test]
+    fn help_lists_keywords_and_describes_one() {
+        assert_eq!(session(":help\n"), format!("> {}> \n", help::listing()));
+        assert_eq!(
+            session(":help fdiv\n:help bogus\n"),
+            concat!(
+                "> fdiv: Divides two numbers, always giving a float\n",
+                "  stack: pops 2, pushes 1\n",
+                "> Nothing is built in called bogus\n",
+                "> \n",
+            )
+        );
+    }
+
+    #[test]
+    fn time_reports_each_line_while_on() {
+        let transcript = session(":time on\n1 puts\n:time off\n2 puts\n");
+        let lines: Vec<&str> = transcript.lines().collect();
+
+        assert_eq!(lines[0], "> > 1");
+        assert!(lines[1].starts_with("time: lex "), "{}", lines[1]);
+        assert!(lines[1].contains(", run ") && lines[1].contains(", total "));
+        assert_eq!(&lines[2..], ["> > 2", "> "]);
+    }
+
+    #[test]
+    fn debug_steps_through_each_line_while_on() {
+        assert_eq!(
+            session(":debug on\n1 2 + puts\ns\nstack\nc\n:debug off\n3 puts\n"),
+            concat!(
+                "> > line 2, column 1: 1\n",
+                "(debug) line 2, column 3: 2\n",
+                "(debug) [1]\n",
+                "(debug) 3\n",
+                "> > 3\n",
+                "> \n",
+            )
+        );
+    }
+
+    #[test]
+    fn types_lists_the_token_types() {
+        assert_eq!(session(":types\n"), format!("> {}> \n", help::types()));
+    }
+
+    #[test]
+    fn unfinished_blocks_continue_on_the_next_line() {
+        assert_eq!(
+            session("def square n {\n  n n *\n}\n3 square puts\n"),
+            "> ... ... > 9\n> \n"
+        );
+    }
+
+    #[test]
+    fn unfinished_ifs_continue_on_the_next_line() {
+        assert_eq!(
+            session(
+                "true if
+\"yes\" puts else \"no\" puts end
+"
+            ),
+            "> ... yes\n> \n"
+        );
+        assert_eq!(
+            session(
+                "try true if 1 else 2 end
+catch end puts
+"
+            ),
+            "> ... 1\n> \n"
+        );
+    }
+
+    #[test]
+    fn unfinished_matches_continue_on_the_next_line() {
+        assert_eq!(
+            session(
+                "1 match
+  1 { \"one\" puts }
+  _ { \"other\" puts }
+end
+"
+            ),
+            "> ... ... ... one\n> \n"
+        );
+    }
+
+    #[test]
+    fn strings_can_span_lines() {
+        assert_eq!(session("\"two\nlines\" puts\n"), "> ... two\nlines\n> \n");
+    }
+
+    #[test]
+    fn two_blank_lines_give_up_on_an_unfinished_program() {
+        assert_eq!(session("def f {\n\n\n1 puts\n"), "> ... ... > 1\n> \n");
+    }
+
+    #[test]
+    fn programs_unfinished_at_the_end_of_input_are_errors() {
+        let output = session("1 puts\ndef f {\n2\n");
+
+        assert!(output.starts_with(concat!(
+            "> 1\n",
+            "> ... ... \n",
+            "SyntaxError: The body of f is missing its closing }\n",
+            " --> line 2, column 1\n",
+        )));
+    }
+
+    #[test]
+    fn errors_in_continued_programs_point_at_their_line() {
+        assert!(session("try\n  1 \"a\" +\ncatch\nbogus end\n").contains(" --> line 4, column 1\n"));
+    }
+
+    #[test]
+    fn blank_lines_just_prompt_again() {
+        assert_eq!(session("\n   \n1 puts\n"), "> > > 1\n> \n");
+    }
+
+    #[test]
+    fn gets_reads_the_following_lines() {
+        assert_eq!(
+            session("\"name?\" puts gets puts\nAlex\n\"done\" puts\n"),
+            "> name?\nAlex\n> done\n> \n"
+        );
+    }
+
+    #[test]
+    fn gets_at_the_end_of_input_pushes_nil() {
+        assert_eq!(session("gets puts\n"), "> nil\n> \n");
+    }
+
+    #[test]
+    fn returns_the_first_error_once_the_input_runs_out() {
+        let runner = Runner::new()
+            .with_output(Box::new(Vec::new()))
+            .with_input(Box::new(LineReader::new("1 \"a\" +\nbogus\n".as_bytes())));
+
+        let error = Repl::new(runner)
+            .with_errors(Box::new(Vec::new()))
+            .run()
+            .unwrap_err();
+
+        assert!(matches!(error, ProgLangError::RuntimeError(_)));
+        assert_eq!(error.kind(), ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn keeps_going_after_a_thrown_error() {
+        assert_eq!(
+            session("\"bad input\" throw\n1 puts\n"),
+            concat!(
+                "> UserError: bad input\n",
+                " --> line 1, column 13\n",
+                "  |\n",
+                "1 | \"bad input\" throw\n",
+                "  |             ^^^^^\n",
+                "> 1\n",
+                "> \n",
+            )
+        );
+    }
+
+    #[test]
+    fn errors_in_words_point_at_the_line_defining_them() {
+        assert_eq!(
+            session("def broken { 1 \"a\" + }\n\"fine\" puts broken\n"),
+            concat!(
+                "> > fine\n",
+                "Mismatched types: Cannot add on 2 values of different types\n",
+                " --> line 1, column 20\n",
+                "  |\n",
+                "1 | def broken { 1 \"a\" + }\n",
+                "  |                    ^\n",
+                "  = in broken, called at line 2, column 13\n",
+                "> \n",
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_going_after_an_error() {
+        assert_eq!(
+            session("bogus\n1 puts\n"),
+            concat!(
+                "> Unknown keyword error: No such keyword: bogus\n",
+                " --> line 1, column 1\n",
+                "  |\n",
+                "1 | bogus\n",
+                "  | ^^^^^\n",
+                "> 1\n",
+                "> \n",
+            )
+        );
+    }
+
+    #[test]
+    fn state_carries_over_between_lines_and_survives_errors() {
+        let transcript = SharedBuffer::default();
+        let mut repl = repl(
+            "5 let x\ndef double n { n 2 * }\nx puts\nx double bogus\nputs x double puts\n",
+            &transcript,
+        )
+        .with_warnings(false);
+
+        assert!(repl.run().is_err());
+        assert_eq!(
+            transcript.contents(),
+            concat!(
+                "> > > 5\n",
+                "> Unknown keyword error: No such keyword: bogus\n",
+                " --> line 4, column 10\n",
+                "  |\n",
+                "4 | x double bogus\n",
+                "  |          ^^^^^\n",
+                "> 10\n10\n",
+                "> \n",
+            )
+        );
+    }
+
+    #[test]
+    fn numbers_errors_by_their_line_in_the_session() {
+        assert!(session("1 puts\n2 bogus\n").contains(concat!(
+            " --> line 2, column 3\n",
+            "  |\n",
+            "2 | 2 bogus\n",
+            "  |   ^^^^^\n",
+        )));
+    }
+
+    #[test]
+    fn warns_about_values_left_at_the_end_of_input() {
+        assert_eq!(
+            session("1 2 puts\n3 4 +\n"),
+            concat!(
+                "> 2\n",
+                "> > \n",
+                "warning: Value left on the stack when the program ended\n",
+                " --> line 1, column 1\n",
+                "  |\n",
+                "1 | 1 2 puts\n",
+                "  | ^\n",
+                "warning: Value left on the stack when the program ended\n",
+                " --> line 2, column 5\n",
+                "  |\n",
+                "2 | 3 4 +\n",
+                "  |     ^\n",
+            )
+        );
+    }
+
+    #[test]
+    fn counts_warnings_it_does_not_show() {
+        let transcript = SharedBuffer::default();
+        let mut repl = repl("1 2\n", &transcript).with_warnings(false);
+
+        repl.run().unwrap();
+
+        assert_eq!(repl.warning_count(), 2);
+        assert_eq!(transcript.contents(), "> > \n");
+    }
+
+    #[test]
+    fn colors_errors_if_asked_to() {
+        let transcript = SharedBuffer::default();
+        let _ = repl("bogus\n", &transcript).with_color(true).run();
+
+        assert!(transcript
+            .contents()
+            .starts_with("> \x1b[1;31mUnknown keyword error\x1b[0m: No such keyword: bogus\n"));
+        assert!(!session("bogus\n").contains('\x1b'));
+    }
+
+    #[test]
+    fn words_lists_definitions_and_variables() {
+        assert_eq!(
+            session(
+                "def square n { n n * }\ndef answer { 42 }\n5 let x\n\"hi\" let greeting\n:words\n"
+            ),
+            concat!(
+                "> > > > > words:\n",
+                "  answer\n",
+                "  square n\n",
+                "variables:\n",
+                "  greeting: String = hi\n",
+                "  x: Int = 5\n",
+                "> \n",
+            )
+        );
+    }
+
+    #[test]
+    fn words_says_when_nothing_is_defined() {
+        assert_eq!(session(":words\n"), "> Nothing is defined yet\n> \n");
+    }
+
+    #[test]
+    fn commands_never_reach_the_lexer() {
+        assert_eq!(
+            session(":bogus\n1 puts\n"),
+            "> Unknown command: :bogus\n> 1\n> \n"
+        );
+    }
+
+    #[test]
+    fn clear_sends_the_clear_screen_sequence() {
+        assert_eq!(session(":clear\n"), "> \x1b[2J\x1b[H> \n");
+    }
+
+    #[test]
+    fn reset_starts_over_without_a_restart() {
+        assert_eq!(
+            session("1 2 def f { 3 } 5 let x\n:reset\n:words\ncollect len puts\n"),
+            "> > > Nothing is defined yet\n> 0\n> \n"
+        );
+    }
+
+    /// Writes `contents` to a file of its own for a test called `name`.
+    fn file(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("proglang-{}-{}.plang", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn load_keeps_definitions_until_a_reset() {
+        let path = file("load", "def triple n { n 3 * }\n");
+
+        let transcript = session(&format!(
+            ":load {}\n4 triple puts\n:reset\n4 triple puts\n",
+            path.display()
+        ));
+        fs::remove_file(&path).unwrap();
+
+        assert!(
+            transcript.starts_with("> > 12\n> > Unknown keyword error: No such keyword: triple\n")
+        );
+    }
+
+    #[test]
+    fn errors_in_loaded_files_point_into_the_file() {
+        let path = file("load-error", "1 puts\n  bogus\n");
+
+        let transcript = session(&format!("\n:load {}\n", path.display()));
+        fs::remove_file(&path).unwrap();
+
+        assert!(transcript.contains(concat!(
+            "Unknown keyword error: No such keyword: bogus\n",
+            " --> line 2, column 3\n",
+            "  |\n",
+            "2 |   bogus\n",
+        )));
+    }
+
+    #[test]
+    fn loaded_files_cannot_include_themselves() {
+        let path = file("load-self", "");
+        fs::write(&path, format!("{:?} include\n", path.display().to_string())).unwrap();
+
+        let transcript = SharedBuffer::default();
+        let result = repl(&format!(":load {}\n", path.display()), &transcript).run();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Include);
+        assert!(transcript.contents().contains("Include cycle: "));
+    }
+
+    #[test]
+    fn save_writes_only_the_programs_that_ran() {
+        let path = file("save", "");
+        fs::remove_file(&path).unwrap();
+
+        session(&format!(
+            "def double n {{\n  n 2 *\n}}\nbogus\n:words\n3 double let x\n:save {}\n",
+            path.display()
+        ));
+        let saved = fs::read_to_string(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            saved.unwrap(),
+            "def double n {\n  n 2 *\n}\n3 double let x\n"
+        );
+    }
+
+    #[test]
+    fn save_only_replaces_a_file_with_a_bang() {
+        let path = file("save-again", "old\n");
+
+        let transcript = session(&format!(":save {}\n", path.display()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old\n");
+        assert!(transcript.contains("already exists; use :save! "));
+
+        session(&format!("1 puts\n:save! {}\n", path.display()));
+        let saved = fs::read_to_string(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(saved.unwrap(), "1 puts\n");
+    }
+
+    #[test]
+    fn reset_forgets_what_save_would_write() {
+        let path = file("save-reset", "");
+
+        session(&format!(
+            "1 let a\n:reset\n2 let b\n:save! {}\n",
+            path.display()
+        ));
+        let saved = fs::read_to_string(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved.unwrap(), "2 let b\n");
+    }
+
+    #[test]
+    fn loading_a_missing_file_says_so() {
+        let transcript = SharedBuffer::default();
+        let result = repl(":load /no/such/file.plang\n1 puts\n", &transcript).run();
+
+        assert_eq!(result, Ok(()));
+        assert!(transcript
+            .contents()
+            .starts_with("> Could not load /no/such/file.plang: "));
+        assert!(transcript.contents().ends_with("> 1\n> \n"));
+    }
+
+    #[test]
+    fn stats_lists_counts_by_operation() {
+        let transcript = SharedBuffer::default();
+        let runner = Runner::new()
+            .with_stats()
+            .with_output(Box::new(transcript.clone()))
+            .with_input(Box::new(LineReader::new("1 2 + puts\n:stats\n".as_bytes())));
+
+        Repl::new(runner).run().unwrap();
+
+        assert_eq!(
+            transcript.contents(),
+            "> 3\n> Int: 2\nPlus: 1\nputs: 1\n> \n"
+        );
+    }
+
+    #[test]
+    fn stats_says_when_nothing_is_counted() {
+        assert_eq!(
+            session(":stats\n"),
+            "> Operations aren't being counted\n> \n"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use crate::color::{Style, Tone};
+use crate::error::Error;
+use crate::span::Span;
+use crate::warning::Warning;
+
+/// Renders `error` the way the command line shows it: the message, where it
+/// happened, and the offending line of `source` with the span underlined.
+///
+/// ```text
+/// Mismatched types: Cannot add on 2 values of different types
+///  --> line 2, column 7
+///   |
+/// 2 | 1 "a" +
+///   |       ^
+/// ```
+///
+/// Columns count characters rather than bytes, and tabs before the span are
+/// copied into the underline so it lines up however wide the terminal draws
+/// them.
+///
+/// An error inside words ends with its trace, innermost call first:
+///
+/// ```text
+/// UserError: too deep
+///  --> line 1, column 24
+///   |
+/// 1 | def inner { "too deep" throw } def outer { inner } outer
+///   |                        ^^^^^
+///   = in inner, called at line 1, column 44
+///   = in outer, called at line 1, column 52
+/// ```
+///
+/// [`color::render`](crate::color::render) renders the same in color.
+pub fn render(error: &Error, source: &str) -> String {
+    render_styled(error, source, Style::plain(Tone::Error))
+}
+
+pub(crate) fn render_styled(error: &Error, source: &str, style: Style) -> String {
+    let mut rendered = render_at(error.name(), &error.message, error.span, source, style);
+    let gutter = " ".repeat(error.span.line.to_string().len());
+    for frame in &error.trace {
+        rendered += &format!("\n{} = in {}, called at {}", gutter, frame.name, frame.span);
+    }
+    rendered
+}
+
+/// Renders `warning` the same way as [`render`] renders errors, under a
+/// `warning:` headline.
+pub fn render_warning(warning: &Warning, source: &str) -> String {
+    render_warning_styled(warning, source, Style::plain(Tone::Warning))
+}
+
+pub(crate) fn render_warning_styled(warning: &Warning, source: &str, style: Style) -> String {
+    render_at("warning", &warning.message, warning.span, source, style)
+}
+
+fn render_at(name: &str, message: &str, span: Span, source: &str, style: Style) -> String {
+    let line_number = span.line.to_string();
+    let gutter = " ".repeat(line_number.len());
+
+    let mut rendered = format!(
+        "{}: {}\n{}--> {}",
+        style.name(name),
+        message,
+        gutter,
+        style.position(&span.to_string())
+    );
+
+    let line = match source.split('\n').nth(span.line.saturating_sub(1)) {
+        Some(line) => line.trim_end_matches('\r'),
+        None => return rendered,
+    };
+
+    let before: String = line
+        .chars()
+        .take(span.column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let remaining = line.chars().count().saturating_sub(before.chars().count());
+    let width = (span.end - span.start).min(remaining).max(1);
+
+    rendered += &format!(
+        "\n{0} |\n{1} | {2}\n{0} | {3}{4}",
+        gutter,
+        line_number,
+        line,
+        before,
+        style.underline(&"^".repeat(width))
+    );
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::lexer::Lexer;
+    use crate::runner::Runner;
+    use crate::warning::WarningKind;
+
+    fn error_at(span: Span) -> Error {
+        Error::new(ErrorKind::NameError, "No such keyword: bogus", span)
+    }
+
+    #[test]
+    fn underlines_the_whole_span() {
+        let source = "1 bogus puts";
+
+        assert_eq!(
+            render(&error_at(Span::new(2, 7, 1, 3)), source),
+            concat!(
+                "Unknown keyword error: No such keyword: bogus\n",
+                " --> line 1, column 3\n",
+                "  |\n",
+                "1 | 1 bogus puts\n",
+                "  |   ^^^^^",
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_tabs_so_the_caret_lines_up() {
+        let source = "\t\tbogus";
+
+        assert!(render(&error_at(Span::new(2, 7, 1, 3)), source).ends_with("| \t\t^^^^^"));
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        let source = "\"héllo\" bogus";
+
+        assert!(render(&error_at(Span::new(8, 13, 1, 9)), source).ends_with("|         ^^^^^"));
+    }
+
+    #[test]
+    fn widens_the_gutter_for_long_line_numbers() {
+        let source = "\n".repeat(9) + "bogus";
+
+        assert_eq!(
+            render(&error_at(Span::new(9, 14, 10, 1)), &source),
+            concat!(
+                "Unknown keyword error: No such keyword: bogus\n",
+                "  --> line 10, column 1\n",
+                "   |\n",
+                "10 | bogus\n",
+                "   | ^^^^^",
+            )
+        );
+    }
+
+    #[test]
+    fn lists_the_calls_an_error_happened_in() {
+        let source = concat!(
+            "def bottom { \"bad value\" throw }\n",
+            "def middle { bottom }\n",
+            "def top { 1 middle }\n",
+            "top",
+        );
+        let mut runner = Runner::new();
+        let error = runner
+            .run(Lexer::new(String::from(source)).tokenize().unwrap())
+            .unwrap_err();
+
+        assert_eq!(
+            render(&error, source),
+            concat!(
+                "UserError: bad value\n",
+                " --> line 1, column 26\n",
+                "  |\n",
+                "1 | def bottom { \"bad value\" throw }\n",
+                "  |                          ^^^^^\n",
+                "  = in bottom, called at line 2, column 14\n",
+                "  = in middle, called at line 3, column 13\n",
+                "  = in top, called at line 4, column 1",
+            )
+        );
+    }
+
+    #[test]
+    fn only_underlines_the_first_line_of_a_span() {
+        let source = "\"abc\ndef\" bogus";
+
+        assert!(render(&error_at(Span::new(0, 9, 1, 1)), source).ends_with("\n  | ^^^^"));
+    }
+
+    #[test]
+    fn renders_warnings_under_their_own_headline() {
+        let warning = Warning::new(
+            WarningKind::LeftoverValue,
+            "Value left on the stack when the program ended",
+            Span::new(0, 1, 1, 1),
+        );
+
+        assert_eq!(
+            render_warning(&warning, "1 2 puts"),
+            concat!(
+                "warning: Value left on the stack when the program ended\n",
+                " --> line 1, column 1\n",
+                "  |\n",
+                "1 | 1 2 puts\n",
+                "  | ^",
+            )
+        );
+    }
+}
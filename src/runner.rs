@@ -0,0 +1,5632 @@
+use crate::bignum;
+use crate::config::Config;
+use crate::debugger::{self, Command, Debugger};
+use crate::error::{Error, ErrorKind, Frame};
+use crate::input::Input;
+use crate::interner::{Interner, Symbol};
+use crate::keyword::Keyword;
+use crate::lexer::Lexer;
+use crate::limits::ExecutionLimits;
+use crate::random::Random;
+use crate::span::Span;
+use crate::template::{self, Piece};
+use crate::token::{Token, TokenType};
+use crate::trace::{self, TraceLevel};
+use crate::warning::{Warning, WarningKind};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, stdin, stdout, Write};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process::{Command as Process, Stdio};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use strum::IntoEnumIterator;
+
+/// How deeply calls, blocks and evals can nest, all together, before the
+/// runner gives up. Each one nests a few Rust stack frames, so this stays well
+/// inside the 2 MiB stack of a spawned thread.
+const MAX_CALL_DEPTH: usize = 250;
+
+/// The most digits after the point floats can be printed with, the same as a
+/// `format` placeholder can ask for.
+pub const MAX_FLOAT_PRECISION: usize = template::MAX_PRECISION;
+
+/// Words written in the language itself, loaded by
+/// [`Runner::with_prelude`].
+const PRELUDE: &str = include_str!("prelude.plang");
+
+/// A word defined with `def`.
+#[derive(Debug)]
+pub struct Word {
+    /// The names the arguments are bound to, with the last one taken from the
+    /// top of the stack.
+    pub params: Vec<Rc<str>>,
+    pub body: Vec<Token>,
+    /// The interned `params`, which the arguments are stored under.
+    param_symbols: Vec<Symbol>,
+}
+
+/// One `PATTERN { CODE }` of a `match`.
+struct Arm {
+    /// The value to compare with, or `None` for `_`, which matches anything.
+    pattern: Option<Token>,
+    code: Vec<Token>,
+}
+
+/// The parts of a `try`.
+struct Try {
+    body: Vec<Token>,
+    /// The variable the handler gets the error's message in, or `None` to
+    /// have it pushed.
+    binding: Option<Symbol>,
+    handler: Vec<Token>,
+}
+
+/// What `map`, `filter` and `count` run on each element: a function value
+/// from the stack, or a `{ }` block written after the keyword.
+enum Callback {
+    Function(Token),
+    Block(Vec<Token>),
+}
+
+impl Callback {
+    /// What to call the callback in errors.
+    fn describe(&self) -> String {
+        match self {
+            Callback::Function(function) => function.to_string(),
+            Callback::Block(_) => String::from("The block"),
+        }
+    }
+}
+
+/// A function written in Rust that programs can call by name, registered with
+/// [`Runner::register_fn`].
+type NativeFn = Rc<dyn Fn(&[Token]) -> Result<Token, String>>;
+
+struct Native {
+    arity: usize,
+    function: NativeFn,
+}
+
+/// What [`Runner::stats`] counts runs of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Operation {
+    /// A keyword, word, variable or registered function.
+    Name(Symbol),
+    /// Any other token, such as an operator or a value.
+    Token(TokenType),
+}
+
+/// Evaluates lists of tokens.
+///
+/// Tokens run from left to right: values are pushed onto the token stack and
+/// operators and keywords pop their operands back off it, so the result of any
+/// operation can feed any other. The stack, words and variables are kept
+/// between calls to [`run`](Runner::run).
+pub struct Runner {
+    program: VecDeque<Token>,
+    token_stack: Vec<Token>,
+    out: Box<dyn Write>,
+    input: Box<dyn Input>,
+    current_span: Span,
+    warnings: Vec<Warning>,
+    limits: ExecutionLimits,
+    steps: usize,
+    /// How long `gets` has spent waiting for input altogether.
+    waiting: Duration,
+    /// What `args` pushes: the arguments the program was given.
+    args: Vec<Rc<str>>,
+    /// Where `rand` and `randint` get their numbers.
+    random: Random,
+    /// Which steps to describe and where, if tracing.
+    trace: Option<(TraceLevel, Box<dyn Write>)>,
+    /// When to pause for commands, if debugging.
+    debugger: Option<Debugger>,
+    /// Whether programs may read and write files.
+    file_access: bool,
+    /// Whether programs may read environment variables.
+    env_access: bool,
+    /// Whether programs may run other programs with `exec`.
+    process_access: bool,
+    /// The exit status of the last program `exec` ran, if it had one.
+    last_status: Option<i32>,
+    /// Whether ints that outgrow an `i64` carry on as bignums.
+    big_ints: bool,
+    /// How many digits after the point `puts` and `format` show floats with,
+    /// if not as many as they need.
+    float_precision: Option<usize>,
+    /// The files being run, the innermost include last.
+    files: Vec<PathBuf>,
+    /// How many `eval`s are running inside one another.
+    evals: usize,
+    /// How many blocks of `if`, `try`, `match` and `time` are running inside
+    /// one another.
+    blocks: usize,
+    /// Whether the prelude is loaded, and so loaded again by `reset`.
+    prelude: bool,
+    /// Every name the runner has seen, so the tables below can be keyed on
+    /// symbols rather than text.
+    interner: Interner,
+    words: HashMap<Symbol, Word>,
+    natives: HashMap<Symbol, Native>,
+    /// How many times each operation has run, if counting is on.
+    stats: Option<HashMap<Operation, usize>>,
+    /// The global variables, then the variables of each word being run.
+    scopes: Vec<HashMap<Symbol, Token>>,
+}
+
+impl Default for Runner {
+    fn default() -> Runner {
+        Runner::new()
+    }
+}
+
+impl Runner {
+    /// Creates a runner that prints to stdout and reads from stdin, with the
+    /// [default](Config::default) settings.
+    pub fn new() -> Runner {
+        Runner::from_config(Config::default())
+    }
+
+    /// Creates a runner that prints to stdout and reads from stdin, set up as
+    /// `config` says. The prelude, if wanted, is loaded straight away.
+    pub fn from_config(config: Config) -> Runner {
+        let runner = Runner {
+            program: VecDeque::new(),
+            token_stack: Vec::new(),
+            out: Box::new(stdout()),
+            input: Box::new(stdin()),
+            current_span: Span::default(),
+            warnings: Vec::new(),
+            limits: config.limits,
+            steps: 0,
+            waiting: Duration::ZERO,
+            args: Vec::new(),
+            random: Random::unseeded(),
+            trace: None,
+            debugger: None,
+            file_access: true,
+            env_access: true,
+            process_access: false,
+            last_status: None,
+            big_ints: config.big_ints,
+            float_precision: config
+                .float_precision
+                .map(|digits| digits.min(MAX_FLOAT_PRECISION)),
+            files: Vec::new(),
+            evals: 0,
+            blocks: 0,
+            prelude: false,
+            interner: Interner::new(),
+            words: HashMap::new(),
+            natives: HashMap::new(),
+            stats: config.stats.then(HashMap::new),
+            scopes: vec![HashMap::new()],
+        };
+        if config.prelude {
+            runner.with_prelude()
+        } else {
+            runner
+        }
+    }
+
+    /// The settings the runner has now, however they were set.
+    pub fn config(&self) -> Config {
+        Config {
+            big_ints: self.big_ints,
+            limits: self.limits,
+            prelude: self.prelude,
+            stats: self.stats.is_some(),
+            float_precision: self.float_precision,
+        }
+    }
+
+    /// Sends everything programs print to `out` instead of stdout.
+    pub fn with_output(mut self, out: Box<dyn Write>) -> Runner {
+        self.out = out;
+        self
+    }
+
+    /// Reads lines for `gets` from `input` instead of stdin.
+    pub fn with_input(mut self, input: Box<dyn Input>) -> Runner {
+        self.input = input;
+        self
+    }
+
+    /// Gives programs `args` as the arguments `args` pushes. They have none
+    /// otherwise.
+    pub fn with_args(mut self, args: impl IntoIterator<Item = String>) -> Runner {
+        self.args = args.into_iter().map(Rc::from).collect();
+        self
+    }
+
+    /// Describes each step at `level` to `out` before taking it: its number,
+    /// what it runs, the stack and the variables it can see. A step is one
+    /// token, counted across every word the program calls.
+    pub fn with_trace(mut self, level: TraceLevel, out: Box<dyn Write>) -> Runner {
+        self.trace = Some((level, out));
+        self
+    }
+
+    /// Lets programs read and write files only if `allowed`, as they can by
+    /// default. Without it `slurp`, `spit`, `append` and `include` fail with
+    /// a capability error, so untrusted programs can't touch the file system.
+    pub fn with_file_access(mut self, allowed: bool) -> Runner {
+        self.file_access = allowed;
+        self
+    }
+
+    /// Lets programs read environment variables only if `allowed`, as they
+    /// can by default. Without it `getenv` and `env` fail with a capability
+    /// error, so untrusted programs can't see secrets kept there.
+    pub fn with_env_access(mut self, allowed: bool) -> Runner {
+        self.env_access = allowed;
+        self
+    }
+
+    /// Lets programs run other programs with `exec` only if `allowed`. They
+    /// can't by default, since a program that can run others can do anything
+    /// the user can.
+    pub fn with_process_access(mut self, allowed: bool) -> Runner {
+        self.process_access = allowed;
+        self
+    }
+
+    /// Runs programs [one step at a time](Runner::set_debugging).
+    pub fn with_debugger(mut self) -> Runner {
+        self.set_debugging(true);
+        self
+    }
+
+    /// Turns the debugger on or off. While it's on, each run pauses before
+    /// its first step and reads commands from the input, after a `(debug) `
+    /// prompt, until one carries on:
+    ///
+    /// - `s` (or an empty line) runs the next step and pauses again.
+    /// - `c` carries on until a breakpoint's line is reached.
+    /// - `b LINE` sets a breakpoint on a line.
+    /// - `p NAME` shows a variable, and `stack` shows the stack.
+    /// - `q` stops the program with an error `try` can't catch.
+    ///
+    /// Steps are counted as for [tracing](Runner::with_trace). Once the
+    /// input runs out, the program runs to the end without pausing.
+    pub fn set_debugging(&mut self, on: bool) {
+        self.debugger = on.then(Debugger::new);
+    }
+
+    /// Holds programs to `limits`, so untrusted ones can't run forever or use
+    /// up all the memory.
+    pub fn with_limits(mut self, limits: ExecutionLimits) -> Runner {
+        self.limits = limits;
+        self
+    }
+
+    /// Stops any single [`run`](Runner::run) with an error once it has run
+    /// `limit` tokens, like [`ExecutionLimits::max_steps`].
+    pub fn with_step_limit(mut self, limit: usize) -> Runner {
+        self.limits.max_steps = Some(limit);
+        self
+    }
+
+    /// Makes ints exact at any size: `+` and `*` switch to slower digit by
+    /// digit arithmetic instead of overflowing once a value outgrows an
+    /// `i64`. Source run by the REPL and [`run`](crate::run) may then hold
+    /// int literals of any size too.
+    pub fn with_big_ints(mut self) -> Runner {
+        self.big_ints = true;
+        self
+    }
+
+    /// Whether ints are exact at any size, as set by
+    /// [`with_big_ints`](Runner::with_big_ints).
+    pub fn big_ints(&self) -> bool {
+        self.big_ints
+    }
+
+    /// Prints floats with `puts` and `format`'s `{}` with `precision` digits
+    /// after the point, or as many as they need for `None`, the default.
+    /// Floats inside lists are printed the same way. Only printing changes;
+    /// the floats themselves keep every digit. At most
+    /// [`MAX_FLOAT_PRECISION`] digits are shown.
+    pub fn with_float_precision(mut self, precision: Option<usize>) -> Runner {
+        self.float_precision = precision.map(|digits| digits.min(MAX_FLOAT_PRECISION));
+        self
+    }
+
+    /// Runs programs as the contents of the file at `path`, so `include` looks
+    /// for files next to it and won't include it again. Without this,
+    /// programs include files relative to the current directory.
+    pub fn with_source_file(mut self, path: impl AsRef<Path>) -> Runner {
+        self.files = vec![canonical(path.as_ref())];
+        self
+    }
+
+    /// Defines the prelude's words, such as `max`, `min` and `square`, which
+    /// are written in the language itself. Call this before registering any
+    /// function that shares a name with one of them.
+    ///
+    /// # Panics
+    ///
+    /// If the prelude fails to run, which is a bug in the interpreter rather
+    /// than in anything the caller did.
+    pub fn with_prelude(mut self) -> Runner {
+        self.prelude = true;
+        self.load_prelude();
+        self
+    }
+
+    fn load_prelude(&mut self) {
+        // Limits and the debugger are for the caller's programs, so don't let
+        // them break or pause it.
+        let limits = std::mem::take(&mut self.limits);
+        let debugger = self.debugger.take();
+        let program = Lexer::new(String::from(PRELUDE))
+            .tokenize()
+            .and_then(|tokens| self.run(tokens));
+        self.limits = limits;
+        self.debugger = debugger;
+
+        if let Err(error) = program {
+            panic!(
+                "The prelude is broken, which is a bug in ProgLang: {}",
+                error
+            );
+        }
+        // Only count what the caller's programs do.
+        if let Some(stats) = &mut self.stats {
+            stats.clear();
+        }
+    }
+
+    /// Counts every operation that runs, for [`stats`](Runner::stats).
+    pub fn with_stats(mut self) -> Runner {
+        self.stats = Some(HashMap::new());
+        self
+    }
+
+    /// How many times each operation has run since counting started: keywords,
+    /// words and registered functions by name, everything else by token type,
+    /// e.g. `Plus` or `Int`. Empty unless counting was turned on with
+    /// [`with_stats`](Runner::with_stats).
+    pub fn stats(&self) -> HashMap<String, usize> {
+        let Some(stats) = &self.stats else {
+            return HashMap::new();
+        };
+
+        stats
+            .iter()
+            .map(|(operation, &count)| {
+                let name = match *operation {
+                    Operation::Name(symbol) => self.interner.name(symbol).to_string(),
+                    Operation::Token(t_type) => t_type.to_string(),
+                };
+                (name, count)
+            })
+            .collect()
+    }
+
+    /// How long `gets` has spent waiting for input since the runner was
+    /// made, which timing a program should leave out.
+    pub fn time_waiting(&self) -> Duration {
+        self.waiting
+    }
+
+    /// Whether operations are being counted.
+    pub fn counts_stats(&self) -> bool {
+        self.stats.is_some()
+    }
+
+    /// Lets programs call `function` by `name`. Calling it pops `arity`
+    /// arguments, which `function` gets in the order they were pushed, and
+    /// pushes what it returns. An `Err` message becomes a runtime error at the
+    /// call.
+    ///
+    /// Registering a keyword's name, or a name that is already registered, is
+    /// an error, and programs can't redefine registered names either.
+    ///
+    /// ```
+    /// use proglang::{Runner, Token, TokenType};
+    ///
+    /// let runner = Runner::new()
+    ///     .register_fn("shout", 1, |args| {
+    ///         let text = args[0].token_value.to_uppercase();
+    ///         Ok(Token::new(TokenType::String, text))
+    ///     })
+    ///     .unwrap();
+    /// # let _ = runner;
+    /// ```
+    pub fn register_fn(
+        mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&[Token]) -> Result<Token, String> + 'static,
+    ) -> Result<Runner, Error> {
+        let problem = if name.is_empty() || name.chars().any(char::is_whitespace) {
+            Some(format!("{:?} can't be used as a name", name))
+        } else if Keyword::from_str(name).is_ok() {
+            Some(format!("{} is a keyword and can't be registered", name))
+        } else if self
+            .interner
+            .get(name)
+            .is_some_and(|symbol| self.natives.contains_key(&symbol))
+        {
+            Some(format!("{} is already registered", name))
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            return Err(Error::new(ErrorKind::NameError, &problem, Span::default()));
+        }
+
+        let symbol = self.interner.intern(name);
+        self.natives.insert(
+            symbol,
+            Native {
+                arity,
+                function: Rc::new(function),
+            },
+        );
+        Ok(self)
+    }
+
+    /// Shows `prompt` on the output, then reads a line from the input the way
+    /// `gets` would. The input is [offered](Input::offer_names) every name
+    /// first.
+    pub fn prompt(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        self.input.offer_names(self.names());
+        self.input.prompt(prompt, &mut *self.out)?;
+        self.input.read_line()
+    }
+
+    /// Sends further output to the same place programs print to.
+    pub fn output(&mut self) -> &mut dyn Write {
+        &mut *self.out
+    }
+
+    /// The values on the stack, bottom first.
+    pub fn stack(&self) -> &[Token] {
+        &self.token_stack
+    }
+
+    /// The words defined so far, by name.
+    pub fn words(&self) -> BTreeMap<Rc<str>, &Word> {
+        self.by_name(&self.words)
+    }
+
+    /// The global variables defined so far, by name.
+    pub fn variables(&self) -> BTreeMap<Rc<str>, &Token> {
+        self.by_name(&self.scopes[0])
+    }
+
+    /// Every keyword, registered function, word and global variable, sorted.
+    pub fn names(&self) -> Vec<Rc<str>> {
+        let mut names: Vec<Rc<str>> = Keyword::iter().map(|k| Rc::from(k.to_string())).collect();
+        let defined = self
+            .natives
+            .keys()
+            .chain(self.words.keys())
+            .chain(self.scopes[0].keys());
+        names.extend(defined.map(|&symbol| Rc::clone(self.interner.name(symbol))));
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn by_name<'a, T>(&self, table: &'a HashMap<Symbol, T>) -> BTreeMap<Rc<str>, &'a T> {
+        table
+            .iter()
+            .map(|(&symbol, value)| (Rc::clone(self.interner.name(symbol)), value))
+            .collect()
+    }
+
+    /// Forgets every value, word and variable, leaving the runner as it was
+    /// when created apart from its output, input, limits, arguments and
+    /// registered functions. The prelude is loaded again if it was loaded before.
+    pub fn reset(&mut self) {
+        self.token_stack.clear();
+        self.words.clear();
+        self.scopes = vec![HashMap::new()];
+        self.warnings.clear();
+        self.last_status = None;
+        if let Some(stats) = &mut self.stats {
+            stats.clear();
+        }
+        if self.prelude {
+            self.load_prelude();
+        }
+    }
+
+    /// Runs every token in `program`.
+    pub fn run(&mut self, mut program: Vec<Token>) -> Result<(), Error> {
+        for token in &mut program {
+            self.intern(token);
+        }
+
+        self.steps = 0;
+        if let Some(debugger) = &mut self.debugger {
+            debugger.restart();
+        }
+        self.run_all(VecDeque::from(program))
+    }
+
+    /// Runs every token in `program`, the contents of the file at `path`,
+    /// as [`with_source_file`](Runner::with_source_file) would. Later runs
+    /// go back to whichever file they were from before.
+    pub fn run_file(&mut self, path: impl AsRef<Path>, program: Vec<Token>) -> Result<(), Error> {
+        let outer = std::mem::replace(&mut self.files, vec![canonical(path.as_ref())]);
+        let result = self.run(program);
+        self.files = outer;
+        result
+    }
+
+    /// Gives a keyword token its symbol, and the text every other use of that
+    /// name shares.
+    fn intern(&mut self, token: &mut Token) -> Symbol {
+        if let Some(symbol) = token.symbol {
+            return symbol;
+        }
+
+        let symbol = self.interner.intern(&token.token_value);
+        token.token_value = Rc::clone(self.interner.name(symbol));
+        token.symbol = Some(symbol);
+        symbol
+    }
+
+    /// Runs `program` in place of whatever is running now, then picks up
+    /// where that left off.
+    fn run_all(&mut self, program: VecDeque<Token>) -> Result<(), Error> {
+        let outer = std::mem::replace(&mut self.program, program);
+        let result = self.run_program();
+        self.program = outer;
+        result
+    }
+
+    fn run_program(&mut self) -> Result<(), Error> {
+        while let Some(token) = self.program.pop_front() {
+            self.current_span = token.span;
+            self.steps += 1;
+            if self
+                .limits
+                .max_steps
+                .is_some_and(|limit| self.steps > limit)
+            {
+                return Err(self.error(
+                    ErrorKind::LimitExceeded,
+                    &format!("Gave up after {} steps", self.steps - 1),
+                ));
+            }
+            if self.trace.is_some() {
+                self.trace_step(&token);
+            }
+            if self.debugger.is_some() {
+                self.debug_step(&token)?;
+            }
+            self.execute(token)?;
+            self.check_limits()?;
+        }
+        Ok(())
+    }
+
+    /// Describes the step about to run `token`, if it's one being traced.
+    fn trace_step(&mut self, token: &Token) {
+        let Some((level, _)) = &self.trace else {
+            return;
+        };
+        let call = token.token_type == TokenType::Keyword
+            && self.interner.get(&token.token_value).is_some_and(|name| {
+                self.words.contains_key(&name) || self.natives.contains_key(&name)
+            });
+        if *level == TraceLevel::Calls && !call {
+            return;
+        }
+
+        let mut variables = self.by_name(&self.scopes[0]);
+        if self.scopes.len() > 1 {
+            variables.extend(self.by_name(self.scopes.last().unwrap()));
+        }
+        let line = trace::line(self.steps, token, &self.token_stack, &variables);
+        if let Some((_, out)) = &mut self.trace {
+            let _ = writeln!(out, "{}", line);
+        }
+    }
+
+    /// Pauses before running `token` if the debugger says to, then runs
+    /// commands until one carries on.
+    fn debug_step(&mut self, token: &Token) -> Result<(), Error> {
+        let Some(mut debugger) = self.debugger.take() else {
+            return Ok(());
+        };
+        if !debugger.pauses_at(token.span) {
+            self.debugger = Some(debugger);
+            return Ok(());
+        }
+
+        let _ = writeln!(self.out, "{}: {}", token.span, trace::operation(token));
+        loop {
+            let line = match self.prompt("(debug) ") {
+                Ok(Some(line)) => line,
+                // With nothing left to read, the debugger stays off.
+                _ => return Ok(()),
+            };
+            let reply = match line.parse() {
+                Ok(Command::Step) => {
+                    debugger.step();
+                    break;
+                }
+                Ok(Command::Continue) => {
+                    debugger.resume();
+                    break;
+                }
+                Ok(Command::Break(line)) => {
+                    debugger.break_at(line);
+                    format!("Breakpoint on line {}", line)
+                }
+                Ok(Command::Print(name)) => self.describe_variable(&name),
+                Ok(Command::Stack) => {
+                    let values: Vec<String> = self.token_stack.iter().map(trace::show).collect();
+                    format!("[{}]", values.join(", "))
+                }
+                Ok(Command::Quit) => {
+                    self.debugger = Some(debugger);
+                    return Err(self.error(ErrorKind::Stopped, "Stopped from the debugger"));
+                }
+                Ok(Command::Help) => String::from(debugger::HELP),
+                Err(message) => format!("{}\n{}", message, debugger::HELP),
+            };
+            let _ = writeln!(self.out, "{}", reply);
+        }
+        self.debugger = Some(debugger);
+        Ok(())
+    }
+
+    /// The variable called `name` that the running code can see, the way
+    /// `:words` shows variables.
+    fn describe_variable(&self, name: &str) -> String {
+        let value = self.interner.get(name).and_then(|symbol| {
+            let scope = self.scopes.last().unwrap();
+            scope.get(&symbol).or_else(|| self.scopes[0].get(&symbol))
+        });
+        match value {
+            Some(value) => format!("{}: {} = {}", name, value.token_type, value.token_value),
+            None => format!("No variable called {}", name),
+        }
+    }
+
+    /// Checks the stack against the limits after a token has run. A token
+    /// pushes at most one new value, which is then on top, so only that one
+    /// needs its size checked; copies such as `dupn`'s were checked already.
+    fn check_limits(&self) -> Result<(), Error> {
+        if let Some(limit) = self.limits.max_stack_depth {
+            if self.token_stack.len() > limit {
+                return Err(self.error(
+                    ErrorKind::LimitExceeded,
+                    &format!("The stack grew past {} values", limit),
+                ));
+            }
+        }
+        if let (Some(limit), Some(top)) = (self.limits.max_value_size, self.token_stack.last()) {
+            if top.token_value.len() > limit {
+                return Err(self.error(
+                    ErrorKind::LimitExceeded,
+                    &format!("A value grew past {} bytes", limit),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Warns about every value still on the stack, pointing at the code that
+    /// pushed it. Call this once the whole program has run.
+    pub fn warn_about_leftovers(&mut self) {
+        for token in &self.token_stack {
+            self.warnings.push(Warning::new(
+                WarningKind::LeftoverValue,
+                "Value left on the stack when the program ended",
+                token.span,
+            ));
+        }
+    }
+
+    /// Hands over the warnings raised since the last call.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn execute(&mut self, mut token: Token) -> Result<(), Error> {
+        if self.stats.is_some() {
+            let operation = match token.token_type {
+                TokenType::Keyword => Operation::Name(self.intern(&mut token)),
+                t_type => Operation::Token(t_type),
+            };
+            if let Some(stats) = &mut self.stats {
+                *stats.entry(operation).or_insert(0) += 1;
+            }
+        }
+
+        match token.token_type {
+            TokenType::Int
+            | TokenType::Float
+            | TokenType::String
+            | TokenType::Bool
+            | TokenType::Nil
+            | TokenType::List
+            | TokenType::Function => {
+                self.token_stack.push(token);
+                Ok(())
+            }
+            TokenType::Plus => self.add(),
+            TokenType::Multiply => self.multiply(),
+            TokenType::Equal | TokenType::EqualEqual => self.equality(true),
+            TokenType::NotEqual => self.equality(false),
+            TokenType::Less => self.compare(Ordering::is_lt),
+            TokenType::LessEqual => self.compare(Ordering::is_le),
+            TokenType::Greater => self.compare(Ordering::is_gt),
+            TokenType::GreaterEqual => self.compare(Ordering::is_ge),
+            TokenType::ShiftLeft => self.shift("<<", i64::checked_shl),
+            TokenType::ShiftRight => self.shift(">>", i64::checked_shr),
+            TokenType::Keyword => self.handle_keyword(token),
+            TokenType::LeftBrace | TokenType::RightBrace => Err(self.error(
+                ErrorKind::Syntax,
+                "Blocks can only follow def or a pattern in a match",
+            )),
+        }
+    }
+
+    /// Creates an error pointing at the token currently being run.
+    fn error(&self, kind: ErrorKind, message: &str) -> Error {
+        Error::new(kind, message, self.current_span)
+    }
+
+    fn handle_keyword(&mut self, mut token: Token) -> Result<(), Error> {
+        let keyword = match Keyword::from_str(&token.token_value) {
+            Ok(keyword) => keyword,
+            Err(_) => {
+                let name = self.intern(&mut token);
+                return self.handle_name(name);
+            }
+        };
+
+        match keyword {
+            Keyword::Puts => self.puts(),
+            Keyword::Now => self.now(),
+            Keyword::Time => self.time(),
+            Keyword::Getenv => self.getenv(),
+            Keyword::Env => self.env(),
+            Keyword::Args => {
+                let args = self
+                    .args
+                    .iter()
+                    .map(|arg| Token::new(TokenType::String, Rc::clone(arg)))
+                    .collect();
+                self.token_stack
+                    .push(Token::list(args).at(self.current_span));
+                Ok(())
+            }
+            Keyword::Exec => self.exec(),
+            Keyword::LastStatus => {
+                match self.last_status {
+                    Some(status) => self.push(TokenType::Int, status.to_string()),
+                    None => self.push(TokenType::Nil, "nil"),
+                }
+                Ok(())
+            }
+            Keyword::Gets => self.gets(),
+            Keyword::Slurp | Keyword::ReadFile => self.slurp(keyword),
+            Keyword::Spit | Keyword::WriteFile => self.spit(keyword),
+            Keyword::Append | Keyword::AppendFile => self.append(keyword),
+            Keyword::Fdiv => self.fdiv(),
+            Keyword::Divisible => self.divisible(),
+            Keyword::Gcd => self.common(false),
+            Keyword::Lcm => self.common(true),
+            Keyword::IsPrime => self.is_prime(),
+            Keyword::Fact => self.fact(),
+            Keyword::Floor => self.whole("floor", f64::floor),
+            Keyword::Ceil => self.whole("ceil", f64::ceil),
+            Keyword::Round => self.whole("round", f64::round),
+            Keyword::Sign => self.sign(),
+            Keyword::Abs => self.abs(),
+            Keyword::Clamp => self.clamp(),
+            Keyword::Sin => self.math("sin", f64::sin),
+            Keyword::Cos => self.math("cos", f64::cos),
+            Keyword::Tan => self.math("tan", f64::tan),
+            Keyword::Ln => self.logarithm("ln", f64::ln),
+            Keyword::Log10 => self.logarithm("log10", f64::log10),
+            Keyword::Exp => self.math("exp", f64::exp),
+            Keyword::Pi => {
+                self.push(TokenType::Float, std::f64::consts::PI.to_string());
+                Ok(())
+            }
+            Keyword::E => {
+                self.push(TokenType::Float, std::f64::consts::E.to_string());
+                Ok(())
+            }
+            Keyword::Rand => {
+                let float = self.random.next_float();
+                self.push(TokenType::Float, float.to_string());
+                Ok(())
+            }
+            Keyword::Randint => self.randint(),
+            Keyword::Seed => self.seed(),
+            Keyword::True => {
+                self.push(TokenType::Bool, "true");
+                Ok(())
+            }
+            Keyword::False => {
+                self.push(TokenType::Bool, "false");
+                Ok(())
+            }
+            Keyword::Collect => self.collect(),
+            Keyword::Pick => self.pick(),
+            Keyword::Roll => self.roll(),
+            Keyword::Dupn => self.dupn(),
+            Keyword::Empty => {
+                let empty = self.token_stack.is_empty();
+                self.push(TokenType::Bool, empty.to_string());
+                Ok(())
+            }
+            Keyword::Len => self.len(),
+            Keyword::Count => self.count(),
+            Keyword::Map => self.map(),
+            Keyword::Filter => self.filter(),
+            Keyword::Repeat => self.repeat(),
+            Keyword::Trim => self.map_string("trim", str::trim),
+            Keyword::TrimStart => self.map_string("trim_start", str::trim_start),
+            Keyword::TrimEnd => self.map_string("trim_end", str::trim_end),
+            Keyword::Contains => self.test_strings("contains", |a, b| a.contains(b)),
+            Keyword::StartsWith => self.test_strings("starts_with", |a, b| a.starts_with(b)),
+            Keyword::EndsWith => self.test_strings("ends_with", |a, b| a.ends_with(b)),
+            Keyword::Replace => self.replace(),
+            Keyword::Indexof => self.indexof(),
+            Keyword::Format => self.format(),
+            Keyword::Sort => self.sort(),
+            Keyword::Unique => self.unique(),
+            Keyword::Sum => self.aggregate("sum", "0", Runner::add),
+            Keyword::Product => self.aggregate("product", "1", Runner::multiply),
+            Keyword::Select => self.select(),
+            Keyword::Def => self.def(),
+            Keyword::Let => self.let_(),
+            Keyword::Fn => self.fn_(),
+            Keyword::Call => {
+                let function = self.pop_of_type(TokenType::Function, "call")?;
+                self.call_function(&function)
+            }
+            Keyword::Include => self.include(),
+            Keyword::Eval => self.eval(),
+            Keyword::Throw => {
+                let message = self.pop_of_type(TokenType::String, "throw")?.token_value;
+                Err(self.error(ErrorKind::User, &message))
+            }
+            Keyword::Raise => self.raise(),
+            Keyword::Try => self.try_(),
+            Keyword::Catch => Err(self.error(ErrorKind::Syntax, "catch can only follow try")),
+            Keyword::If => self.if_(),
+            Keyword::Else => Err(self.error(ErrorKind::Syntax, "else can only follow if")),
+            Keyword::Match => self.match_(),
+            Keyword::End => Err(self.error(
+                ErrorKind::Syntax,
+                "end can only close a try, an if or a match",
+            )),
+        }
+    }
+
+    /// Pushes the variable called `name` or calls the word called `name`.
+    fn handle_name(&mut self, name: Symbol) -> Result<(), Error> {
+        let scope = self.scopes.last().unwrap();
+        if let Some(value) = scope.get(&name).or_else(|| self.scopes[0].get(&name)) {
+            let value = value.clone();
+            self.token_stack.push(value);
+            return Ok(());
+        }
+
+        self.call_named(name, None)
+    }
+
+    /// Calls the registered function or word called `name`, whether or not a
+    /// variable has the same name. A word starts with `captured` as its
+    /// variables, under its parameters, and leaves them as its `let`s set
+    /// them for the next call.
+    fn call_named(
+        &mut self,
+        name: Symbol,
+        captured: Option<&RefCell<Vec<(Symbol, Token)>>>,
+    ) -> Result<(), Error> {
+        if let Some(native) = self.natives.get(&name) {
+            let arity = native.arity;
+            let function = Rc::clone(&native.function);
+            return self.call_native(arity, function);
+        }
+
+        let word = match self.words.get(&name) {
+            Some(word) => word,
+            None => {
+                return Err(self.error(
+                    ErrorKind::NameError,
+                    &format!("No such keyword: {}", self.interner.name(name))[..],
+                ))
+            }
+        };
+        let params = word.param_symbols.clone();
+        let body: VecDeque<Token> = word.body.iter().cloned().collect();
+
+        if self.nesting() >= MAX_CALL_DEPTH {
+            return Err(self.error(
+                ErrorKind::Recursion,
+                &format!(
+                    "Too many nested calls while calling {}",
+                    self.interner.name(name)
+                ),
+            ));
+        }
+        if let Some(limit) = self.limits.max_call_depth {
+            if self.scopes.len() > limit {
+                return Err(self.error(
+                    ErrorKind::LimitExceeded,
+                    &format!("Calls nested more than {} deep", limit),
+                ));
+            }
+        }
+
+        let mut locals: HashMap<Symbol, Token> = captured
+            .map(|captured| captured.borrow().iter().cloned().collect())
+            .unwrap_or_default();
+        for &param in params.iter().rev() {
+            let value = self.pop()?;
+            locals.insert(param, value);
+        }
+
+        let call_site = self.current_span;
+        self.scopes.push(locals);
+        let result = self.run_all(body);
+        let locals = self.scopes.pop().unwrap();
+        if let Some(captured) = captured {
+            for (name, value) in captured.borrow_mut().iter_mut() {
+                if !params.contains(name) {
+                    *value = locals[name].clone();
+                }
+            }
+        }
+        result.map_err(|mut error| {
+            error.trace.push(Frame {
+                name: Rc::clone(self.interner.name(name)),
+                span: call_site,
+            });
+            error
+        })
+    }
+
+    /// Pops `arity` arguments, calls `function` with them and pushes its
+    /// result.
+    fn call_native(&mut self, arity: usize, function: NativeFn) -> Result<(), Error> {
+        if self.token_stack.len() < arity {
+            return Err(self.error(ErrorKind::StackUnderflow, "Not enough values on the stack"));
+        }
+        let args = self.token_stack.split_off(self.token_stack.len() - arity);
+
+        let result = function(&args).map_err(|message| self.error(ErrorKind::Native, &message))?;
+        self.token_stack.push(result.at(self.current_span));
+        Ok(())
+    }
+
+    /// Takes the next token of the program as the name being defined by
+    /// `keyword`, refusing names that are already keywords.
+    fn take_name(&mut self, keyword: &str) -> Result<Symbol, Error> {
+        let mut token = match self.program.pop_front() {
+            Some(token) if token.token_type == TokenType::Keyword => token,
+            _ => {
+                return Err(self.error(
+                    ErrorKind::Syntax,
+                    &format!("{} needs a name after it", keyword),
+                ))
+            }
+        };
+        self.current_span = token.span;
+
+        if Keyword::from_str(&token.token_value).is_ok() {
+            return Err(self.error(
+                ErrorKind::NameError,
+                &format!("{} is a keyword and can't be redefined", token.token_value),
+            ));
+        }
+        let name = self.intern(&mut token);
+        if self.natives.contains_key(&name) {
+            return Err(self.error(
+                ErrorKind::NameError,
+                &format!(
+                    "{} is a registered function and can't be redefined",
+                    token.token_value
+                ),
+            ));
+        }
+        Ok(name)
+    }
+
+    /// Reads `def NAME PARAMS... { BODY }` from the program and stores the
+    /// word, replacing any earlier word with the same name.
+    fn def(&mut self) -> Result<(), Error> {
+        let def_span = self.current_span;
+        let symbol = self.take_name("def")?;
+        let name = Rc::clone(self.interner.name(symbol));
+
+        let mut params = Vec::new();
+        loop {
+            match self.program.front().map(|token| &token.token_type) {
+                Some(TokenType::LeftBrace) => break,
+                Some(TokenType::Keyword) => params.push(self.take_name("def")?),
+                _ => {
+                    self.current_span = def_span;
+                    return Err(self.error(
+                        ErrorKind::Syntax,
+                        &format!("The definition of {} needs a {{ body }}", name),
+                    ));
+                }
+            }
+        }
+        self.program.pop_front();
+
+        let Some(body) = take_block(iter::from_fn(|| self.program.pop_front())) else {
+            self.current_span = def_span;
+            return Err(self.error(
+                ErrorKind::Syntax,
+                &format!("The body of {} is missing its closing }}", name),
+            ));
+        };
+
+        let names = params
+            .iter()
+            .map(|&param| Rc::clone(self.interner.name(param)))
+            .collect();
+        self.words.insert(
+            symbol,
+            Word {
+                params: names,
+                body,
+                param_symbols: params,
+            },
+        );
+        Ok(())
+    }
+
+    /// Pops an error's message and then the name pushed before it, and stops
+    /// with a user error shown under that name. Both are needed; `throw` is
+    /// the one for a message on its own.
+    fn raise(&mut self) -> Result<(), Error> {
+        let message = self.pop_of_type(TokenType::String, "raise")?.token_value;
+        let name = self.pop_of_type(TokenType::String, "raise")?.token_value;
+
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(self.error(
+                ErrorKind::Value,
+                &format!("raise needs an error name without spaces, not {:?}", name),
+            ));
+        }
+        let mut error = self.error(ErrorKind::User, &message);
+        error.label = Some(name);
+        Err(error)
+    }
+
+    /// Reads `try BODY catch HANDLER end`, or `try { BODY } catch NAME {
+    /// HANDLER }`, from the program and runs BODY. If that fails, the stack
+    /// goes back to how it was at the `try` and HANDLER runs with the error's
+    /// message in the variable NAME, or pushed if there is no NAME. Running
+    /// out of a limit or of call depth can't be caught, so limits hold inside
+    /// a `try` too, and neither can being stopped from the debugger.
+    fn try_(&mut self) -> Result<(), Error> {
+        let try_span = self.current_span;
+        let parts = match self.program.front() {
+            Some(token) if token.token_type == TokenType::LeftBrace => self.try_blocks(try_span)?,
+            _ => Try {
+                body: self.take_part(Keyword::Try, try_span, Keyword::Catch)?,
+                binding: None,
+                handler: self.take_part(Keyword::Try, try_span, Keyword::End)?,
+            },
+        };
+
+        let checkpoint = self.token_stack.clone();
+        match self.run_block(parts.body) {
+            Err(error)
+                if !matches!(
+                    error.kind,
+                    ErrorKind::LimitExceeded | ErrorKind::Recursion | ErrorKind::Stopped
+                ) =>
+            {
+                self.token_stack = checkpoint;
+                self.current_span = try_span;
+                let message = Token::new(TokenType::String, error.message).at(try_span);
+                match parts.binding {
+                    Some(name) => {
+                        self.scopes.last_mut().unwrap().insert(name, message);
+                    }
+                    None => self.token_stack.push(message),
+                }
+                self.run_block(parts.handler)
+            }
+            result => result,
+        }
+    }
+
+    /// Reads the `{ BODY } catch NAME { HANDLER }` of a `try` whose body is a
+    /// block, where the NAME can be left out.
+    fn try_blocks(&mut self, try_span: Span) -> Result<Try, Error> {
+        self.program.pop_front();
+        let Some(body) = take_block(iter::from_fn(|| self.program.pop_front())) else {
+            self.current_span = try_span;
+            return Err(self.error(ErrorKind::Syntax, "The body of this try is missing its }"));
+        };
+
+        if !self.program.front().is_some_and(is_catch) {
+            self.current_span = try_span;
+            return Err(self.error(ErrorKind::Syntax, "This try needs a catch after its body"));
+        }
+        let catch_span = self.program.pop_front().unwrap().span;
+
+        let binding = match self.program.front() {
+            Some(token) if token.token_type == TokenType::Keyword => Some(self.take_name("catch")?),
+            _ => None,
+        };
+        let handler = match self.program.pop_front() {
+            Some(token) if token.token_type == TokenType::LeftBrace => {
+                take_block(iter::from_fn(|| self.program.pop_front()))
+            }
+            _ => None,
+        };
+        let Some(handler) = handler else {
+            self.current_span = catch_span;
+            return Err(self.error(ErrorKind::Syntax, "This catch needs a { handler }"));
+        };
+        Ok(Try {
+            body,
+            binding,
+            handler,
+        })
+    }
+
+    /// Reads `if THEN else OTHERWISE end` from the program, then pops a bool
+    /// and runs THEN if it is true or OTHERWISE if it is false. The other
+    /// branch doesn't run at all.
+    fn if_(&mut self) -> Result<(), Error> {
+        let if_span = self.current_span;
+        let then = self.take_part(Keyword::If, if_span, Keyword::Else)?;
+        let otherwise = self.take_part(Keyword::If, if_span, Keyword::End)?;
+
+        let condition = self.pop_of_type(TokenType::Bool, "if")?;
+        let chosen = if &*condition.token_value == "true" {
+            then
+        } else {
+            otherwise
+        };
+        self.run_block(chosen)
+    }
+
+    /// Reads `match PATTERN { CODE } ... end` from the program, then pops a
+    /// value and runs the CODE of the first PATTERN equal to it. A pattern is
+    /// an int, float or string, `true` or `false`, or `_` to match anything.
+    /// Nothing after the first match runs.
+    fn match_(&mut self) -> Result<(), Error> {
+        let match_span = self.current_span;
+        let part = self.take_part(Keyword::Match, match_span, Keyword::End)?;
+        let arms = self.match_arms(part)?;
+
+        let value = self.pop()?;
+        let chosen = arms.into_iter().find(|arm| match &arm.pattern {
+            Some(pattern) => same_value(pattern, &value),
+            None => true,
+        });
+        match chosen {
+            Some(arm) => self.run_block(arm.code),
+            None => Err(self.error(
+                ErrorKind::Value,
+                &format!("Nothing in this match is {}", trace::show(&value)),
+            )),
+        }
+    }
+
+    /// How many calls, blocks and evals are running inside one another, to
+    /// hold against [`MAX_CALL_DEPTH`].
+    fn nesting(&self) -> usize {
+        self.scopes.len() - 1 + self.blocks + self.evals
+    }
+
+    /// Runs the tokens of an `if`, `try`, `match` or `time` block. Each block
+    /// nests a few Rust stack frames, as a call does, so blocks can nest as
+    /// deeply as calls and no deeper.
+    fn run_block(&mut self, block: Vec<Token>) -> Result<(), Error> {
+        if self.nesting() >= MAX_CALL_DEPTH {
+            return Err(self.error(ErrorKind::Recursion, "Too many nested blocks"));
+        }
+        if let Some(limit) = self.limits.max_call_depth {
+            if self.blocks >= limit {
+                return Err(self.error(
+                    ErrorKind::LimitExceeded,
+                    &format!("Blocks nested more than {} deep", limit),
+                ));
+            }
+        }
+
+        self.blocks += 1;
+        let result = self.run_all(VecDeque::from(block));
+        self.blocks -= 1;
+        result
+    }
+
+    /// Splits the tokens of a `match` into its arms.
+    fn match_arms(&mut self, part: Vec<Token>) -> Result<Vec<Arm>, Error> {
+        let mut tokens = part.into_iter();
+        let mut arms = Vec::new();
+        while let Some(token) = tokens.next() {
+            let pattern = match (token.token_type, &*token.token_value) {
+                (TokenType::Int | TokenType::Float | TokenType::String, _) => Some(token.clone()),
+                (TokenType::Keyword, "true" | "false") => {
+                    Some(Token::new(TokenType::Bool, Rc::clone(&token.token_value)))
+                }
+                (TokenType::Keyword, "_") => None,
+                _ => {
+                    self.current_span = token.span;
+                    return Err(self.error(
+                        ErrorKind::Syntax,
+                        &format!(
+                            "Expected a value or _ to match, found {}",
+                            trace::operation(&token)
+                        ),
+                    ));
+                }
+            };
+            if tokens.next().map(|next| next.token_type) != Some(TokenType::LeftBrace) {
+                self.current_span = token.span;
+                return Err(self.error(ErrorKind::Syntax, "This pattern needs a { block } to run"));
+            }
+
+            let Some(code) = take_block(&mut tokens) else {
+                self.current_span = token.span;
+                return Err(self.error(
+                    ErrorKind::Syntax,
+                    "The block of this pattern is missing its closing }",
+                ));
+            };
+            arms.push(Arm { pattern, code });
+        }
+        Ok(arms)
+    }
+
+    /// Takes the tokens of the `try`, `if` or `match` that is `opener` up to
+    /// its `closer`, skipping over any of them nested inside.
+    fn take_part(
+        &mut self,
+        opener: Keyword,
+        opener_span: Span,
+        closer: Keyword,
+    ) -> Result<Vec<Token>, Error> {
+        let mut part = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = match self.program.pop_front() {
+                Some(token) => token,
+                None => {
+                    self.current_span = opener_span;
+                    return Err(self.error(
+                        ErrorKind::Syntax,
+                        &format!("This {} is missing its {}", opener, closer),
+                    ));
+                }
+            };
+            let keyword = match token.token_type {
+                TokenType::Keyword => Keyword::from_str(&token.token_value).ok(),
+                _ => None,
+            };
+            let opens_block = |token: Option<&Token>| {
+                token.is_some_and(|token| token.token_type == TokenType::LeftBrace)
+            };
+            match keyword {
+                // A try with a block for a body has no end, so it's taken
+                // here whole, up to its catch.
+                Some(Keyword::Try) if opens_block(self.program.front()) => {
+                    part.push(token);
+                    let mut braces = 0;
+                    while let Some(token) = self.program.pop_front() {
+                        match token.token_type {
+                            TokenType::LeftBrace => braces += 1,
+                            TokenType::RightBrace => braces -= 1,
+                            _ => {}
+                        }
+                        part.push(token);
+                        if braces == 0 {
+                            break;
+                        }
+                    }
+                    if self.program.front().is_some_and(is_catch) {
+                        part.extend(self.program.pop_front());
+                    }
+                    continue;
+                }
+                Some(Keyword::Try | Keyword::If | Keyword::Match) => depth += 1,
+                Some(Keyword::End) if depth > 0 => depth -= 1,
+                Some(keyword) if keyword == closer && depth == 0 => return Ok(part),
+                Some(Keyword::End) => {
+                    let needed = match closer {
+                        Keyword::Else => "an else",
+                        _ => "a catch",
+                    };
+                    self.current_span = token.span;
+                    return Err(self.error(
+                        ErrorKind::Syntax,
+                        &format!("This {} needs {} before its end", opener, needed),
+                    ));
+                }
+                _ => {}
+            }
+            part.push(token);
+        }
+    }
+
+    /// Pops a value and stores it in the variable named by the next token,
+    /// inside the word being run if there is one.
+    fn let_(&mut self) -> Result<(), Error> {
+        let value = self.pop()?;
+        let name = self.take_name("let")?;
+
+        self.scopes.last_mut().unwrap().insert(name, value);
+        Ok(())
+    }
+
+    /// Pushes the word or registered function named by the next token as a
+    /// function value, without calling it. It's printed with how many
+    /// arguments it takes, like `<function double/1>`. Inside a word, it
+    /// takes that call's variables as they are now, for the word to see and
+    /// change whenever it's called through this value.
+    fn fn_(&mut self) -> Result<(), Error> {
+        let fn_span = self.current_span;
+        let (name, _) = self.take_word("fn")?;
+        let arity = match self.natives.get(&name) {
+            Some(native) => native.arity,
+            None => self.words[&name].params.len(),
+        };
+
+        let mut function = Token::new(
+            TokenType::Function,
+            format!("<function {}/{}>", self.interner.name(name), arity),
+        )
+        .at(fn_span);
+        function.symbol = Some(name);
+        if self.scopes.len() > 1 {
+            let mut captured: Vec<(Symbol, Token)> = self
+                .scopes
+                .last()
+                .unwrap()
+                .iter()
+                .map(|(&name, value)| (name, value.clone()))
+                .collect();
+            captured.sort_by_key(|&(name, _)| name);
+            function.captured = Rc::new(RefCell::new(captured));
+        }
+        self.current_span = fn_span;
+        self.token_stack.push(function);
+        Ok(())
+    }
+
+    /// Calls the word a function value names, as it is defined now, with
+    /// the variables the value captured.
+    fn call_function(&mut self, function: &Token) -> Result<(), Error> {
+        match function.symbol {
+            Some(name) => self.call_named(name, Some(&function.captured)),
+            None => Err(self.error(
+                ErrorKind::NameError,
+                &format!("{} doesn't name a word here", function.token_value),
+            )),
+        }
+    }
+
+    /// Pops a string and runs it as code right where the `eval` is, on the
+    /// same stack and with the same words and variables. Limits count its
+    /// steps along with the rest of the program's.
+    fn eval(&mut self) -> Result<(), Error> {
+        let code = self.pop_of_type(TokenType::String, "eval")?.token_value;
+        let eval_span = self.current_span;
+        if self.nesting() >= MAX_CALL_DEPTH {
+            return Err(self.error(ErrorKind::Recursion, "Too many nested evals"));
+        }
+        if let Some(limit) = self.limits.max_call_depth {
+            if self.evals >= limit {
+                return Err(self.error(
+                    ErrorKind::LimitExceeded,
+                    &format!("Evals nested more than {} deep", limit),
+                ));
+            }
+        }
+
+        let mut lexer = Lexer::new(code.to_string());
+        if self.big_ints {
+            lexer = lexer.with_big_ints();
+        }
+        self.evals += 1;
+        let result = lexer.tokenize().and_then(|mut program| {
+            for token in &mut program {
+                self.intern(token);
+            }
+            self.run_all(VecDeque::from(program))
+        });
+        self.evals -= 1;
+        self.current_span = eval_span;
+
+        // Positions in the string mean nothing against the program's source,
+        // so the error points at the eval and says where in the string it was.
+        // Running out of depth says so once rather than for every eval.
+        result.map_err(|mut error| {
+            if matches!(error.kind, ErrorKind::Recursion | ErrorKind::LimitExceeded)
+                && error.message.starts_with("In eval")
+            {
+                error.span = eval_span;
+                return error;
+            }
+            error.message = format!("In eval, column {}: {}", error.span.column, error.message);
+            error.span = eval_span;
+            error.trace.clear();
+            error
+        })
+    }
+
+    /// Pops a path and runs the file there as if its source were written in
+    /// place, so the words and variables it defines stay defined. A relative
+    /// path is found from the directory of the file doing the including.
+    fn include(&mut self) -> Result<(), Error> {
+        self.need_file_access("include")?;
+        let name = self.pop_of_type(TokenType::String, "include")?.token_value;
+        let include_span = self.current_span;
+
+        let path = match self.files.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.join(&*name),
+            None => PathBuf::from(&*name),
+        };
+        let (contents, path) = fs::read_to_string(&path)
+            .and_then(|contents| Ok((contents, fs::canonicalize(&path)?)))
+            .map_err(|e| {
+                self.error(
+                    ErrorKind::Io,
+                    &format!("Could not include {}: {}", path.display(), e),
+                )
+            })?;
+
+        if let Some(start) = self.files.iter().position(|file| *file == path) {
+            let cycle: Vec<String> = self.files[start..]
+                .iter()
+                .chain([&path])
+                .map(|file| file.display().to_string())
+                .collect();
+            return Err(self.error(
+                ErrorKind::Include,
+                &format!("Include cycle: {}", cycle.join(" -> ")),
+            ));
+        }
+        if self.files.len() + self.nesting() > MAX_CALL_DEPTH {
+            return Err(self.error(ErrorKind::Recursion, "Too many nested includes"));
+        }
+
+        let mut lexer = Lexer::new(contents);
+        if self.big_ints {
+            lexer = lexer.with_big_ints();
+        }
+        self.files.push(path);
+        let result = lexer.tokenize().and_then(|mut program| {
+            for token in &mut program {
+                self.intern(token);
+            }
+            self.run_all(VecDeque::from(program))
+        });
+        let path = self.files.pop().unwrap();
+        self.current_span = include_span;
+
+        result.map_err(|error| {
+            self.error(
+                ErrorKind::Include,
+                &format!(
+                    "In {}, line {}, column {}: {}",
+                    path.display(),
+                    error.span.line,
+                    error.span.column,
+                    error
+                ),
+            )
+        })
+    }
+
+    fn pop(&mut self) -> Result<Token, Error> {
+        match self.token_stack.pop() {
+            Some(token) => Ok(token),
+            None => Err(self.error(ErrorKind::StackUnderflow, "Not enough values on the stack")),
+        }
+    }
+
+    /// Pops a value, raising a type error unless it is a `t_type`.
+    fn pop_of_type(&mut self, t_type: TokenType, keyword: &str) -> Result<Token, Error> {
+        let token = self.pop()?;
+        if token.token_type != t_type {
+            return Err(self.error(
+                ErrorKind::MismatchedTypes,
+                &format!(
+                    "{} expects a {}, found {}",
+                    keyword, t_type, token.token_type
+                ),
+            ));
+        }
+        Ok(token)
+    }
+
+    /// Pushes a value created by the token currently being run.
+    fn push(&mut self, t_type: TokenType, value: impl Into<Rc<str>>) {
+        let token = Token::new(t_type, value).at(self.current_span);
+        self.token_stack.push(token);
+    }
+
+    fn puts(&mut self) -> Result<(), Error> {
+        let valueToPrint = self.pop()?;
+        let text = self.printed(&valueToPrint);
+
+        writeln!(self.out, "{}", text).map_err(|e| self.error(ErrorKind::Io, &e.to_string()))
+    }
+
+    /// How `puts` prints `value`: as the token would print itself, but with
+    /// floats, even inside lists, cut to the float precision if there is one.
+    fn printed(&self, value: &Token) -> String {
+        let Some(precision) = self.float_precision else {
+            return value.to_string();
+        };
+        match value.token_type {
+            TokenType::Float => match value.token_value.parse::<f64>() {
+                Ok(number) if number.is_finite() => format!("{:.*}", precision, number),
+                _ => value.to_string(),
+            },
+            TokenType::List => {
+                let items: Vec<String> = value
+                    .items
+                    .iter()
+                    .map(|item| match item.token_type {
+                        TokenType::String => format!("\"{}\"", item.token_value),
+                        _ => self.printed(item),
+                    })
+                    .collect();
+                format!("[{}]", items.join(", "))
+            }
+            _ => value.to_string(),
+        }
+    }
+
+    /// Reads a line from the input and pushes it as a string, or pushes nil at
+    /// the end of the input.
+    fn gets(&mut self) -> Result<(), Error> {
+        let started = Instant::now();
+        let line = self.input.read_line();
+        self.waiting += started.elapsed();
+
+        match line {
+            Ok(Some(line)) => self.push(TokenType::String, line),
+            Ok(None) => self.push(TokenType::Nil, "nil"),
+            Err(e) => return Err(self.error(ErrorKind::Io, &e.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Pops a path and pushes the contents of that file as a string, for
+    /// `slurp` or `read_file`.
+    fn slurp(&mut self, keyword: Keyword) -> Result<(), Error> {
+        let keyword = keyword.to_string();
+        self.need_file_access(&keyword)?;
+        let path = self.pop_of_type(TokenType::String, &keyword)?.token_value;
+
+        let contents = fs::read_to_string(&*path)
+            .map_err(|e| self.error(ErrorKind::Io, &format!("Could not read {}: {}", path, e)))?;
+
+        self.push(TokenType::String, contents);
+        Ok(())
+    }
+
+    /// Pops a path and then the contents to write there, replacing the file if
+    /// it already exists, for `spit` or `write_file`.
+    fn spit(&mut self, keyword: Keyword) -> Result<(), Error> {
+        let keyword = keyword.to_string();
+        self.need_file_access(&keyword)?;
+        let path = self.pop_of_type(TokenType::String, &keyword)?.token_value;
+        let contents = self.pop_of_type(TokenType::String, &keyword)?.token_value;
+
+        fs::write(&*path, &*contents)
+            .map_err(|e| self.error(ErrorKind::Io, &format!("Could not write {}: {}", path, e)))
+    }
+
+    /// Like `spit`, but adds to the end of the file instead of replacing it,
+    /// for `append` or `append_file`.
+    fn append(&mut self, keyword: Keyword) -> Result<(), Error> {
+        let keyword = keyword.to_string();
+        self.need_file_access(&keyword)?;
+        let path = self.pop_of_type(TokenType::String, &keyword)?.token_value;
+        let contents = self.pop_of_type(TokenType::String, &keyword)?.token_value;
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&*path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .map_err(|e| self.error(ErrorKind::Io, &format!("Could not write {}: {}", path, e)))
+    }
+
+    /// Pops an int n and pushes a copy of the value n down from the top of
+    /// the stack, so 0 copies the top value and 1 the one under it.
+    fn pick(&mut self) -> Result<(), Error> {
+        let depth = self.pop_of_type(TokenType::Int, "pick")?.token_value;
+
+        let picked = depth
+            .parse::<usize>()
+            .ok()
+            .and_then(|depth| self.token_stack.iter().rev().nth(depth));
+        match picked {
+            Some(value) => {
+                let value = value.clone();
+                self.token_stack.push(value);
+                Ok(())
+            }
+            None => Err(self.error(
+                ErrorKind::StackUnderflow,
+                &format!(
+                    "Cannot pick {} down from a stack of {} values",
+                    depth,
+                    self.token_stack.len()
+                ),
+            )),
+        }
+    }
+
+    /// Pops an int n and rotates the top n values of the stack, moving the
+    /// nth one down to the top: `2 roll` swaps the top two values, and
+    /// `3 roll` brings the third one up over the other two.
+    fn roll(&mut self) -> Result<(), Error> {
+        let count = self.pop_of_type(TokenType::Int, "roll")?.token_value;
+
+        let rolled = count
+            .parse::<usize>()
+            .ok()
+            .and_then(|count| self.token_stack.len().checked_sub(count));
+        match rolled {
+            Some(start) => {
+                let values = &mut self.token_stack[start..];
+                if !values.is_empty() {
+                    values.rotate_left(1);
+                }
+                Ok(())
+            }
+            None => Err(self.error(
+                ErrorKind::StackUnderflow,
+                &format!(
+                    "Cannot roll {} values on a stack of {}",
+                    count,
+                    self.token_stack.len()
+                ),
+            )),
+        }
+    }
+
+    /// Pops an int n and pushes copies of the top n values of the stack, in
+    /// the same order, so `2 dupn` turns `1 2 3` into `1 2 3 2 3`.
+    fn dupn(&mut self) -> Result<(), Error> {
+        let count = self.pop_of_type(TokenType::Int, "dupn")?.token_value;
+
+        let start = count
+            .parse::<usize>()
+            .ok()
+            .and_then(|count| self.token_stack.len().checked_sub(count));
+        match start {
+            Some(start) => {
+                self.token_stack.extend_from_within(start..);
+                Ok(())
+            }
+            None => Err(self.error(
+                ErrorKind::StackUnderflow,
+                &format!(
+                    "Cannot copy {} values from a stack of {}",
+                    count,
+                    self.token_stack.len()
+                ),
+            )),
+        }
+    }
+
+    /// Fails unless programs may use files, for `keyword` to call first.
+    fn need_file_access(&self, keyword: &str) -> Result<(), Error> {
+        if self.file_access {
+            return Ok(());
+        }
+        Err(self.error(
+            ErrorKind::Capability,
+            &format!("{} needs file access, which isn't allowed here", keyword),
+        ))
+    }
+
+    /// Fails unless programs may read environment variables, for `keyword` to
+    /// call first.
+    fn need_env_access(&self, keyword: &str) -> Result<(), Error> {
+        if self.env_access {
+            return Ok(());
+        }
+        Err(self.error(
+            ErrorKind::Capability,
+            &format!(
+                "{} needs environment access, which isn't allowed here",
+                keyword
+            ),
+        ))
+    }
+
+    /// Fails unless programs may run other programs, for `keyword` to call
+    /// first.
+    fn need_process_access(&self, keyword: &str) -> Result<(), Error> {
+        if self.process_access {
+            return Ok(());
+        }
+        Err(self.error(
+            ErrorKind::Capability,
+            &format!(
+                "{} needs permission to run programs, which isn't allowed here",
+                keyword
+            ),
+        ))
+    }
+
+    /// Replaces everything on the stack with one list of it, bottom first.
+    fn collect(&mut self) -> Result<(), Error> {
+        let items = std::mem::take(&mut self.token_stack);
+        self.token_stack
+            .push(Token::list(items).at(self.current_span));
+        Ok(())
+    }
+
+    /// Pops a list or string and pushes how many elements or characters it has.
+    fn len(&mut self) -> Result<(), Error> {
+        let value = self.pop()?;
+        let length = match value.token_type {
+            TokenType::List => value.items.len(),
+            TokenType::String => value.token_value.chars().count(),
+            _ => {
+                return Err(self.error(
+                    ErrorKind::MismatchedTypes,
+                    &format!("len expects a List or String, found {}", value.token_type),
+                ))
+            }
+        };
+
+        self.push(TokenType::Int, length.to_string());
+        Ok(())
+    }
+
+    /// The elements of a list, or the characters of a string as strings, for
+    /// `keyword` to go through.
+    fn elements(&self, value: Token, keyword: &str) -> Result<Vec<Token>, Error> {
+        match value.token_type {
+            TokenType::List => Ok(value.items.to_vec()),
+            TokenType::String => Ok(value
+                .token_value
+                .chars()
+                .map(|c| Token::new(TokenType::String, c.to_string()).at(value.span))
+                .collect()),
+            _ => Err(self.error(
+                ErrorKind::MismatchedTypes,
+                &format!(
+                    "{} expects a List or String, found {}",
+                    keyword, value.token_type
+                ),
+            )),
+        }
+    }
+
+    /// Pops a function and then a list or string, and pushes how many of its
+    /// elements or characters the function leaves true for. A block after
+    /// the `count` can stand in for the function.
+    fn count(&mut self) -> Result<(), Error> {
+        let matches = self.kept("count")?.len();
+
+        self.push(TokenType::Int, matches.to_string());
+        Ok(())
+    }
+
+    /// Pops a function and then a list or string, and pushes a list of what
+    /// the function leaves for each element or character in turn. A block
+    /// after the `map` can stand in for the function.
+    fn map(&mut self) -> Result<(), Error> {
+        let function = self.take_callback("map")?;
+        let value = self.pop()?;
+        let items = self.elements(value, "map")?;
+
+        let mut results = Vec::with_capacity(items.len());
+        for item in items {
+            results.push(self.apply(&function, item, "map")?);
+        }
+        self.token_stack
+            .push(Token::list(results).at(self.current_span));
+        Ok(())
+    }
+
+    /// Pops a function and then a list or string, and pushes a list of the
+    /// elements or characters the function leaves true for. A block after
+    /// the `filter` can stand in for the function.
+    fn filter(&mut self) -> Result<(), Error> {
+        let kept = self.kept("filter")?;
+
+        self.token_stack
+            .push(Token::list(kept).at(self.current_span));
+        Ok(())
+    }
+
+    /// Pops a function and then a list or string for `keyword`, and takes the
+    /// elements or characters the function leaves true for.
+    fn kept(&mut self, keyword: &str) -> Result<Vec<Token>, Error> {
+        let function = self.take_callback(keyword)?;
+        let value = self.pop()?;
+        let items = self.elements(value, keyword)?;
+
+        let mut kept = Vec::new();
+        for item in items {
+            let result = self.apply(&function, item.clone(), keyword)?;
+            match (result.token_type, &*result.token_value) {
+                (TokenType::Bool, "true") => kept.push(item),
+                (TokenType::Bool, _) => {}
+                (t_type, _) => {
+                    return Err(self.error(
+                        ErrorKind::MismatchedTypes,
+                        &format!(
+                            "{} should leave a Bool for {}, found {}",
+                            function.describe(),
+                            keyword,
+                            t_type
+                        ),
+                    ))
+                }
+            }
+        }
+        Ok(kept)
+    }
+
+    /// Reads the block after `keyword` if there is one, or else pops the
+    /// function it runs on each element.
+    fn take_callback(&mut self, keyword: &str) -> Result<Callback, Error> {
+        match self.program.front() {
+            Some(token) if token.token_type == TokenType::LeftBrace => {
+                self.program.pop_front();
+                match take_block(iter::from_fn(|| self.program.pop_front())) {
+                    Some(block) => Ok(Callback::Block(block)),
+                    None => Err(self.error(
+                        ErrorKind::Syntax,
+                        &format!("The block after {} is missing its }}", keyword),
+                    )),
+                }
+            }
+            _ => Ok(Callback::Function(
+                self.pop_of_type(TokenType::Function, keyword)?,
+            )),
+        }
+    }
+
+    /// Runs `function` on `item` for `keyword` and takes the one value it
+    /// leaves.
+    fn apply(&mut self, function: &Callback, item: Token, keyword: &str) -> Result<Token, Error> {
+        let span = self.current_span;
+        let depth = self.token_stack.len();
+        self.token_stack.push(item);
+        match function {
+            Callback::Function(function) => self.call_function(function)?,
+            Callback::Block(block) => self.run_block(block.clone())?,
+        }
+        self.current_span = span;
+
+        let left = self.token_stack.len() as isize - depth as isize;
+        if left != 1 {
+            return Err(self.error(
+                ErrorKind::Value,
+                &format!(
+                    "{} should leave one value for {}, not {}",
+                    function.describe(),
+                    keyword,
+                    left
+                ),
+            ));
+        }
+        self.pop()
+    }
+
+    /// Takes the next token as the name of a word or registered function for
+    /// `keyword` to call, and where it is.
+    fn take_word(&mut self, keyword: &str) -> Result<(Symbol, Span), Error> {
+        let mut token = match self.program.pop_front() {
+            Some(token) if token.token_type == TokenType::Keyword => token,
+            _ => {
+                return Err(self.error(
+                    ErrorKind::Syntax,
+                    &format!("{} needs a word after it", keyword),
+                ))
+            }
+        };
+        self.current_span = token.span;
+
+        let name = self.intern(&mut token);
+        if !self.words.contains_key(&name) && !self.natives.contains_key(&name) {
+            return Err(self.error(
+                ErrorKind::NameError,
+                &format!("No such word: {}", token.token_value),
+            ));
+        }
+        Ok((name, token.span))
+    }
+
+    /// Pops a string and pushes the part of it `f` picks out.
+    fn map_string(&mut self, keyword: &str, f: fn(&str) -> &str) -> Result<(), Error> {
+        let text = self.pop_of_type(TokenType::String, keyword)?.token_value;
+
+        self.push(TokenType::String, f(&text));
+        Ok(())
+    }
+
+    /// Pops two strings and pushes whether `test` holds for the first and the
+    /// second.
+    fn test_strings(&mut self, keyword: &str, test: fn(&str, &str) -> bool) -> Result<(), Error> {
+        let needle = self.pop_of_type(TokenType::String, keyword)?.token_value;
+        let haystack = self.pop_of_type(TokenType::String, keyword)?.token_value;
+
+        self.push(TokenType::Bool, test(&haystack, &needle).to_string());
+        Ok(())
+    }
+
+    /// Pops two strings and pushes how many characters into the first the
+    /// second first appears, or -1 if it doesn't.
+    fn indexof(&mut self) -> Result<(), Error> {
+        let needle = self.pop_of_type(TokenType::String, "indexof")?.token_value;
+        let haystack = self.pop_of_type(TokenType::String, "indexof")?.token_value;
+
+        match haystack.find(&*needle) {
+            Some(byte) => {
+                let index = haystack[..byte].chars().count();
+                self.push(TokenType::Int, index.to_string());
+            }
+            None => self.push(TokenType::Int, "-1"),
+        }
+        Ok(())
+    }
+
+    /// Pops a template, then a value for each `{}` in it, and pushes the
+    /// template with each `{}` replaced by its value as `puts` would print it.
+    /// The value pushed first fills the first `{}`.
+    fn format(&mut self) -> Result<(), Error> {
+        let template = self.pop_of_type(TokenType::String, "format")?.token_value;
+        let pieces = template::parse(&template).map_err(|e| self.error(ErrorKind::Value, &e))?;
+
+        let placeholders = pieces
+            .iter()
+            .filter(|piece| matches!(piece, Piece::Value(_)))
+            .count();
+        let Some(start) = self.token_stack.len().checked_sub(placeholders) else {
+            return Err(self.error(
+                ErrorKind::StackUnderflow,
+                &format!(
+                    "format has {} placeholders but only {} values to fill them",
+                    placeholders,
+                    self.token_stack.len()
+                ),
+            ));
+        };
+        let values = self.token_stack.split_off(start);
+
+        let mut text = String::new();
+        let mut values = values.iter();
+        for piece in &pieces {
+            match piece {
+                Piece::Text(part) => text.push_str(part),
+                Piece::Value(spec) => {
+                    let value = values.next().unwrap();
+                    match (spec.precision, value.token_type) {
+                        (None, _) => text.push_str(&self.printed(value)),
+                        (Some(precision), TokenType::Int | TokenType::Float) => {
+                            let number = value.token_value.parse::<f64>().unwrap();
+                            text.push_str(&format!("{:.*}", precision, number));
+                        }
+                        (Some(_), t_type) => {
+                            return Err(self.error(
+                                ErrorKind::MismatchedTypes,
+                                &format!(
+                                    "format can only give an Int or Float a precision, found {}",
+                                    t_type
+                                ),
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+        self.push(TokenType::String, text);
+        Ok(())
+    }
+
+    /// Pops a string, the part of it to replace and what to replace it with,
+    /// and pushes the string with every match replaced.
+    fn replace(&mut self) -> Result<(), Error> {
+        let to = self.pop_of_type(TokenType::String, "replace")?.token_value;
+        let from = self.pop_of_type(TokenType::String, "replace")?.token_value;
+        let text = self.pop_of_type(TokenType::String, "replace")?.token_value;
+
+        // The empty string matches between every character, so there's no
+        // one answer for where to put the replacement.
+        if from.is_empty() {
+            return Err(self.error(ErrorKind::Value, "replace can't replace an empty string"));
+        }
+        self.push(TokenType::String, text.replace(&*from, &to));
+        Ok(())
+    }
+
+    /// Pops a string and then a count, and pushes the string that many times
+    /// over. The count can't be negative.
+    fn repeat(&mut self) -> Result<(), Error> {
+        let count = self.pop_of_type(TokenType::Int, "repeat")?.token_value;
+        let text = self.pop_of_type(TokenType::String, "repeat")?.token_value;
+
+        if order(TokenType::Int, &count, "0") == Some(Ordering::Less) {
+            return Err(self.error(
+                ErrorKind::Value,
+                &format!("repeat can't repeat a string {} times", count),
+            ));
+        }
+        // Sized up front, as a string too big to allocate would abort rather
+        // than fail.
+        let size = count
+            .parse::<usize>()
+            .ok()
+            .and_then(|count| Some((count, text.len().checked_mul(count)?)))
+            .filter(|&(_, size)| size <= isize::MAX as usize);
+        let count = match (size, self.limits.max_value_size) {
+            (None, _) => {
+                return Err(self.error(
+                    ErrorKind::Overflow,
+                    "repeat would make a string too long to hold",
+                ))
+            }
+            (Some((_, size)), Some(limit)) if size > limit => {
+                return Err(self.error(
+                    ErrorKind::LimitExceeded,
+                    &format!("A value grew past {} bytes", limit),
+                ))
+            }
+            (Some((count, _)), _) => count,
+        };
+
+        self.push(TokenType::String, text.repeat(count));
+        Ok(())
+    }
+
+    /// Pops two numbers and pushes the first divided by the second as a float,
+    /// even if both are ints.
+    fn fdiv(&mut self) -> Result<(), Error> {
+        let divisor = self.pop_number("fdiv")?;
+        let dividend = self.pop_number("fdiv")?;
+
+        self.push(TokenType::Float, (dividend / divisor).to_string());
+        Ok(())
+    }
+
+    /// Pops two ints and pushes whether the second divides the first evenly.
+    /// Nothing is divisible by 0, so a divisor of 0 pushes `false`.
+    fn divisible(&mut self) -> Result<(), Error> {
+        let divisor = self.pop_of_type(TokenType::Int, "divisible")?.token_value;
+        let value = self.pop_of_type(TokenType::Int, "divisible")?.token_value;
+
+        let (Ok(value), Ok(divisor)) = (value.parse::<i64>(), divisor.parse::<i64>()) else {
+            return Err(self.error(
+                ErrorKind::Overflow,
+                &format!("Cannot tell whether {} divides {}", divisor, value),
+            ));
+        };
+
+        // Wrapping, as the smallest int over -1 is one past the largest.
+        let divides = divisor != 0 && value.wrapping_rem(divisor) == 0;
+        self.push(TokenType::Bool, divides.to_string());
+        Ok(())
+    }
+
+    /// Pops two ints and pushes their greatest common divisor, or their
+    /// least common multiple if `multiple`. Both are taken without their
+    /// signs, so neither is ever negative. Anything divides 0, so the gcd of
+    /// 0 and n is n, and the lcm of 0 and anything is 0.
+    fn common(&mut self, multiple: bool) -> Result<(), Error> {
+        let keyword = if multiple { "lcm" } else { "gcd" };
+        let b = self.pop_of_type(TokenType::Int, keyword)?.token_value;
+        let a = self.pop_of_type(TokenType::Int, keyword)?.token_value;
+
+        let (Ok(a), Ok(b)) = (a.parse::<i64>(), b.parse::<i64>()) else {
+            return Err(self.error(
+                ErrorKind::Overflow,
+                &format!("Cannot find the {} of {} and {}", keyword, a, b),
+            ));
+        };
+        let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+
+        let divisor = gcd(a, b);
+        // The gcd is only 0 if both are, when the lcm is 0 too.
+        let result = match a.checked_div(divisor) {
+            _ if !multiple => divisor,
+            None => 0,
+            Some(part) => match part.checked_mul(b) {
+                Some(multiple) => multiple,
+                None if self.big_ints => {
+                    let multiple = bignum::multiply(&part.to_string(), &b.to_string());
+                    self.push(TokenType::Int, multiple);
+                    return Ok(());
+                }
+                None => {
+                    return Err(self.error(
+                        ErrorKind::Overflow,
+                        "Integer overflow while trying to find the lcm",
+                    ))
+                }
+            },
+        };
+        // Without their signs, the smallest int's divisors and multiples can
+        // be one past the largest.
+        if i64::try_from(result).is_err() && !self.big_ints {
+            return Err(self.error(
+                ErrorKind::Overflow,
+                &format!("Integer overflow while trying to find the {}", keyword),
+            ));
+        }
+        self.push(TokenType::Int, result.to_string());
+        Ok(())
+    }
+
+    /// Pops an int and pushes whether it's prime, trying every divisor up to
+    /// its square root. Nothing below 2 is prime, negative ints included.
+    fn is_prime(&mut self) -> Result<(), Error> {
+        let value = self.pop_of_type(TokenType::Int, "is_prime")?.token_value;
+        let Ok(n) = value.parse::<i64>() else {
+            return Err(self.error(
+                ErrorKind::Overflow,
+                &format!("Cannot tell whether {} is prime", value),
+            ));
+        };
+
+        let prime = n >= 2 && (2..).take_while(|d| d <= &(n / d)).all(|d| n % d != 0);
+        self.push(TokenType::Bool, prime.to_string());
+        Ok(())
+    }
+
+    /// Pops an int and pushes its factorial, the product of every int from 1
+    /// up to it. Negative ints have no factorial.
+    fn fact(&mut self) -> Result<(), Error> {
+        let value = self.pop_of_type(TokenType::Int, "fact")?.token_value;
+        let Ok(n) = value.parse::<i64>() else {
+            return Err(self.error(
+                ErrorKind::Overflow,
+                &format!("Integer overflow while trying to find {} fact", value),
+            ));
+        };
+        if n < 0 {
+            return Err(self.error(
+                ErrorKind::Value,
+                &format!("fact needs an int of 0 or more, not {}", n),
+            ));
+        }
+
+        let mut product: i64 = 1;
+        for i in 2..=n {
+            product = match product.checked_mul(i) {
+                Some(product) => product,
+                None if self.big_ints => {
+                    // Carry on digit by digit from here.
+                    let mut product = product.to_string();
+                    for i in i..=n {
+                        product = bignum::multiply(&product, &i.to_string());
+                    }
+                    self.push(TokenType::Int, product);
+                    return Ok(());
+                }
+                None => {
+                    return Err(self.error(
+                        ErrorKind::Overflow,
+                        &format!("Integer overflow while trying to find {} fact", n),
+                    ))
+                }
+            };
+        }
+        self.push(TokenType::Int, product.to_string());
+        Ok(())
+    }
+
+    /// Pops an int and starts the numbers `rand` and `randint` give from it,
+    /// so the same seed always gives the same numbers.
+    fn seed(&mut self) -> Result<(), Error> {
+        let seed = self.pop_of_type(TokenType::Int, "seed")?.token_value;
+        let seed = seed
+            .parse::<i64>()
+            .map_err(|_| self.error(ErrorKind::Overflow, "Seeds must fit in 64 bits"))?;
+
+        self.random = Random::seeded(seed as u64);
+        Ok(())
+    }
+
+    /// Pops two ints and pushes a random int from the first to the second,
+    /// both included.
+    fn randint(&mut self) -> Result<(), Error> {
+        let high = self.pop_of_type(TokenType::Int, "randint")?.token_value;
+        let low = self.pop_of_type(TokenType::Int, "randint")?.token_value;
+
+        let (Ok(low), Ok(high)) = (low.parse::<i64>(), high.parse::<i64>()) else {
+            return Err(self.error(
+                ErrorKind::Overflow,
+                "The bounds of randint must fit in 64 bits",
+            ));
+        };
+        if low > high {
+            return Err(self.error(
+                ErrorKind::Value,
+                &format!("randint has nothing from {} to {}", low, high),
+            ));
+        }
+
+        let int = self.random.between(low, high);
+        self.push(TokenType::Int, int.to_string());
+        Ok(())
+    }
+
+    /// Pops a number and pushes it made whole by `f`, still as a float. Ints
+    /// are already whole, so they go back as they were.
+    fn whole(&mut self, keyword: &str, f: fn(f64) -> f64) -> Result<(), Error> {
+        let value = self.pop()?;
+        match value.token_type {
+            TokenType::Int => self.token_stack.push(value),
+            TokenType::Float => {
+                let float = value.token_value.parse::<f64>().unwrap();
+                self.push(TokenType::Float, f(float).to_string());
+            }
+            _ => {
+                return Err(self.error(
+                    ErrorKind::MismatchedTypes,
+                    &format!(
+                        "{} expects an Int or Float, found {}",
+                        keyword, value.token_type
+                    ),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Pops a number and pushes the int 1, 0 or -1 as it's above, at or below
+    /// 0. NaN is neither, so it gets 0.
+    fn sign(&mut self) -> Result<(), Error> {
+        let value = self.pop()?;
+        let ordering = match value.token_type {
+            TokenType::Int => order(TokenType::Int, &value.token_value, "0"),
+            TokenType::Float => value.token_value.parse::<f64>().unwrap().partial_cmp(&0.0),
+            _ => {
+                return Err(self.error(
+                    ErrorKind::MismatchedTypes,
+                    &format!("sign expects an Int or Float, found {}", value.token_type),
+                ))
+            }
+        };
+
+        let sign = match ordering {
+            Some(Ordering::Greater) => "1",
+            Some(Ordering::Less) => "-1",
+            _ => "0",
+        };
+        self.push(TokenType::Int, sign);
+        Ok(())
+    }
+
+    /// Pops a number and pushes how far it is from 0, as the same type of
+    /// number.
+    fn abs(&mut self) -> Result<(), Error> {
+        let value = self.pop()?;
+        match value.token_type {
+            TokenType::Int => match value.token_value.parse::<i64>().map(i64::checked_abs) {
+                Ok(Some(abs)) => self.push(TokenType::Int, abs.to_string()),
+                // The smallest int is one further from 0 than the largest.
+                Ok(None) if !self.big_ints => {
+                    return Err(self.error(
+                        ErrorKind::Overflow,
+                        &format!("Integer overflow while trying to find {} abs", value),
+                    ))
+                }
+                _ => self.push(TokenType::Int, bignum::abs(&value.token_value)),
+            },
+            TokenType::Float => {
+                let float = value.token_value.parse::<f64>().unwrap();
+                self.push(TokenType::Float, float.abs().to_string());
+            }
+            _ => {
+                return Err(self.error(
+                    ErrorKind::MismatchedTypes,
+                    &format!("abs expects an Int or Float, found {}", value.token_type),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Pops a number, then the lowest and highest it may be, and pushes
+    /// whichever of the three is in the middle. If any of them is a float, the
+    /// result is too.
+    fn clamp(&mut self) -> Result<(), Error> {
+        let high = self.pop()?;
+        let low = self.pop()?;
+        let value = self.pop()?;
+
+        let numbers = [&value, &low, &high];
+        if let Some(other) = numbers
+            .iter()
+            .find(|token| !matches!(token.token_type, TokenType::Int | TokenType::Float))
+        {
+            return Err(self.error(
+                ErrorKind::MismatchedTypes,
+                &format!("clamp expects an Int or Float, found {}", other.token_type),
+            ));
+        }
+        let t_type = if numbers
+            .iter()
+            .all(|token| token.token_type == TokenType::Int)
+        {
+            TokenType::Int
+        } else {
+            TokenType::Float
+        };
+        let cmp = |a: &Token, b: &Token| order(t_type, &a.token_value, &b.token_value).unwrap();
+
+        if cmp(&low, &high) == Ordering::Greater {
+            return Err(self.error(
+                ErrorKind::Value,
+                &format!(
+                    "clamp has nothing from {} to {}",
+                    low.token_value, high.token_value
+                ),
+            ));
+        }
+        let clamped = if cmp(&value, &low) == Ordering::Less {
+            low
+        } else if cmp(&value, &high) == Ordering::Greater {
+            high
+        } else {
+            value
+        };
+        match t_type {
+            TokenType::Float => {
+                let float = clamped.token_value.parse::<f64>().unwrap();
+                self.push(TokenType::Float, float.to_string());
+            }
+            _ => self.push(TokenType::Int, clamped.token_value),
+        }
+        Ok(())
+    }
+
+    /// Pops a number and pushes `f` of it as a float.
+    fn math(&mut self, keyword: &str, f: fn(f64) -> f64) -> Result<(), Error> {
+        let number = self.pop_number(keyword)?;
+
+        self.push(TokenType::Float, f(number).to_string());
+        Ok(())
+    }
+
+    /// Like [`math`](Runner::math), but with a logarithm `f`, which only has
+    /// an answer for numbers above 0.
+    fn logarithm(&mut self, keyword: &str, f: fn(f64) -> f64) -> Result<(), Error> {
+        let number = self.pop_number(keyword)?;
+        if number <= 0.0 || number.is_nan() {
+            return Err(self.error(
+                ErrorKind::Value,
+                &format!("{} needs a number above 0, not {}", keyword, number),
+            ));
+        }
+
+        self.push(TokenType::Float, f(number).to_string());
+        Ok(())
+    }
+
+    /// Pops an int or a float as an `f64`.
+    fn pop_number(&mut self, keyword: &str) -> Result<f64, Error> {
+        let value = self.pop()?;
+        match value.token_type {
+            TokenType::Int | TokenType::Float => Ok(value.token_value.parse::<f64>().unwrap()),
+            _ => Err(self.error(
+                ErrorKind::MismatchedTypes,
+                &format!(
+                    "{} expects an Int or Float, found {}",
+                    keyword, value.token_type
+                ),
+            )),
+        }
+    }
+
+    /// Pops a bool and two values, then pushes the first value if the bool is
+    /// true and the second if it is false.
+    fn select(&mut self) -> Result<(), Error> {
+        let if_false = self.pop()?;
+        let if_true = self.pop()?;
+        let condition = self.pop_of_type(TokenType::Bool, "select")?;
+
+        let chosen = if &*condition.token_value == "true" {
+            if_true
+        } else {
+            if_false
+        };
+        self.token_stack.push(chosen);
+        Ok(())
+    }
+
+    /// Pushes the current time as whole seconds since the Unix epoch.
+    fn now(&mut self) -> Result<(), Error> {
+        // The standard clock panics on bare WebAssembly, which has no way to
+        // ask for the time.
+        if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+            return Err(self.error(ErrorKind::Time, "The clock isn't available here"));
+        }
+
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| self.error(ErrorKind::Time, &e.to_string()))?
+            .as_secs();
+
+        self.push(TokenType::Int, seconds.to_string());
+        Ok(())
+    }
+
+    /// Reads `time { BODY }` from the program, runs BODY and pushes how many
+    /// whole milliseconds it took. Like `--time`, time spent waiting on `gets`
+    /// doesn't count.
+    fn time(&mut self) -> Result<(), Error> {
+        let time_span = self.current_span;
+        if self.program.front().map(|token| token.token_type) != Some(TokenType::LeftBrace) {
+            return Err(self.error(ErrorKind::Syntax, "time needs a { block } to run"));
+        }
+        self.program.pop_front();
+
+        let Some(body) = take_block(iter::from_fn(|| self.program.pop_front())) else {
+            self.current_span = time_span;
+            return Err(self.error(
+                ErrorKind::Syntax,
+                "The block of this time is missing its closing }",
+            ));
+        };
+        // Instant panics on bare WebAssembly too.
+        if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+            return Err(self.error(ErrorKind::Time, "The clock isn't available here"));
+        }
+
+        let started = Instant::now();
+        let waited = self.waiting;
+        self.run_block(body)?;
+        let elapsed = started.elapsed().saturating_sub(self.waiting - waited);
+
+        self.current_span = time_span;
+        self.push(TokenType::Int, elapsed.as_millis().to_string());
+        Ok(())
+    }
+
+    /// Pops a variable name and pushes its value from the environment, or an
+    /// empty string when it isn't set.
+    fn getenv(&mut self) -> Result<(), Error> {
+        let value = self.env_var("getenv")?.unwrap_or_default();
+
+        self.push(TokenType::String, value);
+        Ok(())
+    }
+
+    /// Like [`getenv`](Runner::getenv), but pushes nil when the variable isn't
+    /// set, so that can be told apart from it being set to nothing.
+    fn env(&mut self) -> Result<(), Error> {
+        match self.env_var("env")? {
+            Some(value) => self.push(TokenType::String, value),
+            None => self.push(TokenType::Nil, "nil"),
+        }
+        Ok(())
+    }
+
+    /// Pops a variable name for `keyword` and gives its value from the
+    /// environment, if it is set.
+    fn env_var(&mut self, keyword: &str) -> Result<Option<String>, Error> {
+        self.need_env_access(keyword)?;
+        let name = self.pop_of_type(TokenType::String, keyword)?.token_value;
+
+        match env::var(&*name) {
+            Ok(value) => Ok(Some(value)),
+            Err(env::VarError::NotPresent) => Ok(None),
+            Err(env::VarError::NotUnicode(_)) => Err(self.error(
+                ErrorKind::Env,
+                &format!("The value of {} is not valid UTF-8", name),
+            )),
+        }
+    }
+
+    /// Pops a command, runs it and waits for it to finish, then pushes what it
+    /// printed. The command is split at whitespace into a program and its
+    /// arguments and run directly, not through a shell. Its exit status is
+    /// kept for `last_status`; failing is up to the program to check.
+    fn exec(&mut self) -> Result<(), Error> {
+        self.need_process_access("exec")?;
+        let command = self.pop_of_type(TokenType::String, "exec")?.token_value;
+
+        let mut words = command.split_whitespace();
+        let Some(program) = words.next() else {
+            return Err(self.error(ErrorKind::Value, "exec needs a program to run"));
+        };
+        let output = Process::new(program)
+            .args(words)
+            .stdin(Stdio::null())
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|e| self.error(ErrorKind::Io, &format!("Could not run {}: {}", program, e)))?;
+
+        self.last_status = output.status.code();
+        self.push(
+            TokenType::String,
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+        );
+        Ok(())
+    }
+
+    /// Pops two values and pushes whether they are equal, or whether they
+    /// aren't if `equal` is false. Values of different types are never equal.
+    fn equality(&mut self, equal: bool) -> Result<(), Error> {
+        let second = self.pop()?;
+        let first = self.pop()?;
+
+        let same = same_value(&first, &second);
+        self.push(TokenType::Bool, (same == equal).to_string());
+        Ok(())
+    }
+
+    /// Pops two numbers or strings of the same type and pushes whether
+    /// `accept` holds for how the first orders against the second.
+    fn compare(&mut self, accept: fn(Ordering) -> bool) -> Result<(), Error> {
+        let second = self.pop()?;
+        let first = self.pop()?;
+
+        if first.token_type != second.token_type {
+            return Err(self.error(
+                ErrorKind::MismatchedTypes,
+                "Cannot compare 2 values of different types",
+            ));
+        }
+
+        let ordering = match order(first.token_type, &first.token_value, &second.token_value) {
+            Some(ordering) => ordering,
+            None => {
+                return Err(self.error(
+                    ErrorKind::MismatchedTypes,
+                    &format!("Cannot compare values of type {}", first.token_type),
+                ))
+            }
+        };
+
+        self.push(TokenType::Bool, accept(ordering).to_string());
+        Ok(())
+    }
+
+    /// Pops a list and pushes it sorted from smallest to largest. Its values
+    /// must be all strings, or all numbers, which sort by value whether they
+    /// are ints or floats. NaN sorts after every other number.
+    fn sort(&mut self) -> Result<(), Error> {
+        let list = self.pop_of_type(TokenType::List, "sort")?;
+        let mut items = list.items.to_vec();
+
+        let numbers = items
+            .iter()
+            .all(|item| matches!(item.token_type, TokenType::Int | TokenType::Float));
+        let strings = items
+            .iter()
+            .all(|item| item.token_type == TokenType::String);
+        if !numbers && !strings {
+            return Err(self.error(
+                ErrorKind::MismatchedTypes,
+                "sort expects a List of only numbers or only strings",
+            ));
+        }
+
+        items.sort_by(|a, b| match (a.token_type, b.token_type) {
+            // Ints mixed with floats compare as floats, in an order that has
+            // a place for NaN.
+            (TokenType::Float, _) | (_, TokenType::Float) => {
+                let float = |token: &Token| token.token_value.parse::<f64>().unwrap();
+                float(a).total_cmp(&float(b))
+            }
+            (t_type, _) => order(t_type, &a.token_value, &b.token_value).unwrap(),
+        });
+        self.token_stack
+            .push(Token::list(items).at(self.current_span));
+        Ok(())
+    }
+
+    /// Pops a list and pushes it without any value equal to one before it,
+    /// keeping the first of each.
+    fn unique(&mut self) -> Result<(), Error> {
+        let list = self.pop_of_type(TokenType::List, "unique")?;
+
+        let mut items: Vec<Token> = Vec::with_capacity(list.items.len());
+        for item in list.items.iter() {
+            if !items.iter().any(|kept| same_value(kept, item)) {
+                items.push(item.clone());
+            }
+        }
+        self.token_stack
+            .push(Token::list(items).at(self.current_span));
+        Ok(())
+    }
+
+    /// Pops an int and then the int to shift by that many bits.
+    fn shift(&mut self, operator: &str, op: fn(i64, u32) -> Option<i64>) -> Result<(), Error> {
+        let bits = self.pop_of_type(TokenType::Int, operator)?.token_value;
+        let value = self.pop_of_type(TokenType::Int, operator)?.token_value;
+
+        // Bignums are never shifted, as any shift would overflow them.
+        let result = bits
+            .parse::<u32>()
+            .ok()
+            .zip(value.parse::<i64>().ok())
+            .and_then(|(bits, value)| op(value, bits))
+            .ok_or_else(|| {
+                self.error(
+                    ErrorKind::Overflow,
+                    &format!("Cannot shift {} by {} bits", value, bits),
+                )
+            })?;
+
+        self.push(TokenType::Int, result.to_string());
+        Ok(())
+    }
+
+    fn add(&mut self) -> Result<(), Error> {
+        self.arithmetic("add", i64::checked_add, bignum::add, |a, b| a + b)
+    }
+
+    fn multiply(&mut self) -> Result<(), Error> {
+        self.arithmetic("multiply", i64::checked_mul, bignum::multiply, |a, b| a * b)
+    }
+
+    /// Pops a list of numbers and pushes them combined with `op`, starting
+    /// from `identity`. A list mixing ints and floats is combined as floats.
+    fn aggregate(
+        &mut self,
+        keyword: &str,
+        identity: &str,
+        op: fn(&mut Runner) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let list = self.pop_of_type(TokenType::List, keyword)?;
+
+        if !list
+            .items
+            .iter()
+            .all(|item| matches!(item.token_type, TokenType::Int | TokenType::Float))
+        {
+            return Err(self.error(
+                ErrorKind::MismatchedTypes,
+                &format!("{} expects a List of only numbers", keyword),
+            ));
+        }
+        let t_type = if list
+            .items
+            .iter()
+            .any(|item| item.token_type == TokenType::Float)
+        {
+            TokenType::Float
+        } else {
+            TokenType::Int
+        };
+
+        self.push(t_type, identity);
+        for item in list.items.iter() {
+            self.push(t_type, Rc::clone(&item.token_value));
+            op(self)?;
+        }
+        Ok(())
+    }
+
+    /// Pops two numbers of the same type and pushes the result of applying
+    /// `int_op` or `float_op` to them. `int_op` returns `None` on overflow,
+    /// in which case `big_op` gives the exact result if big ints are on.
+    fn arithmetic(
+        &mut self,
+        verb: &str,
+        int_op: fn(i64, i64) -> Option<i64>,
+        big_op: fn(&str, &str) -> String,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Result<(), Error> {
+        let second = self.pop()?;
+        let first = self.pop()?;
+
+        if first.token_type != second.token_type {
+            return Err(self.error(
+                ErrorKind::MismatchedTypes,
+                &format!("Cannot {} on 2 values of different types", verb),
+            ));
+        }
+
+        let first_num = first.token_value;
+        let second_num = second.token_value;
+
+        let result = match first.token_type {
+            TokenType::Int => {
+                let fixed = match (first_num.parse::<i64>(), second_num.parse::<i64>()) {
+                    (Ok(a), Ok(b)) => int_op(a, b),
+                    _ => None,
+                };
+                match fixed {
+                    Some(result) => result.to_string(),
+                    None if self.big_ints => big_op(&first_num, &second_num),
+                    None => {
+                        return Err(self.error(
+                            ErrorKind::Overflow,
+                            &format!("Integer overflow while trying to {}", verb),
+                        ))
+                    }
+                }
+            }
+            TokenType::Float => float_op(
+                first_num.parse::<f64>().unwrap(),
+                second_num.parse::<f64>().unwrap(),
+            )
+            .to_string(),
+            _ => {
+                return Err(self.error(
+                    ErrorKind::MismatchedTypes,
+                    &format!("Cannot {} on values of type {}", verb, first.token_type),
+                ))
+            }
+        };
+
+        self.push(first.token_type, result);
+        Ok(())
+    }
+}
+
+/// The greatest common divisor of `a` and `b`, by Euclid's algorithm.
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Takes the tokens of a block, whose `{` has already been taken, up to the
+/// `}` that closes it, keeping any blocks nested inside whole. Gives `None`
+/// if `tokens` runs out first.
+fn take_block(tokens: impl IntoIterator<Item = Token>) -> Option<Vec<Token>> {
+    let mut block = Vec::new();
+    let mut depth = 0;
+    for token in tokens {
+        match token.token_type {
+            TokenType::LeftBrace => depth += 1,
+            TokenType::RightBrace if depth == 0 => return Some(block),
+            TokenType::RightBrace => depth -= 1,
+            _ => {}
+        }
+        block.push(token);
+    }
+    None
+}
+
+/// Whether `token` is the keyword `catch`.
+fn is_catch(token: &Token) -> bool {
+    token.token_type == TokenType::Keyword && &*token.token_value == "catch"
+}
+
+/// The full path of `path`, or `path` itself if that can't be found.
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Whether `a` and `b` are the same value, as `==` sees it, wherever they
+/// came from.
+fn same_value(a: &Token, b: &Token) -> bool {
+    a.token_type == b.token_type
+        && match a.token_type {
+            TokenType::Int => {
+                order(TokenType::Int, &a.token_value, &b.token_value) == Some(Ordering::Equal)
+            }
+            TokenType::Float => {
+                a.token_value.parse::<f64>().ok() == b.token_value.parse::<f64>().ok()
+            }
+            TokenType::List => {
+                a.items.len() == b.items.len()
+                    && a.items
+                        .iter()
+                        .zip(b.items.iter())
+                        .all(|(a, b)| same_value(a, b))
+            }
+            TokenType::Function => {
+                let (a_captured, b_captured) = (a.captured.borrow(), b.captured.borrow());
+                a.token_value == b.token_value
+                    && (Rc::ptr_eq(&a.captured, &b.captured)
+                        || a_captured.len() == b_captured.len()
+                            && a_captured.iter().zip(b_captured.iter()).all(
+                                |((a_name, a_value), (b_name, b_value))| {
+                                    a_name == b_name && same_value(a_value, b_value)
+                                },
+                            ))
+            }
+            _ => a.token_value == b.token_value,
+        }
+}
+
+/// How the values `a` and `b`, both of type `t_type`, order, or `None` if
+/// values of that type can't be ordered.
+fn order(t_type: TokenType, a: &str, b: &str) -> Option<Ordering> {
+    let ordering = match t_type {
+        TokenType::Int => match (a.parse::<i64>(), b.parse::<i64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => bignum::compare(a, b),
+        },
+        TokenType::Float => a
+            .parse::<f64>()
+            .unwrap()
+            .partial_cmp(&b.parse::<f64>().unwrap())
+            .unwrap_or(Ordering::Equal),
+        TokenType::String => a.cmp(b),
+        _ => return None,
+    };
+    Some(ordering)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval_to_string;
+    use crate::input::LineReader;
+    use crate::lexer::Lexer;
+    use crate::output::SharedBuffer;
+
+    fn runner() -> Runner {
+        Runner::new()
+            .with_output(Box::new(Vec::new()))
+            .with_input(Box::new(LineReader::new(io::empty())))
+    }
+
+    fn run(source: &str) -> Runner {
+        let mut runner = runner();
+        runner.run(lex(source)).unwrap();
+        runner
+    }
+
+    fn run_err(source: &str) -> Error {
+        runner().run(lex(source)).unwrap_err()
+    }
+
+    fn lex(source: &str) -> Vec<Token> {
+        Lexer::new(String::from(source)).tokenize().unwrap()
+    }
+
+    fn top(runner: &Runner) -> &Token {
+        runner.token_stack.last().unwrap()
+    }
+
+    #[test]
+    fn warns_about_each_value_left_on_the_stack() {
+        let mut runner = run("1 2 3 + puts 4");
+        runner.warn_about_leftovers();
+
+        let warnings = runner.take_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].kind, WarningKind::LeftoverValue);
+        assert_eq!(warnings[0].span.column, 1);
+        assert_eq!(warnings[1].span.column, 14);
+        assert!(runner.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn collect_gathers_the_stack_into_one_list() {
+        assert_eq!(eval_to_string("1 2 3 collect len puts"), "3\n");
+    }
+
+    #[test]
+    fn collect_keeps_the_bottom_of_the_stack_first() {
+        let runner = run("1 \"two\" 3 collect");
+
+        assert_eq!(runner.token_stack.len(), 1);
+        assert_eq!(&*top(&runner).token_value, "[1, \"two\", 3]");
+        assert_eq!(&*top(&runner).items[0].token_value, "1");
+    }
+
+    #[test]
+    fn pick_copies_a_value_from_down_the_stack() {
+        assert_eq!(
+            eval_to_string("1 2 3 0 pick collect puts"),
+            "[1, 2, 3, 3]\n"
+        );
+        assert_eq!(
+            eval_to_string("1 2 3 1 pick collect puts"),
+            "[1, 2, 3, 2]\n"
+        );
+        assert_eq!(
+            eval_to_string("1 2 3 2 pick collect puts"),
+            "[1, 2, 3, 1]\n"
+        );
+    }
+
+    #[test]
+    fn pick_past_the_bottom_of_the_stack_is_an_underflow() {
+        let error = run_err("1 2 2 pick");
+
+        assert_eq!(error.kind, ErrorKind::StackUnderflow);
+        assert_eq!(error.message, "Cannot pick 2 down from a stack of 2 values");
+        assert_eq!(run_err("0 pick").kind, ErrorKind::StackUnderflow);
+        assert_eq!(run_err("1 \"0\" pick").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn roll_moves_a_value_from_down_the_stack_to_the_top() {
+        // 2 roll is swap and 3 roll is rot.
+        assert_eq!(eval_to_string("1 2 3 2 roll collect puts"), "[1, 3, 2]\n");
+        assert_eq!(eval_to_string("1 2 3 3 roll collect puts"), "[2, 3, 1]\n");
+        assert_eq!(
+            eval_to_string("1 2 3 4 5 4 roll collect puts"),
+            "[1, 3, 4, 5, 2]\n"
+        );
+        assert_eq!(eval_to_string("1 2 1 roll 0 roll collect puts"), "[1, 2]\n");
+    }
+
+    #[test]
+    fn roll_past_the_bottom_of_the_stack_is_an_underflow() {
+        let error = run_err("1 2 3 roll");
+
+        assert_eq!(error.kind, ErrorKind::StackUnderflow);
+        assert_eq!(error.message, "Cannot roll 3 values on a stack of 2");
+        assert_eq!(run_err("1 \"2\" roll").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn dupn_copies_the_top_values_in_order() {
+        assert_eq!(
+            eval_to_string("1 2 3 2 dupn collect puts"),
+            "[1, 2, 3, 2, 3]\n"
+        );
+        assert_eq!(eval_to_string("1 2 3 0 dupn collect puts"), "[1, 2, 3]\n");
+        assert_eq!(eval_to_string("0 dupn collect puts"), "[]\n");
+    }
+
+    #[test]
+    fn dupn_past_the_bottom_of_the_stack_is_an_underflow() {
+        let error = run_err("1 2 3 dupn");
+
+        assert_eq!(error.kind, ErrorKind::StackUnderflow);
+        assert_eq!(error.message, "Cannot copy 3 values from a stack of 2");
+        assert_eq!(run_err("1 \"1\" dupn").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn empty_pushes_whether_the_stack_was_empty() {
+        assert_eq!(eval_to_string("empty? puts"), "true\n");
+        assert_eq!(eval_to_string("1 2 empty? puts"), "false\n");
+        assert_eq!(eval_to_string("empty? empty? puts"), "false\n");
+    }
+
+    #[test]
+    fn collect_on_an_empty_stack_is_an_empty_list() {
+        assert_eq!(eval_to_string("collect len puts"), "0\n");
+    }
+
+    #[test]
+    fn len_counts_characters_of_strings() {
+        assert_eq!(eval_to_string("\"héllo\" len puts"), "5\n");
+    }
+
+    #[test]
+    fn len_rejects_numbers() {
+        assert_eq!(run_err("5 len").kind, ErrorKind::MismatchedTypes);
+    }
+
+    fn run_big(source: &str) -> String {
+        let out = SharedBuffer::default();
+        let mut runner = Runner::new()
+            .with_output(Box::new(out.clone()))
+            .with_big_ints();
+        crate::lex_and_run(Lexer::new(String::from(source)), &mut runner).unwrap();
+        out.contents()
+    }
+
+    #[test]
+    fn big_ints_are_exact_past_64_bits() {
+        assert_eq!(
+            run_big("999999999999999999999 1 + puts"),
+            "1000000000000000000000\n"
+        );
+        assert_eq!(
+            run_big("18446744073709551615 2 * 1 + puts"),
+            "36893488147419103231\n"
+        );
+    }
+
+    #[test]
+    fn big_ints_compare_by_value() {
+        assert_eq!(
+            run_big("99999999999999999999 100 > puts 1 99999999999999999999 >= puts"),
+            "true\nfalse\n"
+        );
+    }
+
+    #[test]
+    fn big_ints_cannot_be_shifted() {
+        let mut runner = runner().with_big_ints();
+        let error = crate::lex_and_run(
+            Lexer::new(String::from("99999999999999999999 1 <<")),
+            &mut runner,
+        )
+        .unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::Overflow);
+    }
+
+    #[test]
+    fn ints_overflow_without_big_ints() {
+        assert_eq!(
+            run_err("9223372036854775807 1 +").to_string(),
+            "OverflowError: Integer overflow while trying to add"
+        );
+    }
+
+    #[test]
+    fn fdiv_divides_ints_as_floats() {
+        let runner = run("7 2 fdiv");
+
+        assert_eq!(top(&runner).token_type, TokenType::Float);
+        assert_eq!(top(&runner).token_value.as_ref(), "3.5");
+    }
+
+    #[test]
+    fn fdiv_mixes_ints_and_floats() {
+        assert_eq!(eval_to_string("1 .5 fdiv puts 7.5 3 fdiv puts"), "2\n2.5\n");
+    }
+
+    #[test]
+    fn fdiv_needs_numbers() {
+        let error = run_err("\"7\" 2 fdiv");
+
+        assert_eq!(error.kind, ErrorKind::MismatchedTypes);
+        assert_eq!(
+            error.to_string(),
+            "Mismatched types: fdiv expects an Int or Float, found String"
+        );
+    }
+
+    #[test]
+    fn divisible_checks_for_a_remainder() {
+        assert_eq!(
+            eval_to_string("10 5 divisible puts 10 3 divisible puts 0 7 divisible puts"),
+            "true\nfalse\ntrue\n"
+        );
+    }
+
+    #[test]
+    fn gcd_and_lcm_find_common_divisors_and_multiples() {
+        assert_eq!(
+            eval_to_string("12 18 gcd puts 12 18 lcm puts 7 13 gcd puts 7 13 lcm puts"),
+            "6\n36\n1\n91\n"
+        );
+    }
+
+    #[test]
+    fn gcd_and_lcm_of_zero_follow_the_usual_convention() {
+        assert_eq!(
+            eval_to_string("0 9 gcd puts 9 0 gcd puts 0 0 gcd puts"),
+            "9\n9\n0\n"
+        );
+        assert_eq!(eval_to_string("0 9 lcm puts 0 0 lcm puts"), "0\n0\n");
+    }
+
+    #[test]
+    fn gcd_and_lcm_ignore_signs() {
+        assert_eq!(
+            eval_to_string("-12 18 gcd puts 12 -18 gcd puts -12 -18 gcd puts"),
+            "6\n6\n6\n"
+        );
+        assert_eq!(
+            eval_to_string("-12 18 lcm puts -12 -18 lcm puts -4 0 lcm puts"),
+            "36\n36\n0\n"
+        );
+        assert_eq!(eval_to_string("-9 0 gcd puts"), "9\n");
+    }
+
+    #[test]
+    fn gcd_of_the_smallest_int_overflows_without_big_ints() {
+        let source = format!("{} 0 gcd", i64::MIN);
+
+        assert_eq!(run_err(&source).kind, ErrorKind::Overflow);
+        assert_eq!(
+            run_big(&format!("{} puts", source)),
+            "9223372036854775808\n"
+        );
+    }
+
+    #[test]
+    fn lcm_overflows_without_big_ints() {
+        let source = "9223372036854775807 9223372036854775806 lcm";
+
+        assert_eq!(run_err(source).kind, ErrorKind::Overflow);
+        assert_eq!(
+            run_big(&format!("{} puts", source)),
+            "85070591730234615838173535747377725442\n"
+        );
+        assert_eq!(run_err("12 2.0 gcd").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn is_prime_finds_primes() {
+        let primes: [i64; 8] = [2, 3, 5, 7, 13, 97, 7919, 1_000_000_007];
+        let composites: [i64; 7] = [0, 1, 4, 9, 91, 7917, 3_000_000_021];
+
+        for n in primes {
+            assert_eq!(
+                eval_to_string(&format!("{} is_prime puts", n)),
+                "true\n",
+                "{}",
+                n
+            );
+        }
+        for n in composites {
+            assert_eq!(
+                eval_to_string(&format!("{} is_prime puts", n)),
+                "false\n",
+                "{}",
+                n
+            );
+        }
+        assert_eq!(run_err("7.0 is_prime").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn negative_ints_are_not_prime() {
+        for n in [-1, -2, -7, i64::MIN] {
+            assert_eq!(
+                eval_to_string(&format!("{} is_prime puts", n)),
+                "false\n",
+                "{}",
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn fact_multiplies_every_int_up_to_its_own() {
+        assert_eq!(
+            eval_to_string("0 fact puts 1 fact puts 5 fact puts 10 fact puts"),
+            "1\n1\n120\n3628800\n"
+        );
+    }
+
+    #[test]
+    fn fact_overflows_past_twenty_without_big_ints() {
+        assert_eq!(eval_to_string("20 fact puts"), "2432902008176640000\n");
+        assert_eq!(run_err("21 fact").kind, ErrorKind::Overflow);
+        assert_eq!(run_big("21 fact puts"), "51090942171709440000\n");
+        assert_eq!(
+            run_big("30 fact puts"),
+            "265252859812191058636308480000000\n"
+        );
+        assert_eq!(run_err("5.0 fact").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn negative_ints_have_no_factorial() {
+        let error = run_err("-3 fact");
+
+        assert_eq!(error.kind, ErrorKind::Value);
+        assert_eq!(error.message, "fact needs an int of 0 or more, not -3");
+    }
+
+    #[test]
+    fn nothing_is_divisible_by_zero() {
+        assert_eq!(
+            eval_to_string("10 0 divisible puts 0 0 divisible puts"),
+            "false\nfalse\n"
+        );
+    }
+
+    #[test]
+    fn floor_ceil_and_round_make_floats_whole() {
+        assert_eq!(
+            eval_to_string("3.7 floor puts 3.2 ceil puts 3.5 round puts"),
+            "3\n4\n4\n"
+        );
+        assert_eq!(
+            eval_to_string("3.2 floor puts 3.7 ceil puts 3.4 round puts"),
+            "3\n4\n3\n"
+        );
+        assert_eq!(eval_to_string(".5 floor puts .5 ceil puts"), "0\n1\n");
+
+        let runner = run("3.7 floor");
+        assert_eq!(top(&runner).token_type, TokenType::Float);
+    }
+
+    #[test]
+    fn floor_ceil_and_round_work_below_zero() {
+        assert_eq!(
+            eval_to_string("-3.7 floor puts -3.7 ceil puts -3.7 round puts"),
+            "-4\n-3\n-4\n"
+        );
+        // Halves round away from 0 either side of it.
+        assert_eq!(eval_to_string("-2.5 round puts 2.5 round puts"), "-3\n3\n");
+        assert_eq!(eval_to_string("-7 floor puts"), "-7\n");
+    }
+
+    #[test]
+    fn floor_ceil_and_round_leave_whole_numbers_alone() {
+        assert_eq!(
+            eval_to_string("4.0 floor puts 4.0 ceil puts 4.0 round puts"),
+            "4\n4\n4\n"
+        );
+
+        let runner = run("7 round");
+        assert_eq!(top(&runner).token_type, TokenType::Int);
+        assert_eq!(top(&runner).token_value.as_ref(), "7");
+        assert_eq!(run_err("\"a\" floor").kind, ErrorKind::MismatchedTypes);
+    }
+
+    /// The float on top of the stack after running `source`.
+    fn float_after(source: &str) -> f64 {
+        let runner = run(source);
+        assert_eq!(top(&runner).token_type, TokenType::Float, "{}", source);
+        top(&runner).token_value.parse().unwrap()
+    }
+
+    #[test]
+    fn math_keywords_match_known_values() {
+        let cases = [
+            ("0 sin", 0.0),
+            ("1.5707963267948966 sin", 1.0),
+            ("0 cos", 1.0),
+            ("3.141592653589793 cos", -1.0),
+            (".7853981633974483 tan", 1.0),
+            ("1 ln", 0.0),
+            ("2.718281828459045 ln", 1.0),
+            ("1000 log10", 3.0),
+            ("0 exp", 1.0),
+            ("1 exp", std::f64::consts::E),
+        ];
+
+        for (source, expected) in cases {
+            assert!((float_after(source) - expected).abs() < 1e-12, "{}", source);
+        }
+    }
+
+    #[test]
+    fn math_keywords_print_whole_results_plainly() {
+        assert_eq!(
+            eval_to_string("0 sin puts 1 ln puts 100 log10 puts"),
+            "0\n0\n2\n"
+        );
+    }
+
+    #[test]
+    fn sign_gives_one_zero_or_minus_one() {
+        assert_eq!(
+            eval_to_string("7 sign puts 0 sign puts -3 sign puts"),
+            "1\n0\n-1\n"
+        );
+        assert_eq!(
+            eval_to_string("2.5 sign puts 0.0 sign puts -2.5 sign puts 4 sin sign puts"),
+            "1\n0\n-1\n-1\n"
+        );
+        // Negative zero is still zero.
+        assert_eq!(eval_to_string("-0.0 sign puts -0 sign puts"), "0\n0\n");
+    }
+
+    #[test]
+    fn sign_always_pushes_an_int() {
+        assert_eq!(top(&run("3 sign")).token_type, TokenType::Int);
+        assert_eq!(top(&run("3.0 sign")).token_type, TokenType::Int);
+        assert_eq!(top(&run("-0.5 sign")).token_value.as_ref(), "-1");
+        assert_eq!(run_err("\"3\" sign").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn sign_of_a_big_int_looks_at_its_minus() {
+        assert_eq!(
+            run_big("-99999999999999999999 sign puts 99999999999999999999 sign puts"),
+            "-1\n1\n"
+        );
+    }
+
+    #[test]
+    fn abs_takes_the_sign_off_a_number() {
+        assert_eq!(
+            eval_to_string("-5 abs puts 5 abs puts 0 abs puts"),
+            "5\n5\n0\n"
+        );
+        assert_eq!(eval_to_string("-2.5 abs puts 2.5 abs puts"), "2.5\n2.5\n");
+        assert_eq!(top(&run("-5 abs")).token_type, TokenType::Int);
+        assert_eq!(top(&run("-5.0 abs")).token_type, TokenType::Float);
+        assert_eq!(run_err("\"-5\" abs").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn abs_of_the_smallest_int_overflows_without_big_ints() {
+        let source = format!("{} abs", i64::MIN);
+
+        assert_eq!(run_err(&source).kind, ErrorKind::Overflow);
+        assert_eq!(
+            run_big(&format!("{} puts", source)),
+            "9223372036854775808\n"
+        );
+        assert_eq!(
+            run_big("-99999999999999999999 abs puts"),
+            "99999999999999999999\n"
+        );
+    }
+
+    #[test]
+    fn clamp_moves_numbers_into_range() {
+        assert_eq!(
+            eval_to_string("5 0 10 clamp puts 15 0 10 clamp puts 2 3 10 clamp puts"),
+            "5\n10\n3\n"
+        );
+        assert_eq!(eval_to_string("10 0 10 clamp puts"), "10\n");
+    }
+
+    #[test]
+    fn clamp_with_any_float_pushes_a_float() {
+        assert_eq!(top(&run("15 0 10 clamp")).token_type, TokenType::Int);
+        assert_eq!(float_after("1 2.5 10 clamp"), 2.5);
+        assert_eq!(float_after("15 0 10.0 clamp"), 10.0);
+        assert_eq!(float_after("4 sin 0 1 clamp"), 0.0);
+    }
+
+    #[test]
+    fn clamp_needs_its_bounds_in_order() {
+        let error = run_err("5 10 0 clamp");
+
+        assert_eq!(error.kind, ErrorKind::Value);
+        assert_eq!(error.message, "clamp has nothing from 10 to 0");
+        assert_eq!(run_err("5 \"a\" 10 clamp").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn pi_and_e_push_their_constants() {
+        assert_eq!(top(&run("pi")).token_type, TokenType::Float);
+        assert_eq!(float_after("pi"), std::f64::consts::PI);
+        assert_eq!(float_after("e"), std::f64::consts::E);
+        assert_eq!(eval_to_string("pi puts"), "3.141592653589793\n");
+    }
+
+    #[test]
+    fn logarithms_of_zero_are_value_errors() {
+        assert_eq!(run_err("0 ln").kind, ErrorKind::Value);
+        assert_eq!(run_err("0.0 log10").kind, ErrorKind::Value);
+        assert_eq!(
+            run_err("-1 ln").message,
+            "ln needs a number above 0, not -1"
+        );
+        assert_eq!(run_err("\"e\" exp").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn seeded_random_numbers_repeat() {
+        let program = "42 seed 1 100 randint puts 1 100 randint puts rand puts";
+
+        assert_eq!(eval_to_string(program), eval_to_string(program));
+        assert_eq!(eval_to_string(program), "14\n92\n0.27860113025513866\n");
+    }
+
+    #[test]
+    fn random_numbers_stay_in_range() {
+        let runner = run("rand 0 100 randint 5 5 randint");
+
+        assert_eq!(runner.token_stack[2].token_value.as_ref(), "5");
+        assert!(runner.token_stack[1].token_value.parse::<usize>().unwrap() <= 100);
+        let float = runner.token_stack[0].token_value.parse::<f64>().unwrap();
+        assert!((0.0..1.0).contains(&float));
+    }
+
+    #[test]
+    fn randint_needs_its_bounds_in_order() {
+        assert_eq!(run_err("5 1 randint").kind, ErrorKind::Value);
+        assert_eq!(run_err("1 2.5 randint").kind, ErrorKind::MismatchedTypes);
+        assert_eq!(run_err("\"a\" seed").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn divisible_needs_ints() {
+        let error = run_err("10 2.5 divisible");
+
+        assert_eq!(error.kind, ErrorKind::MismatchedTypes);
+        assert_eq!(
+            error.to_string(),
+            "Mismatched types: divisible expects a Int, found Float"
+        );
+    }
+
+    #[test]
+    fn select_picks_the_first_value_when_true() {
+        assert_eq!(eval_to_string("true \"yes\" \"no\" select puts"), "yes\n");
+    }
+
+    #[test]
+    fn select_picks_the_second_value_when_false() {
+        assert_eq!(eval_to_string("1 2 > \"yes\" \"no\" select puts"), "no\n");
+    }
+
+    #[test]
+    fn select_needs_a_bool() {
+        let error = run_err("1 \"yes\" \"no\" select");
+
+        assert_eq!(
+            error.to_string(),
+            "Mismatched types: select expects a Bool, found Int"
+        );
+    }
+
+    #[test]
+    fn throw_raises_a_user_error() {
+        let error = run_err("1 puts \"bad input\" throw 2 puts");
+
+        assert_eq!(error.kind, ErrorKind::User);
+        assert_eq!(error.message, "bad input");
+        assert_eq!(error.to_string(), "UserError: bad input");
+        assert_eq!(error.span.column, 20);
+    }
+
+    #[test]
+    fn throw_needs_a_string_message() {
+        assert_eq!(run_err("404 throw").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn raise_names_the_error() {
+        let error = run_err("\"ConfigError\" \"config missing\" raise");
+
+        assert_eq!(error.kind, ErrorKind::User);
+        assert_eq!(error.to_string(), "ConfigError: config missing");
+        assert_eq!(error.span.column, 32);
+    }
+
+    #[test]
+    fn raised_errors_can_be_caught() {
+        assert_eq!(
+            eval_to_string("try \"ConfigError\" \"config missing\" raise catch puts end"),
+            "config missing\n"
+        );
+    }
+
+    #[test]
+    fn raise_needs_a_name_and_a_message() {
+        assert_eq!(run_err("\"Oops\" 1 raise").kind, ErrorKind::MismatchedTypes);
+        assert_eq!(run_err("1 \"oops\" raise").kind, ErrorKind::MismatchedTypes);
+        assert_eq!(
+            run_err("\"Bad name\" \"oops\" raise").message,
+            "raise needs an error name without spaces, not \"Bad name\""
+        );
+    }
+
+    #[test]
+    fn if_runs_only_the_branch_its_bool_picks() {
+        assert_eq!(
+            eval_to_string("5 3 > if \"big\" else bogus end puts"),
+            "big\n"
+        );
+        assert_eq!(
+            eval_to_string("1 3 > if bogus else \"small\" end puts"),
+            "small\n"
+        );
+    }
+
+    #[test]
+    fn ifs_push_values_for_whatever_comes_after() {
+        assert_eq!(eval_to_string("2 true if 3 else 4 end + puts"), "5\n");
+        assert_eq!(
+            eval_to_string(concat!(
+                "def size n { n 10 > if \"big\" else n 3 > if \"medium\" else \"small\" end end } ",
+                "20 size puts 5 size puts 1 size puts"
+            )),
+            "big\nmedium\nsmall\n"
+        );
+    }
+
+    #[test]
+    fn if_needs_a_bool_an_else_and_an_end() {
+        assert_eq!(
+            run_err("1 if 2 else 3 end").kind,
+            ErrorKind::MismatchedTypes
+        );
+
+        let error = run_err("true if 1 end");
+        assert_eq!(error.message, "This if needs an else before its end");
+        assert_eq!(error.span.column, 11);
+
+        let error = run_err("true if 1 else 2");
+        assert_eq!(error.message, "This if is missing its end");
+        assert_eq!(error.span.column, 6);
+        assert_eq!(run_err("1 else").message, "else can only follow if");
+    }
+
+    fn matched(value: &str) -> String {
+        eval_to_string(&format!(
+            "{} match 1 {{ \"one\" }} \"two\" {{ \"two\" }} true {{ \"yes\" }} _ {{ \"other\" }} end puts",
+            value
+        ))
+    }
+
+    #[test]
+    fn match_runs_the_block_of_the_first_equal_pattern() {
+        assert_eq!(matched("1"), "one\n");
+        assert_eq!(matched("\"two\""), "two\n");
+        assert_eq!(matched("true"), "yes\n");
+        assert_eq!(matched("1.0"), "other\n");
+        assert_eq!(
+            eval_to_string("2 match 2 { \"first\" } 2 { bogus } end puts"),
+            "first\n"
+        );
+    }
+
+    #[test]
+    fn a_match_with_nothing_equal_is_an_error() {
+        let error = run_err("7 match 1 { 2 } end");
+
+        assert_eq!(error.kind, ErrorKind::Value);
+        assert_eq!(error.message, "Nothing in this match is 7");
+        assert_eq!(error.span.column, 3);
+        assert_eq!(
+            run_err("\"x\" match end").message,
+            "Nothing in this match is \"x\""
+        );
+    }
+
+    #[test]
+    fn matches_need_literal_patterns_with_blocks() {
+        assert_eq!(
+            run_err("1 match x { 2 } end").message,
+            "Expected a value or _ to match, found x"
+        );
+        assert_eq!(
+            run_err("1 match 1 2 end").message,
+            "This pattern needs a { block } to run"
+        );
+        assert_eq!(
+            run_err("1 match 1 { 2 ").message,
+            "This match is missing its end"
+        );
+    }
+
+    #[test]
+    fn match_blocks_can_hold_other_blocks() {
+        assert_eq!(
+            eval_to_string(concat!(
+                "def describe n { n match 0 { \"zero\" } _ { n 9 > if \"big\" else \"small\" end } end } ",
+                "0 describe puts 5 describe puts"
+            )),
+            "zero\nsmall\n"
+        );
+    }
+
+    #[test]
+    fn try_runs_its_body_and_skips_the_handler() {
+        assert_eq!(
+            eval_to_string("try 1 puts catch \"handled\" puts end 2 puts"),
+            "1\n2\n"
+        );
+    }
+
+    #[test]
+    fn catch_gets_the_message_of_an_error() {
+        assert_eq!(
+            eval_to_string("try \"bad input\" throw catch puts end"),
+            "bad input\n"
+        );
+        assert_eq!(
+            eval_to_string("try 1 \"a\" + catch \"add failed\" puts end"),
+            "add failed\n"
+        );
+    }
+
+    #[test]
+    fn a_failed_try_puts_the_stack_back() {
+        let runner = run("1 2 try + \"oops\" throw catch let message end");
+
+        let values: Vec<&str> = runner.token_stack.iter().map(|t| &*t.token_value).collect();
+        assert_eq!(values, ["1", "2"]);
+        assert_eq!(&*runner.variables()["message"].token_value, "oops");
+    }
+
+    #[test]
+    fn catch_handles_errors_from_keywords_and_unknown_names() {
+        assert_eq!(
+            eval_to_string("try 5 10 0 clamp catch puts end"),
+            "clamp has nothing from 10 to 0\n"
+        );
+        assert_eq!(
+            eval_to_string("try bogus catch puts end"),
+            "No such keyword: bogus\n"
+        );
+    }
+
+    #[test]
+    fn what_a_failed_try_did_before_failing_is_kept() {
+        let out = SharedBuffer::default();
+        let mut runner = runner().with_output(Box::new(out.clone()));
+
+        runner
+            .run(lex(
+                "try \"before\" puts 7 let x bogus catch let message end",
+            ))
+            .unwrap();
+
+        assert_eq!(out.contents(), "before\n");
+        assert_eq!(&*runner.variables()["x"].token_value, "7");
+        assert!(runner.token_stack.is_empty());
+    }
+
+    #[test]
+    fn tries_nest() {
+        assert_eq!(
+            eval_to_string(concat!(
+                "try try \"inner\" throw catch puts end \"outer\" throw ",
+                "catch puts end"
+            )),
+            "inner\nouter\n"
+        );
+    }
+
+    #[test]
+    fn errors_in_a_handler_are_not_caught_by_it() {
+        assert_eq!(
+            run_err("try \"first\" throw catch \"second\" throw end").message,
+            "second"
+        );
+    }
+
+    #[test]
+    fn limits_cannot_be_caught() {
+        let limits = ExecutionLimits {
+            max_steps: Some(50),
+            ..ExecutionLimits::default()
+        };
+        let error = limited(limits, "def forever { forever } try forever catch end");
+
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!(
+            run_err("def down { down } try down catch end").kind,
+            ErrorKind::Recursion
+        );
+    }
+
+    #[test]
+    fn catch_can_give_the_message_a_name() {
+        let divide = "def divide a b { b 0 == \
+                      if \"ZeroDivisionError\" \"division by zero\" raise else a b fdiv end }";
+
+        assert_eq!(
+            eval_to_string(&format!(
+                "{} try {{ 1 0 divide puts }} catch err {{ err puts }} \"after\" puts",
+                divide
+            )),
+            "division by zero\nafter\n"
+        );
+        assert_eq!(
+            run_err(&format!("{} 1 0 divide", divide)).label.as_deref(),
+            Some("ZeroDivisionError")
+        );
+        assert_eq!(
+            eval_to_string("try { bogus } catch err { err puts }"),
+            "No such keyword: bogus\n"
+        );
+    }
+
+    #[test]
+    fn a_catch_without_a_name_pushes_the_message() {
+        assert_eq!(
+            eval_to_string("try { \"bad\" throw } catch { puts }"),
+            "bad\n"
+        );
+        assert_eq!(
+            eval_to_string("try { 1 puts } catch err { \"handled\" puts } 2 puts"),
+            "1\n2\n"
+        );
+    }
+
+    #[test]
+    fn block_tries_keep_what_they_did_and_nest() {
+        let runner = run("1 try { 2 let x 3 bogus } catch err { }");
+
+        let values: Vec<&str> = runner.token_stack.iter().map(|t| &*t.token_value).collect();
+        assert_eq!(values, ["1"]);
+        assert_eq!(&*runner.variables()["x"].token_value, "2");
+        assert_eq!(
+            &*runner.variables()["err"].token_value,
+            "No such keyword: bogus"
+        );
+        assert_eq!(
+            eval_to_string(concat!(
+                "try { try { \"inner\" throw } catch err { err puts } \"outer\" throw } ",
+                "catch err { err puts }"
+            )),
+            "inner\nouter\n"
+        );
+        assert_eq!(
+            eval_to_string("true if try { \"x\" throw } catch err { err puts } else end"),
+            "x\n"
+        );
+        assert_eq!(
+            run_err("try { \"first\" throw } catch err { err throw }").message,
+            "first"
+        );
+    }
+
+    #[test]
+    fn block_tries_need_a_catch_and_a_handler() {
+        for source in [
+            "try { 1",
+            "try { 1 } 2",
+            "try { 1 } catch",
+            "try { 1 } catch err 2",
+        ] {
+            assert_eq!(run_err(source).kind, ErrorKind::Syntax, "{}", source);
+        }
+        assert_eq!(
+            run_err("try { 1 } catch puts { }").kind,
+            ErrorKind::NameError
+        );
+    }
+
+    #[test]
+    fn try_needs_a_catch_and_an_end() {
+        for source in ["try 1", "try 1 catch 2", "try 1 end", "catch", "end"] {
+            assert_eq!(run_err(source).kind, ErrorKind::Syntax, "{}", source);
+        }
+    }
+
+    #[test]
+    fn errors_in_words_carry_the_calls_they_happened_in() {
+        let error = run_err("def inner { 1 \"a\" + } def outer { inner } outer");
+
+        let trace: Vec<(&str, usize)> = error
+            .trace
+            .iter()
+            .map(|frame| (&*frame.name, frame.span.column))
+            .collect();
+        assert_eq!(trace, [("inner", 35), ("outer", 43)]);
+        assert!(run_err("1 \"a\" +").trace.is_empty());
+    }
+
+    #[test]
+    fn count_runs_a_function_on_every_element() {
+        assert_eq!(
+            eval_to_string("def big n { n 2 > } 1 2 3 4 collect fn big count puts"),
+            "2\n"
+        );
+        assert_eq!(
+            eval_to_string("def vowel c { c \"a\" == } \"banana\" fn vowel count puts"),
+            "3\n"
+        );
+    }
+
+    #[test]
+    fn count_pushes_zero_when_nothing_matches() {
+        assert_eq!(
+            eval_to_string("def big n { n 10 > } 1 2 3 collect fn big count puts"),
+            "0\n"
+        );
+        assert_eq!(
+            eval_to_string("def big n { n 10 > } collect fn big count puts"),
+            "0\n"
+        );
+    }
+
+    #[test]
+    fn count_needs_a_function_that_leaves_a_bool() {
+        assert_eq!(run_err("1 collect count").kind, ErrorKind::MismatchedTypes);
+        assert_eq!(
+            run_err("def big n { n 2 > } 1 collect big count").kind,
+            ErrorKind::MismatchedTypes
+        );
+        assert_eq!(
+            run_err("def same n { n } 1 collect fn same count").message,
+            "<function same/1> should leave a Bool for count, found Int"
+        );
+        assert_eq!(
+            run_err("def f n { true } 5 fn f count").kind,
+            ErrorKind::MismatchedTypes
+        );
+    }
+
+    #[test]
+    fn fn_pushes_a_word_as_a_value() {
+        assert_eq!(
+            eval_to_string("def big n { n 2 > } fn big puts"),
+            "<function big/1>\n"
+        );
+        assert_eq!(eval_to_string("def hi { \"hi\" puts } fn hi call"), "hi\n");
+        assert_eq!(run_err("fn missing").kind, ErrorKind::NameError);
+        assert_eq!(run_err("1 call").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn functions_are_equal_when_they_name_the_same_word() {
+        assert_eq!(
+            eval_to_string("def f { } def g { } fn f fn f == puts fn f fn g == puts"),
+            "true\nfalse\n"
+        );
+    }
+
+    #[test]
+    fn calling_a_function_ignores_variables_with_its_name() {
+        assert_eq!(
+            eval_to_string("def big n { n 2 > } 1 let big 3 fn big call puts"),
+            "true\n"
+        );
+    }
+
+    #[test]
+    fn functions_made_inside_a_word_keep_its_variables() {
+        let adders = "def add x { x n + } def make_adder n { fn add } \
+                      1 make_adder let inc 10 make_adder let ten";
+
+        assert_eq!(
+            eval_to_string(&format!("{} 5 inc call puts 5 ten call puts", adders)),
+            "6\n15\n"
+        );
+        assert_eq!(
+            eval_to_string(&format!(
+                "{} inc ten == puts 1 make_adder inc == puts",
+                adders
+            )),
+            "false\ntrue\n"
+        );
+        assert_eq!(
+            run_err(&format!("{} 5 add", adders)).kind,
+            ErrorKind::NameError
+        );
+    }
+
+    #[test]
+    fn captured_variables_last_between_calls() {
+        assert_eq!(
+            eval_to_string(
+                "def up { n 1 + let n n } def make_counter n { fn up } \
+                 0 make_counter let counter counter call puts counter call puts"
+            ),
+            "1\n2\n"
+        );
+        assert_eq!(
+            eval_to_string("def show { n puts } def f n { fn show 2 let n } 1 f call"),
+            "1\n"
+        );
+    }
+
+    #[test]
+    fn counters_made_by_separate_calls_count_separately() {
+        let counters = "def up { n 1 + let n n } def make_counter n { fn up } \
+                        0 make_counter let a 10 make_counter let b";
+
+        assert_eq!(
+            eval_to_string(&format!(
+                "{} a call puts a call puts b call puts a call puts b call puts",
+                counters
+            )),
+            "1\n2\n11\n3\n12\n"
+        );
+        assert_eq!(
+            eval_to_string(&format!("{} a let c a call puts c call puts", counters)),
+            "1\n2\n"
+        );
+    }
+
+    #[test]
+    fn map_lists_what_a_function_leaves_for_each_element() {
+        assert_eq!(
+            eval_to_string("def double n { n 2 * } 1 2 3 collect fn double map puts"),
+            "[2, 4, 6]\n"
+        );
+        assert_eq!(
+            eval_to_string("def vowel c { c \"a\" == } \"ab\" fn vowel map puts"),
+            "[true, false]\n"
+        );
+        assert_eq!(
+            run_err("def two n { n n } 1 collect fn two map").kind,
+            ErrorKind::Value
+        );
+    }
+
+    #[test]
+    fn filter_keeps_the_elements_a_function_is_true_of() {
+        assert_eq!(
+            eval_to_string("def big n { n 2 > } 1 2 3 4 collect fn big filter puts"),
+            "[3, 4]\n"
+        );
+        assert_eq!(
+            run_err("def same n { n } 1 collect fn same filter").kind,
+            ErrorKind::MismatchedTypes
+        );
+        assert_eq!(
+            run_err("def big n { n 2 > } 1 collect 2 filter").kind,
+            ErrorKind::MismatchedTypes
+        );
+    }
+
+    #[test]
+    fn map_filter_and_count_take_a_block_instead_of_a_function() {
+        assert_eq!(
+            eval_to_string("1 2 3 collect map { 2 * } puts"),
+            "[2, 4, 6]\n"
+        );
+        assert_eq!(
+            eval_to_string("1 2 3 4 collect filter { 2 > } puts"),
+            "[3, 4]\n"
+        );
+        assert_eq!(eval_to_string("\"banana\" count { \"a\" == } puts"), "3\n");
+        assert_eq!(
+            eval_to_string("10 let n 1 2 collect map { n + } puts"),
+            "[11, 12]\n"
+        );
+    }
+
+    #[test]
+    fn blocks_given_to_map_nest() {
+        assert_eq!(
+            eval_to_string(
+                "1 2 collect let a 3 4 collect let b a map { let x b map { x + } } puts"
+            ),
+            "[[4, 5], [5, 6]]\n"
+        );
+    }
+
+    #[test]
+    fn blocks_given_to_map_must_leave_one_value() {
+        let error = run_err("1 2 collect map { 1 }");
+        assert_eq!(error.kind, ErrorKind::Value);
+        assert_eq!(
+            error.message,
+            "The block should leave one value for map, not 2"
+        );
+
+        let error = run_err("1 2 collect filter { }");
+        assert_eq!(error.kind, ErrorKind::MismatchedTypes);
+        assert_eq!(run_err("1 2 collect map { 2 *").kind, ErrorKind::Syntax);
+    }
+
+    #[test]
+    fn sort_orders_ints() {
+        assert_eq!(eval_to_string("3 1 2 collect sort puts"), "[1, 2, 3]\n");
+    }
+
+    #[test]
+    fn sort_orders_floats_and_ints_by_value() {
+        assert_eq!(
+            eval_to_string("2.5 .5 1.75 collect sort puts"),
+            "[0.5, 1.75, 2.5]\n"
+        );
+        assert_eq!(
+            eval_to_string("3 2.5 10 1 collect sort puts"),
+            "[1, 2.5, 3, 10]\n"
+        );
+        assert_eq!(
+            eval_to_string("-1 2.5 -3.5 0 collect sort puts"),
+            "[-3.5, -1, 0, 2.5]\n"
+        );
+    }
+
+    #[test]
+    fn sort_puts_nan_last_wherever_it_starts() {
+        for source in ["0 0 fdiv 3.0 1", "3.0 0 0 fdiv 1", "3.0 1 0 0 fdiv"] {
+            assert_eq!(
+                eval_to_string(&format!("{} collect sort puts", source)),
+                "[1, 3.0, NaN]\n",
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn sort_orders_strings_by_their_characters() {
+        assert_eq!(
+            eval_to_string("\"pear\" \"apple\" \"Zebra\" collect sort puts"),
+            "[\"Zebra\", \"apple\", \"pear\"]\n"
+        );
+        assert_eq!(eval_to_string("collect sort puts"), "[]\n");
+    }
+
+    #[test]
+    fn sort_rejects_mixed_lists() {
+        assert_eq!(
+            run_err("1 \"two\" collect sort").kind,
+            ErrorKind::MismatchedTypes
+        );
+        assert_eq!(
+            run_err("true false collect sort").kind,
+            ErrorKind::MismatchedTypes
+        );
+        assert_eq!(run_err("1 sort").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn unique_keeps_the_first_of_each_value() {
+        assert_eq!(
+            eval_to_string("1 2 2 3 1 collect unique puts"),
+            "[1, 2, 3]\n"
+        );
+        assert_eq!(
+            eval_to_string("\"b\" 1.5 \"a\" \"b\" 1.50 collect unique puts"),
+            "[\"b\", 1.5, \"a\"]\n"
+        );
+    }
+
+    #[test]
+    fn unique_leaves_lists_without_duplicates_alone() {
+        assert_eq!(eval_to_string("3 1 2 collect unique puts"), "[3, 1, 2]\n");
+        assert_eq!(eval_to_string("collect unique puts"), "[]\n");
+    }
+
+    #[test]
+    fn lists_are_equal_when_their_elements_are() {
+        assert_eq!(
+            eval_to_string("1.5 collect let a 1.50 collect a == puts"),
+            "true\n"
+        );
+        assert_eq!(
+            eval_to_string("\"a, b\" collect let s \"a\" \"b\" collect s == puts"),
+            "false\n"
+        );
+        assert_eq!(
+            eval_to_string("1 2 collect 3 collect let n 1 2.0 collect 3 collect n == puts"),
+            "false\n"
+        );
+        assert_eq!(
+            eval_to_string("1.5 collect 2 collect let n 1.50 collect 2 collect n == puts"),
+            "true\n"
+        );
+        assert_eq!(
+            eval_to_string("1.0 collect let a 1.00 collect a collect unique len puts"),
+            "1\n"
+        );
+    }
+
+    #[test]
+    fn unique_shrinks_all_duplicates_to_one() {
+        assert_eq!(eval_to_string("7 7 7 7 collect unique puts"), "[7]\n");
+    }
+
+    #[test]
+    fn unique_tells_types_apart() {
+        assert_eq!(eval_to_string("1 \"1\" collect unique len puts"), "2\n");
+        assert_eq!(run_err("1 unique").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn sum_and_product_combine_a_list() {
+        assert_eq!(eval_to_string("1 2 3 4 collect sum puts"), "10\n");
+        assert_eq!(eval_to_string("1 2 3 4 collect product puts"), "24\n");
+        assert_eq!(top(&run("1 2 collect sum")).token_type, TokenType::Int);
+    }
+
+    #[test]
+    fn sum_and_product_of_nothing_are_their_identities() {
+        assert_eq!(eval_to_string("collect sum puts"), "0\n");
+        assert_eq!(eval_to_string("collect product puts"), "1\n");
+    }
+
+    #[test]
+    fn mixed_lists_sum_as_floats() {
+        let runner = run("1 2.5 collect sum");
+
+        assert_eq!(top(&runner).token_type, TokenType::Float);
+        assert_eq!(&*top(&runner).token_value, "3.5");
+        assert_eq!(eval_to_string("2 .25 collect product puts"), "0.5\n");
+    }
+
+    #[test]
+    fn sum_needs_a_list_of_numbers() {
+        assert_eq!(
+            run_err("1 \"two\" collect sum").kind,
+            ErrorKind::MismatchedTypes
+        );
+        assert_eq!(run_err("3 product").kind, ErrorKind::MismatchedTypes);
+        assert_eq!(
+            run_err("9223372036854775807 2 collect product").kind,
+            ErrorKind::Overflow
+        );
+    }
+
+    #[test]
+    fn repeat_joins_copies_of_a_string() {
+        assert_eq!(eval_to_string("\"ab\" 3 repeat puts"), "ababab\n");
+        assert_eq!(eval_to_string("\"ab\" 1 repeat puts"), "ab\n");
+    }
+
+    #[test]
+    fn repeating_zero_times_gives_an_empty_string() {
+        let runner = run("\"ab\" 0 repeat");
+
+        assert_eq!(top(&runner).token_type, TokenType::String);
+        assert_eq!(&*top(&runner).token_value, "");
+    }
+
+    #[test]
+    fn repeat_refuses_strings_too_long_to_hold() {
+        let error = run_err("\"ab\" 9223372036854775807 repeat");
+        assert_eq!(error.kind, ErrorKind::Overflow);
+
+        let limits = ExecutionLimits {
+            max_value_size: Some(10),
+            ..ExecutionLimits::default()
+        };
+        assert_eq!(
+            limited(limits, "\"ab\" 6 repeat").kind,
+            ErrorKind::LimitExceeded
+        );
+    }
+
+    #[test]
+    fn repeat_refuses_a_negative_count() {
+        let error = run_err("\"ab\" -1 repeat");
+
+        assert_eq!(error.kind, ErrorKind::Value);
+        assert_eq!(error.message, "repeat can't repeat a string -1 times");
+    }
+
+    #[test]
+    fn repeat_needs_a_string_and_an_int() {
+        assert_eq!(run_err("3 \"ab\" repeat").kind, ErrorKind::MismatchedTypes);
+        assert_eq!(
+            run_err("\"ab\" 1.5 repeat").kind,
+            ErrorKind::MismatchedTypes
+        );
+    }
+
+    #[test]
+    fn trim_strips_whitespace_from_both_ends() {
+        assert_eq!(eval_to_string("\" hi \" trim puts"), "hi\n");
+        assert_eq!(eval_to_string("\"\t\n hi there\n\t\" trim len puts"), "8\n");
+        assert_eq!(eval_to_string("\"\u{3000}hi\u{2003}\" trim puts"), "hi\n");
+    }
+
+    #[test]
+    fn trim_start_and_end_strip_one_side() {
+        let both = "\"\t hi \n\"";
+
+        assert_eq!(
+            top(&run(&format!("{} trim_start", both))).token_value,
+            Rc::from("hi \n")
+        );
+        assert_eq!(
+            top(&run(&format!("{} trim_end", both))).token_value,
+            Rc::from("\t hi")
+        );
+    }
+
+    #[test]
+    fn trim_needs_a_string() {
+        assert_eq!(run_err("1 trim").kind, ErrorKind::MismatchedTypes);
+        assert_eq!(run_err("1 trim_end").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn contains_finds_a_string_anywhere_in_another() {
+        assert_eq!(eval_to_string("\"hello\" \"ell\" contains puts"), "true\n");
+        assert_eq!(eval_to_string("\"hello\" \"elo\" contains puts"), "false\n");
+    }
+
+    #[test]
+    fn starts_with_and_ends_with_look_at_one_end() {
+        assert_eq!(
+            eval_to_string(concat!(
+                "\"hello\" \"he\" starts_with puts \"hello\" \"lo\" starts_with puts ",
+                "\"hello\" \"lo\" ends_with puts \"hello\" \"he\" ends_with puts"
+            )),
+            "true\nfalse\ntrue\nfalse\n"
+        );
+    }
+
+    #[test]
+    fn every_string_holds_the_empty_string() {
+        for keyword in ["contains", "starts_with", "ends_with"] {
+            assert_eq!(
+                eval_to_string(&format!(
+                    "\"hello\" \"\" {} puts \"\" \"\" {} puts",
+                    keyword, keyword
+                )),
+                "true\ntrue\n",
+                "{}",
+                keyword
+            );
+        }
+        assert_eq!(eval_to_string("\"\" \"a\" contains puts"), "false\n");
+    }
+
+    #[test]
+    fn string_tests_need_two_strings() {
+        assert_eq!(
+            run_err("\"hello\" 1 contains").kind,
+            ErrorKind::MismatchedTypes
+        );
+        assert_eq!(
+            run_err("1 \"1\" ends_with").kind,
+            ErrorKind::MismatchedTypes
+        );
+    }
+
+    #[test]
+    fn replace_swaps_every_match() {
+        assert_eq!(
+            eval_to_string("\"a-b-c\" \"-\" \"_\" replace puts"),
+            "a_b_c\n"
+        );
+        assert_eq!(eval_to_string("\"aaaa\" \"aa\" \"b\" replace puts"), "bb\n");
+        assert_eq!(eval_to_string("\"a b\" \" \" \"\" replace puts"), "ab\n");
+    }
+
+    #[test]
+    fn replace_without_a_match_changes_nothing() {
+        assert_eq!(eval_to_string("\"abc\" \"x\" \"y\" replace puts"), "abc\n");
+    }
+
+    #[test]
+    fn replace_refuses_an_empty_string_to_replace() {
+        let error = run_err("\"abc\" \"\" \"x\" replace");
+
+        assert_eq!(error.kind, ErrorKind::Value);
+        assert_eq!(
+            error.to_string(),
+            "ValueError: replace can't replace an empty string"
+        );
+        assert_eq!(
+            run_err("\"abc\" \"b\" 1 replace").kind,
+            ErrorKind::MismatchedTypes
+        );
+    }
+
+    #[test]
+    fn format_fills_in_each_placeholder_in_order() {
+        assert_eq!(
+            eval_to_string("3 1.5 \"hi\" true \"x={} y={} {}{}\" format puts"),
+            "x=3 y=1.5 hitrue\n"
+        );
+        assert_eq!(
+            eval_to_string("1 2 collect false \"{} and {}\" format puts"),
+            "[1, 2] and false\n"
+        );
+    }
+
+    #[test]
+    fn format_without_placeholders_takes_no_values() {
+        let runner = run("1 \"plain {{text}}\" format");
+
+        assert_eq!(runner.token_stack.len(), 2);
+        assert_eq!(&*top(&runner).token_value, "plain {text}");
+    }
+
+    #[test]
+    fn format_can_give_numbers_a_precision() {
+        assert_eq!(
+            eval_to_string("2.0 3.14159 7 \"{:.1} {:.2} {:.0}\" format puts"),
+            "2.0 3.14 7\n"
+        );
+        let error = run_err("\"a\" \"{:.2}\" format");
+        assert_eq!(error.kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn float_precision_cuts_printed_floats() {
+        let out = SharedBuffer::default();
+        let mut runner = runner()
+            .with_float_precision(Some(2))
+            .with_output(Box::new(out.clone()));
+
+        runner
+            .run(lex(concat!(
+                "pi puts e puts 2. 3 fdiv puts 5 puts \"1.23456\" puts ",
+                "1.5 2 collect puts pi pi \"{} {:.4}\" format puts ",
+                "1. 0. fdiv puts pi 3.14 == puts"
+            )))
+            .unwrap();
+
+        assert_eq!(
+            out.contents(),
+            "3.14\n2.72\n0.67\n5\n1.23456\n[1.50, 2]\n3.14 3.1416\ninf\nfalse\n"
+        );
+        runner.run(lex("pi")).unwrap();
+        assert_eq!(
+            top(&runner).token_value.parse::<f64>(),
+            Ok(std::f64::consts::PI)
+        );
+    }
+
+    #[test]
+    fn float_precision_comes_from_the_config_and_is_capped() {
+        let config = Config {
+            float_precision: Some(1000),
+            ..Config::default()
+        };
+        let capped = Runner::from_config(config);
+
+        assert_eq!(capped.config().float_precision, Some(MAX_FLOAT_PRECISION));
+        assert_eq!(Runner::new().config().float_precision, None);
+        assert_eq!(
+            runner()
+                .with_float_precision(Some(0))
+                .config()
+                .float_precision,
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn format_precisions_stop_at_a_hundred() {
+        let error = run_err("1.5 \"{:.65536}\" format");
+
+        assert_eq!(error.kind, ErrorKind::Value);
+        assert_eq!(
+            error.message,
+            "format can show at most 100 digits after the point, not 65536"
+        );
+    }
+
+    #[test]
+    fn format_needs_a_value_for_every_placeholder() {
+        let error = run_err("1 \"{} {} {}\" format");
+
+        assert_eq!(error.kind, ErrorKind::StackUnderflow);
+        assert_eq!(
+            error.message,
+            "format has 3 placeholders but only 1 values to fill them"
+        );
+    }
+
+    #[test]
+    fn format_reports_braces_on_their_own() {
+        let error = run_err("1 \"{} }\" format");
+
+        assert_eq!(error.kind, ErrorKind::Value);
+        assert!(error.message.contains("write }} for a brace on its own"));
+    }
+
+    #[test]
+    fn indexof_finds_the_first_match() {
+        assert_eq!(eval_to_string("\"hello\" \"l\" indexof puts"), "2\n");
+        assert_eq!(eval_to_string("\"hello\" \"hello\" indexof puts"), "0\n");
+        assert_eq!(eval_to_string("\"hello\" \"\" indexof puts"), "0\n");
+    }
+
+    #[test]
+    fn indexof_pushes_minus_one_when_there_is_no_match() {
+        let runner = run("\"hello\" \"z\" indexof");
+
+        assert_eq!(top(&runner).token_type, TokenType::Int);
+        assert_eq!(&*top(&runner).token_value, "-1");
+    }
+
+    #[test]
+    fn indexof_counts_characters_rather_than_bytes() {
+        assert_eq!(eval_to_string("\"héllo wörld\" \"w\" indexof puts"), "6\n");
+        assert_eq!(eval_to_string("\"日本語\" \"語\" indexof puts"), "2\n");
+        assert_eq!(
+            run_err("\"hello\" 1 indexof").kind,
+            ErrorKind::MismatchedTypes
+        );
+    }
+
+    #[test]
+    fn equal_values_compare_true() {
+        assert_eq!(eval_to_string("3 3 == puts 3 4 == puts"), "true\nfalse\n");
+        assert_eq!(
+            eval_to_string("3 3 = puts \"a\" \"b\" != puts"),
+            "true\ntrue\n"
+        );
+    }
+
+    #[test]
+    fn values_of_different_types_are_never_equal() {
+        assert_eq!(
+            eval_to_string("1 \"1\" == puts 1 1.0 != puts"),
+            "false\ntrue\n"
+        );
+    }
+
+    #[test]
+    fn floats_compare_by_value() {
+        assert_eq!(eval_to_string("1.0 1.0 + 2.0 == puts"), "true\n");
+    }
+
+    #[test]
+    fn ints_compare_by_value_however_they_are_written() {
+        assert_eq!(
+            eval_to_string("007 7 == puts 007 7 != puts -0 0 == puts"),
+            "true\nfalse\ntrue\n"
+        );
+        assert_eq!(
+            eval_to_string("007 collect let a 7 collect a == puts"),
+            "true\n"
+        );
+        assert_eq!(
+            run_big("0099999999999999999999 99999999999999999999 == puts"),
+            "true\n"
+        );
+    }
+
+    #[test]
+    fn orders_numbers_and_strings() {
+        assert_eq!(
+            eval_to_string("1 2 < puts 2 2 <= puts 1.5 0.5 > puts \"b\" \"a\" >= puts"),
+            "true\ntrue\ntrue\ntrue\n"
+        );
+        assert_eq!(eval_to_string("2 1 < puts 1 2 >= puts"), "false\nfalse\n");
+    }
+
+    #[test]
+    fn ordering_values_of_different_types_is_an_error() {
+        assert_eq!(run_err("1 \"a\" <").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn true_and_false_push_bools() {
+        let runner = run("true");
+
+        assert_eq!(top(&runner).token_type, TokenType::Bool);
+        assert_eq!(eval_to_string("true false == puts"), "false\n");
+    }
+
+    #[test]
+    fn shifts_integers() {
+        assert_eq!(eval_to_string("1 3 << puts 20 2 >> puts"), "8\n5\n");
+    }
+
+    #[test]
+    fn shifting_by_too_many_bits_is_an_overflow() {
+        assert_eq!(run_err("1 64 <<").kind, ErrorKind::Overflow);
+    }
+
+    #[test]
+    fn ints_can_be_negative() {
+        assert_eq!(
+            eval_to_string("-3 5 + puts -3 -4 * puts 3 -4 * puts"),
+            "2\n12\n-12\n"
+        );
+        assert_eq!(eval_to_string("-3 2 < puts -3 -4 < puts"), "true\nfalse\n");
+        assert_eq!(eval_to_string("-2.5 1 fdiv puts"), "-2.5\n");
+    }
+
+    #[test]
+    fn negative_big_ints_are_exact() {
+        assert_eq!(
+            run_big("-9223372036854775808 -1 + puts"),
+            "-9223372036854775809\n"
+        );
+        assert_eq!(
+            run_big("-99999999999999999999 99999999999999999999 + puts"),
+            "0\n"
+        );
+        assert_eq!(run_big("-99999999999999999999 -1 < puts"), "true\n");
+    }
+
+    #[test]
+    fn integer_overflow_is_an_error() {
+        let error = run_err(&format!("{} 1 +", i64::MAX));
+
+        assert_eq!(error.kind, ErrorKind::Overflow);
+        assert_eq!(error.message, "Integer overflow while trying to add");
+    }
+
+    #[test]
+    fn step_limit_stops_a_run_partway() {
+        let mut runner = runner().with_step_limit(2);
+        let error = runner.run(lex("1 2 3")).unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!(error.span.column, 5);
+        assert_eq!(runner.token_stack.len(), 2);
+    }
+
+    fn limited(limits: ExecutionLimits, source: &str) -> Error {
+        runner().with_limits(limits).run(lex(source)).unwrap_err()
+    }
+
+    #[test]
+    fn step_budget_counts_steps_run_by_eval() {
+        let limits = ExecutionLimits {
+            max_steps: Some(100),
+            ..ExecutionLimits::default()
+        };
+        let error = limited(limits, "try \"def f { f } f\" eval catch end");
+
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+    }
+
+    #[test]
+    fn step_budget_stops_a_word_that_never_returns() {
+        let limits = ExecutionLimits {
+            max_steps: Some(100),
+            ..ExecutionLimits::default()
+        };
+        let error = limited(limits, "def forever { forever } forever");
+
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!(
+            error.to_string(),
+            "LimitExceededError: Gave up after 100 steps"
+        );
+    }
+
+    #[test]
+    fn stack_depth_is_limited() {
+        let limits = ExecutionLimits {
+            max_stack_depth: Some(2),
+            ..ExecutionLimits::default()
+        };
+        let error = limited(limits, "1 2 + 4 5 6");
+
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!(error.span.column, 9);
+    }
+
+    #[test]
+    fn call_depth_is_limited() {
+        let limits = ExecutionLimits {
+            max_call_depth: Some(3),
+            ..ExecutionLimits::default()
+        };
+        let error = limited(limits, "def down { down } down");
+
+        assert_eq!(
+            error.to_string(),
+            "LimitExceededError: Calls nested more than 3 deep"
+        );
+    }
+
+    #[test]
+    fn oversized_values_are_rejected() {
+        let limits = ExecutionLimits {
+            max_value_size: Some(8),
+            ..ExecutionLimits::default()
+        };
+
+        assert_eq!(
+            limited(limits, "\"a short\" \"far too long\"").to_string(),
+            "LimitExceededError: A value grew past 8 bytes"
+        );
+        assert_eq!(
+            limited(limits, "1 2 3 4 collect").kind,
+            ErrorKind::LimitExceeded
+        );
+    }
+
+    #[test]
+    fn nothing_is_limited_by_default() {
+        let mut runner = runner().with_limits(ExecutionLimits::default());
+        runner.run(lex(&"1 ".repeat(10_000))).unwrap();
+
+        assert_eq!(runner.token_stack.len(), 10_000);
+    }
+
+    #[test]
+    fn def_defines_a_word_that_runs_its_body() {
+        assert_eq!(eval_to_string("def twice { 2 * } 21 twice puts"), "42\n");
+    }
+
+    #[test]
+    fn words_bind_their_params_from_the_stack() {
+        assert_eq!(
+            eval_to_string("def later a b { b puts a puts } 1 2 later"),
+            "2\n1\n"
+        );
+    }
+
+    #[test]
+    fn words_can_call_other_words() {
+        assert_eq!(
+            eval_to_string("def sq n { n n * } def sum_sq a b { a sq b sq + } 3 4 sum_sq puts"),
+            "25\n"
+        );
+    }
+
+    #[test]
+    fn params_are_local_to_their_word() {
+        let error = run_err("def f n { n } 1 f n");
+
+        assert_eq!(error.kind, ErrorKind::NameError);
+        assert_eq!(error.span.column, 19);
+    }
+
+    #[test]
+    fn let_stores_a_variable() {
+        let runner = run("5 let x x x +");
+
+        assert_eq!(&*top(&runner).token_value, "10");
+        assert_eq!(&*runner.variables()["x"].token_value, "5");
+    }
+
+    #[test]
+    fn every_use_of_a_name_shares_one_symbol() {
+        let runner = run("def sq n { n n * }");
+        let words = runner.words();
+        let body = &words["sq"].body;
+
+        assert!(body[0].symbol.is_some());
+        assert_eq!(body[0].symbol, body[1].symbol);
+        assert!(Rc::ptr_eq(&body[0].token_value, &body[1].token_value));
+        assert!(Rc::ptr_eq(&body[0].token_value, &words["sq"].params[0]));
+    }
+
+    #[test]
+    fn errors_inside_words_point_into_the_body() {
+        let source = concat!(
+            "def half n {\n",
+            "  n \"2\" fdiv\n",
+            "}\n",
+            "def quarter n { n half half }\n",
+            "8 quarter",
+        );
+        let error = run_err(source);
+
+        assert_eq!(error.kind, ErrorKind::MismatchedTypes);
+        assert_eq!((error.span.line, error.span.column), (2, 9));
+    }
+
+    #[test]
+    fn errors_deep_in_nested_calls_point_at_the_failing_token() {
+        let source = concat!(
+            "def inner a b { a b < \"too small\" throw }\n",
+            "def middle n { n 1 inner }\n",
+            "def outer { 5 middle }\n",
+            "outer",
+        );
+        let error = run_err(source);
+
+        assert_eq!(error.kind, ErrorKind::User);
+        assert_eq!((error.span.line, error.span.column), (1, 35));
+    }
+
+    #[test]
+    fn words_see_global_variables() {
+        assert_eq!(
+            eval_to_string("10 let base def add_base n { n base + } 5 add_base puts"),
+            "15\n"
+        );
+    }
+
+    #[test]
+    fn keywords_cannot_be_redefined() {
+        assert_eq!(run_err("def puts { 1 }").kind, ErrorKind::NameError);
+        assert_eq!(run_err("1 let len").kind, ErrorKind::NameError);
+    }
+
+    #[test]
+    fn def_without_a_body_is_a_syntax_error() {
+        assert_eq!(run_err("def f 1").kind, ErrorKind::Syntax);
+        assert_eq!(run_err("def f { 1").kind, ErrorKind::Syntax);
+        assert_eq!(run_err("def").kind, ErrorKind::Syntax);
+    }
+
+    #[test]
+    fn stray_braces_are_syntax_errors() {
+        assert_eq!(run_err("{ 1 }").kind, ErrorKind::Syntax);
+    }
+
+    #[test]
+    fn endless_recursion_is_an_error() {
+        assert_eq!(
+            run_err("def forever { forever } forever").kind,
+            ErrorKind::Recursion
+        );
+    }
+
+    #[test]
+    fn reset_forgets_values_words_and_variables() {
+        let mut runner = run("1 2 def f { 3 } 4 let x");
+        runner.reset();
+
+        assert!(runner.token_stack.is_empty());
+        assert!(runner.words().is_empty());
+        assert!(runner.variables().is_empty());
+        assert_eq!(runner.run(lex("x")).unwrap_err().kind, ErrorKind::NameError);
+    }
+
+    #[test]
+    fn names_cover_everything_a_program_can_use() {
+        let mut runner = runner()
+            .register_fn("shout", 1, |args| Ok(args[0].clone()))
+            .unwrap();
+        runner.run(lex("def greet { } 1 let x")).unwrap();
+        let names = runner.names();
+
+        for name in ["puts", "trim_start", "shout", "greet", "x"] {
+            assert!(names.iter().any(|n| &**n == name), "{}", name);
+        }
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    fn traced(level: TraceLevel, source: &str) -> String {
+        let trace = SharedBuffer::default();
+        runner()
+            .with_output(Box::new(io::sink()))
+            .with_trace(level, Box::new(trace.clone()))
+            .run(lex(source))
+            .unwrap();
+        trace.contents()
+    }
+
+    #[test]
+    fn tracing_everything_describes_each_step() {
+        assert_eq!(
+            traced(TraceLevel::All, "2 let x x 3 + puts"),
+            concat!(
+                "trace 1 | 2 | stack: [] | vars: {}\n",
+                "trace 2 | let | stack: [2] | vars: {}\n",
+                "trace 3 | x | stack: [] | vars: {x: 2}\n",
+                "trace 4 | 3 | stack: [2] | vars: {x: 2}\n",
+                "trace 5 | + | stack: [2, 3] | vars: {x: 2}\n",
+                "trace 6 | puts | stack: [5] | vars: {x: 2}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn tracing_calls_describes_only_calls() {
+        assert_eq!(
+            traced(
+                TraceLevel::Calls,
+                "def double n { n n + } 1 let y 4 double puts"
+            ),
+            "trace 5 | double | stack: [4] | vars: {y: 1}\n"
+        );
+        assert!(traced(TraceLevel::All, "def double n { n n + } 4 double")
+            .contains("trace 4 | n | stack: [] | vars: {n: 4}\n"));
+    }
+
+    /// Runs `source` under the debugger with `commands` as its input,
+    /// returning the result and the whole transcript.
+    fn debugged(source: &str, commands: &'static str) -> (Result<(), Error>, String) {
+        let out = SharedBuffer::default();
+        let result = runner()
+            .with_output(Box::new(out.clone()))
+            .with_input(Box::new(LineReader::new(commands.as_bytes())))
+            .with_debugger()
+            .run(lex(source));
+        (result, out.contents())
+    }
+
+    #[test]
+    fn the_debugger_steps_and_shows_the_stack_and_variables() {
+        let (result, transcript) = debugged("2 let x x 3 + puts", "s\ns\np x\nstack\ns\ns\nc\n");
+
+        assert!(result.is_ok());
+        assert_eq!(
+            transcript,
+            concat!(
+                "line 1, column 1: 2\n(debug) ",
+                "line 1, column 3: let\n(debug) ",
+                "line 1, column 9: x\n(debug) x: Int = 2\n(debug) []\n(debug) ",
+                "line 1, column 11: 3\n(debug) ",
+                "line 1, column 13: +\n(debug) ",
+                "5\n",
+            )
+        );
+    }
+
+    #[test]
+    fn the_debugger_pauses_at_breakpoints() {
+        let (result, transcript) = debugged(
+            "def double n {\n  n n +\n}\n1 double\n2 double puts",
+            "b 2\nc\np n\nc\np n\nc\n",
+        );
+
+        assert!(result.is_ok());
+        assert!(transcript.ends_with(concat!(
+            "(debug) Breakpoint on line 2\n",
+            "(debug) line 2, column 3: n\n(debug) n: Int = 1\n",
+            "(debug) line 2, column 3: n\n(debug) n: Int = 2\n",
+            "(debug) 4\n",
+        )));
+    }
+
+    #[test]
+    fn quitting_the_debugger_stops_the_program_past_any_try() {
+        let (result, transcript) = debugged("try 1 puts catch puts end", "s\nq\n");
+
+        assert_eq!(result.unwrap_err().kind, ErrorKind::Stopped);
+        assert!(transcript.ends_with("line 1, column 5: 1\n(debug) "));
+    }
+
+    #[test]
+    fn the_debugger_runs_to_the_end_once_its_input_runs_out() {
+        let (result, transcript) = debugged("1 puts 2 puts", "bogus\n");
+
+        assert!(result.is_ok());
+        assert!(transcript.contains("Unknown debugger command: bogus\ns, step"));
+        assert!(transcript.ends_with("(debug) 1\n2\n"));
+    }
+
+    #[test]
+    fn the_default_config_is_a_new_runner() {
+        assert_eq!(Runner::new().config(), Config::default());
+        assert_eq!(
+            Runner::from_config(Config::default()).config(),
+            Config::default()
+        );
+    }
+
+    #[test]
+    fn config_sets_up_the_runner_like_its_builders() {
+        let config = Config {
+            big_ints: true,
+            limits: ExecutionLimits {
+                max_steps: Some(100),
+                ..ExecutionLimits::default()
+            },
+            prelude: true,
+            stats: true,
+            float_precision: Some(3),
+        };
+        let built = Runner::new()
+            .with_big_ints()
+            .with_step_limit(100)
+            .with_prelude()
+            .with_stats()
+            .with_float_precision(Some(3));
+        let mut runner = Runner::from_config(config).with_output(Box::new(io::sink()));
+
+        assert_eq!(runner.config(), config);
+        assert_eq!(built.config(), config);
+        assert!(runner.run(lex("3 7 max")).is_ok());
+        assert!(runner.stats().contains_key("max"));
+    }
+
+    #[test]
+    fn prelude_words_are_available() {
+        let out = SharedBuffer::default();
+        let mut runner = runner().with_output(Box::new(out.clone())).with_prelude();
+
+        runner
+            .run(lex(
+                "3 7 max puts 3 7 min puts 2 9 4 max3 puts 7 odd puts 3 4 average puts",
+            ))
+            .unwrap();
+
+        assert_eq!(out.contents(), "7\n3\n9\ntrue\n3.5\n");
+    }
+
+    #[test]
+    fn the_prelude_leaves_nothing_behind() {
+        let runner = runner().with_stats().with_prelude();
+
+        assert!(runner.token_stack.is_empty());
+        assert!(runner.variables().is_empty());
+        assert!(runner.stats().is_empty());
+    }
+
+    #[test]
+    fn the_prelude_loads_whatever_the_limits() {
+        let mut runner = runner().with_step_limit(1).with_prelude();
+
+        assert!(runner.words().contains_key("square"));
+        assert_eq!(
+            runner.run(lex("1 2")).unwrap_err().kind,
+            ErrorKind::LimitExceeded
+        );
+    }
+
+    #[test]
+    fn reset_loads_the_prelude_again() {
+        let mut runner = runner().with_prelude();
+        runner.run(lex("def extra { 1 }")).unwrap();
+        runner.reset();
+
+        assert!(runner.words().contains_key("max"));
+        assert!(!runner.words().contains_key("extra"));
+    }
+
+    #[test]
+    fn floats_with_a_dot_at_either_end_add_like_any_other() {
+        assert_eq!(eval_to_string("5. 1.5 + puts"), "6.5\n");
+        assert_eq!(eval_to_string(".5 5.0 + puts"), "5.5\n");
+    }
+
+    fn with_native() -> Runner {
+        runner()
+            .register_fn("join", 2, |args| {
+                let joined = format!("{}{}", args[0].token_value, args[1].token_value);
+                Ok(Token::new(TokenType::String, joined))
+            })
+            .unwrap()
+            .register_fn("fail", 0, |_| Err(String::from("it broke")))
+            .unwrap()
+    }
+
+    #[test]
+    fn registered_functions_get_their_arguments_in_push_order() {
+        let mut runner = with_native();
+        runner.run(lex("1 \"a\" \"b\" join")).unwrap();
+
+        assert_eq!(runner.token_stack.len(), 2);
+        assert_eq!(&*top(&runner).token_value, "ab");
+        assert_eq!(top(&runner).span.column, 11);
+    }
+
+    #[test]
+    fn registered_functions_need_enough_arguments() {
+        let error = with_native().run(lex("\"a\" join")).unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::StackUnderflow);
+    }
+
+    #[test]
+    fn registered_function_failures_are_runtime_errors() {
+        let error = with_native().run(lex("fail")).unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Native);
+        assert_eq!(error.message, "it broke");
+    }
+
+    #[test]
+    fn keywords_and_taken_names_cannot_be_registered() {
+        let ok = |_: &[Token]| Ok(Token::new(TokenType::Nil, "nil"));
+
+        assert!(runner().register_fn("puts", 0, ok).is_err());
+        assert!(runner().register_fn("two words", 0, ok).is_err());
+        assert!(with_native().register_fn("join", 0, ok).is_err());
+    }
+
+    #[test]
+    fn programs_cannot_redefine_registered_functions() {
+        let error = with_native().run(lex("def join { 1 }")).unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::NameError);
+    }
+
+    #[test]
+    fn stats_count_each_operation() {
+        let mut runner = runner().with_stats();
+        runner
+            .run(lex("def sq n { n n * } 1 2 + puts 3 sq puts"))
+            .unwrap();
+
+        let stats = runner.stats();
+        let expected = [
+            ("def", 1),
+            ("Int", 3),
+            ("Plus", 1),
+            ("puts", 2),
+            ("sq", 1),
+            ("n", 2),
+            ("Multiply", 1),
+        ];
+        assert_eq!(stats.len(), expected.len());
+        for (operation, count) in expected {
+            assert_eq!(stats[operation], count, "{}", operation);
+        }
+    }
+
+    #[test]
+    fn stats_are_empty_unless_turned_on() {
+        assert!(run("1 2 +").stats().is_empty());
+    }
+
+    #[test]
+    fn add_sums_two_integers() {
+        let runner = run("12 13 +");
+
+        assert_eq!(runner.token_stack.len(), 1);
+        assert_eq!(top(&runner).token_type, TokenType::Int);
+        assert_eq!(&*top(&runner).token_value, "25");
+    }
+
+    #[test]
+    fn multiply_multiplies() {
+        assert_eq!(&*top(&run("6 7 *")).token_value, "42");
+    }
+
+    #[test]
+    fn add_sums_floats() {
+        let runner = run("1.5 2.25 +");
+
+        assert_eq!(top(&runner).token_type, TokenType::Float);
+        assert_eq!(&*top(&runner).token_value, "3.75");
+    }
+
+    #[test]
+    fn operator_results_feed_other_operators() {
+        assert_eq!(&*top(&run("2 3 * 4 +")).token_value, "10");
+        assert_eq!(&*top(&run("2 3 + 4 *")).token_value, "20");
+        assert_eq!(&*top(&run("1 2 + 3 4 + *")).token_value, "21");
+    }
+
+    #[test]
+    fn mismatched_types_are_an_error_at_the_operator() {
+        let error = run_err("1 \"a\" +");
+
+        assert_eq!(error.kind, ErrorKind::MismatchedTypes);
+        assert_eq!(error.span, Span::new(6, 7, 1, 7));
+    }
+
+    #[test]
+    fn unknown_keywords_are_an_error() {
+        let error = run_err("1 frobnicate");
+
+        assert_eq!(
+            error.to_string(),
+            "Unknown keyword error: No such keyword: frobnicate"
+        );
+        assert_eq!(error.kind, ErrorKind::NameError);
+        assert_eq!(error.span, Span::new(2, 12, 1, 3));
+    }
+
+    #[test]
+    fn popping_an_empty_stack_is_an_error() {
+        assert_eq!(run_err("puts").kind, ErrorKind::StackUnderflow);
+    }
+
+    #[test]
+    fn arithmetic_prints_nothing() {
+        assert_eq!(eval_to_string("5 5 +"), "");
+        assert_eq!(eval_to_string("5 5 *"), "");
+        assert_eq!(eval_to_string("1.5 2.0 * 3.0 < 1 4 << 2 >> =="), "");
+        assert_eq!(eval_to_string("-5 abs -3 sign"), "");
+    }
+
+    #[test]
+    fn puts_prints_the_top_of_the_stack_once() {
+        assert_eq!(eval_to_string("5 5 + puts"), "10\n");
+        assert_eq!(eval_to_string("2 3 * 4 + puts"), "10\n");
+        assert_eq!(eval_to_string("\"Hello, world!\" puts"), "Hello, world!\n");
+    }
+
+    #[test]
+    fn output_before_an_error_is_kept() {
+        assert!(eval_to_string("1 puts puts")
+            .starts_with("1\nStackUnderflowError: Not enough values on the stack\n"));
+    }
+
+    #[test]
+    fn now_pushes_epoch_seconds() {
+        let runner = run("now");
+
+        assert_eq!(top(&runner).token_type, TokenType::Int);
+        assert!(top(&runner).token_value.parse::<u64>().unwrap() > 0);
+    }
+
+    #[test]
+    fn time_runs_its_block_and_pushes_milliseconds() {
+        let runner = run("time { 1 2 + let x } 5");
+
+        assert_eq!(top(&runner).token_value.as_ref(), "5");
+        let elapsed = &runner.token_stack[0];
+        assert_eq!(elapsed.token_type, TokenType::Int);
+        assert!(elapsed.token_value.parse::<u64>().is_ok());
+        assert_eq!(eval_to_string("time { \"hi\" puts } let t"), "hi\n");
+    }
+
+    #[test]
+    fn time_needs_a_block() {
+        assert_eq!(run_err("time 1").kind, ErrorKind::Syntax);
+        assert_eq!(run_err("time { 1").kind, ErrorKind::Syntax);
+        assert_eq!(run_err("time { puts }").kind, ErrorKind::StackUnderflow);
+    }
+
+    #[test]
+    fn blocks_keep_the_blocks_nested_inside_them() {
+        let mut tokens = lex("1 { 2 } { { } } } 3").into_iter();
+        let block = take_block(&mut tokens).unwrap();
+
+        assert_eq!(block.len(), 8);
+        assert_eq!(tokens.next().unwrap().token_value.as_ref(), "3");
+        assert_eq!(take_block(lex("1 { 2 }")), None);
+        assert_eq!(eval_to_string("time { def f { { } } } let t 1 puts"), "1\n");
+    }
+
+    #[test]
+    fn getenv_pushes_the_variable_value() {
+        env::set_var("PROGLANG_TEST_GETENV", "from the test");
+
+        assert_eq!(
+            eval_to_string("\"PROGLANG_TEST_GETENV\" getenv puts"),
+            "from the test\n"
+        );
+    }
+
+    #[test]
+    fn getenv_pushes_an_empty_string_when_unset() {
+        env::remove_var("PROGLANG_TEST_GETENV_UNSET");
+
+        assert_eq!(
+            eval_to_string("\"PROGLANG_TEST_GETENV_UNSET\" getenv puts"),
+            "\n"
+        );
+        // So a default can be picked for it.
+        assert_eq!(
+            eval_to_string(concat!(
+                "\"PROGLANG_TEST_GETENV_UNSET\" getenv let home\n",
+                "home \"\" == \"/root\" home select puts"
+            )),
+            "/root\n"
+        );
+    }
+
+    #[test]
+    fn env_pushes_the_variable_value_or_nil() {
+        env::set_var("PROGLANG_TEST_ENV", "from the test");
+        env::set_var("PROGLANG_TEST_ENV_EMPTY", "");
+        env::remove_var("PROGLANG_TEST_ENV_UNSET");
+
+        assert_eq!(
+            eval_to_string(concat!(
+                "\"PROGLANG_TEST_ENV\" env puts\n",
+                "\"PROGLANG_TEST_ENV_EMPTY\" env len puts\n",
+                "\"PROGLANG_TEST_ENV_UNSET\" env puts"
+            )),
+            "from the test\n0\nnil\n"
+        );
+        assert_eq!(
+            top(&run("\"PROGLANG_TEST_ENV_UNSET\" env")).token_type,
+            TokenType::Nil
+        );
+        assert_eq!(run_err("12 env").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn getenv_of_a_value_that_is_not_utf8_is_an_error() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        env::set_var("PROGLANG_TEST_GETENV_BYTES", OsStr::from_bytes(b"\xff"));
+
+        let error = run_err("\"PROGLANG_TEST_GETENV_BYTES\" getenv");
+
+        assert_eq!(error.kind, ErrorKind::Env);
+        assert_eq!(
+            error.message,
+            "The value of PROGLANG_TEST_GETENV_BYTES is not valid UTF-8"
+        );
+    }
+
+    #[test]
+    fn environment_variables_can_be_put_out_of_reach() {
+        env::set_var("PROGLANG_TEST_GETENV_DENIED", "secret");
+
+        let error = runner()
+            .with_env_access(false)
+            .run(lex("\"PROGLANG_TEST_GETENV_DENIED\" getenv"))
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Capability);
+        assert_eq!(
+            error.message,
+            "getenv needs environment access, which isn't allowed here"
+        );
+
+        let error = runner()
+            .with_env_access(false)
+            .run(lex("\"PROGLANG_TEST_GETENV_DENIED\" env"))
+            .unwrap_err();
+        assert_eq!(
+            error.message,
+            "env needs environment access, which isn't allowed here"
+        );
+    }
+
+    #[test]
+    fn args_pushes_the_arguments_as_a_list_of_strings() {
+        let out = SharedBuffer::default();
+        let mut runner = runner()
+            .with_output(Box::new(out.clone()))
+            .with_args([String::from("foo"), String::from("bar baz")]);
+
+        runner.run(lex("args puts args len puts")).unwrap();
+
+        assert_eq!(out.contents(), "[\"foo\", \"bar baz\"]\n2\n");
+        assert_eq!(eval_to_string("args puts"), "[]\n");
+    }
+
+    fn exec(source: &str) -> Result<Runner, Error> {
+        let mut runner = runner().with_process_access(true);
+        runner.run(lex(source))?;
+        Ok(runner)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_pushes_what_the_command_printed() {
+        let runner = exec("\"echo hello  there\" exec").unwrap();
+
+        assert_eq!(top(&runner).token_value.as_ref(), "hello there\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn last_status_is_the_exit_status_of_the_last_command() {
+        let runner =
+            exec("last_status \"true\" exec last_status \"false\" exec last_status").unwrap();
+
+        let statuses: Vec<&str> = runner
+            .token_stack
+            .iter()
+            .filter(|token| token.token_type != TokenType::String)
+            .map(|token| token.token_value.as_ref())
+            .collect();
+        assert_eq!(statuses, ["nil", "0", "1"]);
+    }
+
+    #[test]
+    fn exec_reports_commands_that_cannot_run() {
+        let error = exec("\"proglang-test-no-such-program\" exec")
+            .err()
+            .unwrap();
+        assert_eq!(error.kind, ErrorKind::Io);
+        assert!(error
+            .message
+            .starts_with("Could not run proglang-test-no-such-program: "));
+
+        let error = exec("\"  \" exec").err().unwrap();
+        assert_eq!(error.kind, ErrorKind::Value);
+    }
+
+    #[test]
+    fn exec_is_not_allowed_unless_turned_on() {
+        let error = run_err("\"echo hello\" exec");
+
+        assert_eq!(error.kind, ErrorKind::Capability);
+        assert_eq!(
+            error.message,
+            "exec needs permission to run programs, which isn't allowed here"
+        );
+    }
+
+    #[test]
+    fn getenv_needs_a_string() {
+        let error = run_err("12 getenv");
+
+        assert_eq!(error.kind, ErrorKind::MismatchedTypes);
+        assert_eq!(
+            error.to_string(),
+            "Mismatched types: getenv expects a String, found Int"
+        );
+    }
+
+    #[test]
+    fn gets_pushes_lines_then_nil() {
+        let out = SharedBuffer::default();
+        let mut runner = Runner::new()
+            .with_output(Box::new(out.clone()))
+            .with_input(Box::new(LineReader::new("first\n".as_bytes())));
+
+        runner.run(lex("gets puts gets puts")).unwrap();
+
+        assert_eq!(out.contents(), "first\nnil\n");
+        assert_eq!(runner.token_stack.len(), 0);
+    }
+
+    /// A fresh directory holding `files`, given as paths relative to it and
+    /// their contents.
+    fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("proglang-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    /// Runs `source` as if it were the file `main.plang` in `dir`.
+    fn run_in(dir: &Path, source: &str) -> (Result<(), Error>, String) {
+        let out = SharedBuffer::default();
+        let mut runner = runner()
+            .with_output(Box::new(out.clone()))
+            .with_source_file(dir.join("main.plang"));
+
+        let result = runner.run(lex(source));
+        (result, out.contents())
+    }
+
+    #[test]
+    fn eval_runs_a_string_as_code_on_the_same_stack() {
+        assert_eq!(eval_to_string("\"2 3 +\" eval puts"), "5\n");
+        assert_eq!(eval_to_string("4 \"5 *\" eval puts"), "20\n");
+    }
+
+    #[test]
+    fn eval_keeps_what_its_code_defines() {
+        let runner = run("\"def double n { n 2 * } 7 let x\" eval x double");
+
+        assert_eq!(&*top(&runner).token_value, "14");
+    }
+
+    #[test]
+    fn errors_in_eval_point_at_the_eval() {
+        let error = run_err("\"1 bogus\" eval");
+
+        assert_eq!(error.kind, ErrorKind::NameError);
+        assert_eq!(error.message, "In eval, column 3: No such keyword: bogus");
+        assert_eq!(error.span.column, 11);
+        assert_eq!(run_err("\"1.2.3\" eval").kind, ErrorKind::IllegalChar);
+        assert_eq!(run_err("5 eval").kind, ErrorKind::MismatchedTypes);
+    }
+
+    #[test]
+    fn evals_can_only_nest_so_deep() {
+        let error = run_err("\"s eval\" let s s eval");
+
+        assert_eq!(error.kind, ErrorKind::Recursion);
+        assert_eq!(error.message, "In eval, column 3: Too many nested evals");
+        assert_eq!(error.span.column, 18);
+
+        let limits = ExecutionLimits {
+            max_call_depth: Some(5),
+            ..ExecutionLimits::default()
+        };
+        let error = limited(limits, "\"s eval\" let s s eval");
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!(
+            error.message,
+            "In eval, column 3: Evals nested more than 5 deep"
+        );
+    }
+
+    /// `open` nested `depth` times around `1`, each closed by `close`.
+    fn nested(open: &str, close: &str, depth: usize) -> String {
+        format!("{}1{}", open.repeat(depth), close.repeat(depth))
+    }
+
+    #[test]
+    fn blocks_can_only_nest_so_deep() {
+        for (open, close) in [
+            ("true if ", " else end"),
+            ("try ", " catch end"),
+            ("0 match 0 { ", " } end"),
+            ("time { ", " let t }"),
+        ] {
+            let error = run_err(&nested(open, close, 2000));
+
+            assert_eq!(error.kind, ErrorKind::Recursion, "{}", open);
+            assert_eq!(error.message, "Too many nested blocks", "{}", open);
+            assert!(runner().run(lex(&nested(open, close, 200))).is_ok());
+        }
+
+        let limits = ExecutionLimits {
+            max_call_depth: Some(5),
+            ..ExecutionLimits::default()
+        };
+        let error = limited(limits, &nested("true if ", " else end", 6));
+        assert_eq!(error.kind, ErrorKind::LimitExceeded);
+        assert_eq!(error.message, "Blocks nested more than 5 deep");
+    }
+
+    #[test]
+    fn calls_and_blocks_nest_only_so_deep_together() {
+        let error = run_err("def f { true if f else end } f");
+
+        assert_eq!(error.kind, ErrorKind::Recursion);
+        let error = run_err("\"true if s eval else end\" let s s eval");
+        assert_eq!(error.kind, ErrorKind::Recursion);
+    }
+
+    #[test]
+    fn include_runs_a_file_and_keeps_its_definitions() {
+        let dir = fixture(
+            "include",
+            &[("lib/helpers.plang", "10 let base\ndef double n { n 2 * }\n")],
+        );
+
+        let (result, output) = run_in(&dir, "\"lib/helpers.plang\" include base double puts");
+        fs::remove_dir_all(&dir).unwrap();
+
+        result.unwrap();
+        assert_eq!(output, "20\n");
+    }
+
+    #[test]
+    fn nested_includes_are_found_next_to_the_including_file() {
+        let dir = fixture(
+            "nested-include",
+            &[
+                (
+                    "lib/outer.plang",
+                    "\"inner.plang\" include\n\"outer\" puts\n",
+                ),
+                ("lib/inner.plang", "\"inner\" puts\n"),
+                ("inner.plang", "\"wrong inner\" puts\n"),
+            ],
+        );
+
+        let (result, output) = run_in(&dir, "\"lib/outer.plang\" include");
+        fs::remove_dir_all(&dir).unwrap();
+
+        result.unwrap();
+        assert_eq!(output, "inner\nouter\n");
+    }
+
+    #[test]
+    fn including_a_missing_file_is_an_error_at_the_include() {
+        let dir = fixture("missing-include", &[]);
+
+        let (result, _) = run_in(&dir, "1 puts\n\"nope.plang\" include");
+        let error = result.unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Io);
+        assert!(error.message.starts_with("Could not include "));
+        assert!(error.message.contains("nope.plang"));
+        assert_eq!((error.span.line, error.span.column), (2, 14));
+    }
+
+    #[test]
+    fn errors_in_an_included_file_name_both_places() {
+        let dir = fixture("broken-include", &[("broken.plang", "1 puts\n  bogus\n")]);
+
+        let (result, output) = run_in(&dir, "\"broken.plang\" include");
+        let error = result.unwrap_err();
+        let path = fs::canonicalize(dir.join("broken.plang")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(output, "1\n");
+        assert_eq!(error.kind, ErrorKind::Include);
+        assert_eq!(
+            error.message,
+            format!(
+                "In {}, line 2, column 3: Unknown keyword error: No such keyword: bogus",
+                path.display()
+            )
+        );
+        assert_eq!(error.span.column, 16);
+    }
+
+    #[test]
+    fn include_cycles_are_reported() {
+        let dir = fixture(
+            "cyclic-include",
+            &[
+                ("a.plang", "\"b.plang\" include\n"),
+                ("b.plang", "\"a.plang\" include\n"),
+            ],
+        );
+
+        let (result, _) = run_in(&dir, "\"a.plang\" include");
+        let a = fs::canonicalize(dir.join("a.plang")).unwrap();
+        let b = fs::canonicalize(dir.join("b.plang")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let message = result.unwrap_err().message;
+        assert!(
+            message.ends_with(&format!(
+                "Include cycle: {} -> {} -> {}",
+                a.display(),
+                b.display(),
+                a.display()
+            )),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn a_file_cannot_include_itself() {
+        let dir = fixture("self-include", &[("main.plang", "")]);
+
+        let (result, _) = run_in(&dir, "\"main.plang\" include");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(result.unwrap_err().kind, ErrorKind::Include);
+    }
+
+    #[test]
+    fn slurp_pushes_file_contents() {
+        let path = env::temp_dir().join(format!("proglang-slurp-{}.txt", std::process::id()));
+        fs::write(&path, "line one\nline two").unwrap();
+
+        let output = eval_to_string(&format!("\"{}\" slurp puts", path.display()));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(output, "line one\nline two\n");
+    }
+
+    #[test]
+    fn slurp_of_a_missing_file_is_an_error() {
+        let error = run_err("\"/this/file/does/not/exist\" slurp");
+
+        assert_eq!(error.kind, ErrorKind::Io);
+        assert!(error
+            .message
+            .starts_with("Could not read /this/file/does/not/exist: "));
+    }
+
+    #[test]
+    fn spit_then_slurp_round_trips() {
+        let path = env::temp_dir().join(format!("proglang-spit-{}.txt", std::process::id()));
+        let path = path.display();
+
+        let output = eval_to_string(&format!(
+            "\"first\" \"{0}\" spit \"hello\" \"{0}\" spit \"{0}\" slurp puts",
+            path
+        ));
+        fs::remove_file(path.to_string()).unwrap();
+
+        assert_eq!(output, "hello\n");
+    }
+
+    #[test]
+    fn files_can_be_put_out_of_reach() {
+        let path = env::temp_dir().join(format!("proglang-no-files-{}.txt", std::process::id()));
+        let path = path.display();
+        let programs = [
+            format!("\"{}\" slurp", path),
+            format!("\"x\" \"{}\" spit", path),
+            format!("\"x\" \"{}\" append", path),
+            format!("\"{}\" read_file", path),
+            format!("\"x\" \"{}\" write_file", path),
+            format!("\"x\" \"{}\" append_file", path),
+            format!("\"{}\" include", path),
+        ];
+
+        for program in programs {
+            let error = runner()
+                .with_file_access(false)
+                .run(lex(&program))
+                .unwrap_err();
+            assert_eq!(error.kind, ErrorKind::Capability, "{}", program);
+            assert_eq!(error.kind.name(), "CapabilityError");
+        }
+        assert!(fs::metadata(path.to_string()).is_err());
+    }
+
+    #[test]
+    fn append_adds_to_the_file() {
+        let path = env::temp_dir().join(format!("proglang-append-{}.txt", std::process::id()));
+        let path = path.display();
+
+        let output = eval_to_string(&format!(
+            "\"one \" \"{0}\" spit \"two\" \"{0}\" append \"{0}\" slurp puts",
+            path
+        ));
+        fs::remove_file(path.to_string()).unwrap();
+
+        assert_eq!(output, "one two\n");
+    }
+
+    #[test]
+    fn read_write_and_append_file_work_like_slurp_spit_and_append() {
+        let dir = env::temp_dir().join(format!("proglang-files-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notes.txt");
+        let path = path.display();
+
+        let output = eval_to_string(&format!(
+            "\"old\" \"{0}\" write_file \"one \" \"{0}\" write_file \
+             \"two\" \"{0}\" append_file \"{0}\" read_file puts",
+            path
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(output, "one two\n");
+    }
+
+    #[test]
+    fn read_file_of_a_missing_file_is_an_error() {
+        let error = run_err("\"/this/file/does/not/exist\" read_file");
+
+        assert_eq!(error.kind, ErrorKind::Io);
+        assert!(error
+            .message
+            .starts_with("Could not read /this/file/does/not/exist: "));
+        assert_eq!(
+            run_err("1 read_file").message,
+            "read_file expects a String, found Int"
+        );
+    }
+
+    #[test]
+    fn spit_into_a_missing_directory_is_an_error() {
+        let error = run_err("\"x\" \"/this/directory/does/not/exist/out.txt\" spit");
+
+        assert_eq!(error.kind, ErrorKind::Io);
+    }
+
+    #[test]
+    fn run_consumes_every_token() {
+        let runner = run("12 13 +");
+
+        assert!(runner.program.is_empty());
+    }
+
+    #[test]
+    fn the_stack_is_kept_between_runs() {
+        let mut runner = runner();
+
+        runner.run(lex("1 2")).unwrap();
+        runner.run(lex("+")).unwrap();
+
+        assert_eq!(&*top(&runner).token_value, "3");
+    }
+}
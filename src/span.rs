@@ -0,0 +1,42 @@
+use std::fmt;
+
+/// A range of characters in the source, used to point errors at the code that
+/// caused them.
+///
+/// `start` and `end` are character offsets into the source (`end` is
+/// exclusive); `line` and `column` are 1-based and describe `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn displays_line_and_column() {
+        assert_eq!(Span::new(4, 5, 2, 3).to_string(), "line 2, column 3");
+    }
+}
@@ -0,0 +1,150 @@
+//! Reading the templates `format` fills in, like `"x={} y={:.2}"`.
+
+use std::str::FromStr;
+
+/// The most digits after the point a placeholder can ask for.
+pub(crate) const MAX_PRECISION: usize = 100;
+
+/// One part of a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Piece {
+    /// Text to copy as it is, with `{{` and `}}` already read as braces.
+    Text(String),
+    /// A `{}` to fill with the next value.
+    Value(Spec),
+}
+
+/// How a placeholder shows its value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Spec {
+    /// How many digits after the point a number gets, from `{:.N}`.
+    pub(crate) precision: Option<usize>,
+}
+
+/// Splits `template` into text and placeholders, or says what is wrong
+/// with it.
+pub(crate) fn parse(template: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => text.push('{'),
+            '}' if chars.next_if_eq(&'}').is_some() => text.push('}'),
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => return Err(String::from(
+                            "format has a { with no } to close it; write {{ for a brace on its own",
+                        )),
+                    }
+                }
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Value(spec.parse()?));
+            }
+            '}' => {
+                return Err(String::from(
+                    "format has a } with no { to open it; write }} for a brace on its own",
+                ))
+            }
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+/// Reads what comes between a placeholder's braces: nothing, or `:.N` for a
+/// precision.
+impl FromStr for Spec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Spec, String> {
+        if spec.is_empty() {
+            return Ok(Spec::default());
+        }
+        match spec.strip_prefix(":.").map(str::parse) {
+            Some(Ok(precision)) if precision <= MAX_PRECISION => Ok(Spec {
+                precision: Some(precision),
+            }),
+            Some(Ok(precision)) => Err(format!(
+                "format can show at most {} digits after the point, not {}",
+                MAX_PRECISION, precision
+            )),
+            _ => Err(format!(
+                "format doesn't know the placeholder {{{}}}; use {{}} or {{:.2}}",
+                spec
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Piece {
+        Piece::Text(String::from(text))
+    }
+
+    fn value(precision: Option<usize>) -> Piece {
+        Piece::Value(Spec { precision })
+    }
+
+    #[test]
+    fn splits_text_from_placeholders() {
+        assert_eq!(
+            parse("x={} y={:.2}!"),
+            Ok(vec![
+                text("x="),
+                value(None),
+                text(" y="),
+                value(Some(2)),
+                text("!")
+            ])
+        );
+        assert_eq!(parse("{}{}"), Ok(vec![value(None), value(None)]));
+        assert_eq!(parse(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn doubled_braces_are_text() {
+        assert_eq!(
+            parse("{{}} {{{}}}"),
+            Ok(vec![text("{} {"), value(None), text("}")])
+        );
+    }
+
+    #[test]
+    fn braces_on_their_own_are_errors() {
+        assert!(parse("x={").unwrap_err().contains("no } to close it"));
+        assert!(parse("x}").unwrap_err().contains("no { to open it"));
+        assert_eq!(
+            parse("{x}"),
+            Err(String::from(
+                "format doesn't know the placeholder {x}; use {} or {:.2}"
+            ))
+        );
+        assert!(parse("{:.}").is_err());
+    }
+
+    #[test]
+    fn precisions_are_capped() {
+        assert_eq!(parse("{:.100}"), Ok(vec![value(Some(MAX_PRECISION))]));
+        assert_eq!(
+            parse("{:.65536}"),
+            Err(String::from(
+                "format can show at most 100 digits after the point, not 65536"
+            ))
+        );
+        assert!(parse("{:.99999999999999999999999}").is_err());
+    }
+}
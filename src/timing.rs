@@ -0,0 +1,147 @@
+use crate::error::ProgLangError;
+use crate::lexer::Lexer;
+use crate::runner::Runner;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How long each stage of running a program took, for `--time` and the
+/// REPL's `:time on`.
+///
+/// There is no parsing stage to time: the runner reads tokens straight from
+/// the lexer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    pub lex: Duration,
+    /// Running the tokens, apart from any time `gets` spent waiting for
+    /// input.
+    pub run: Duration,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.lex + self.run
+    }
+}
+
+/// Reads like `time: lex 12.3µs, run 1.50ms, total 1.51ms`.
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "time: lex {}, run {}, total {}",
+            human(self.lex),
+            human(self.run),
+            human(self.total())
+        )
+    }
+}
+
+/// Lexes the source of `lexer` and runs it on `runner` the way the REPL and
+/// [`run`](crate::run) do, timing each stage.
+pub fn timed_run(mut lexer: Lexer, runner: &mut Runner) -> (Result<(), ProgLangError>, Timings) {
+    let mut timings = Timings::default();
+    if runner.big_ints() {
+        lexer = lexer.with_big_ints();
+    }
+
+    let started = Instant::now();
+    let tokens = lexer.tokenize();
+    timings.lex = started.elapsed();
+    let tokens = match tokens {
+        Ok(tokens) => tokens,
+        Err(error) => return (Err(ProgLangError::LexError(error)), timings),
+    };
+
+    let waited = runner.time_waiting();
+    let started = Instant::now();
+    let result = runner.run(tokens).map_err(ProgLangError::RuntimeError);
+    timings.run = started
+        .elapsed()
+        .saturating_sub(runner.time_waiting() - waited);
+    (result, timings)
+}
+
+/// `duration` in whichever unit keeps it short, to three significant figures
+/// or so.
+fn human(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos < 1_000 {
+        format!("{}ns", nanos)
+    } else if nanos < 1_000_000 {
+        format!("{:.1}µs", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.2}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::LineReader;
+    use std::io::{self, BufRead, Read};
+    use std::thread;
+
+    #[test]
+    fn durations_use_the_unit_that_fits() {
+        assert_eq!(human(Duration::from_nanos(800)), "800ns");
+        assert_eq!(human(Duration::from_nanos(12_340)), "12.3µs");
+        assert_eq!(human(Duration::from_micros(1_500)), "1.50ms");
+        assert_eq!(human(Duration::from_millis(2_250)), "2.25s");
+    }
+
+    #[test]
+    fn reports_every_stage_and_the_total() {
+        let timings = Timings {
+            lex: Duration::from_micros(5),
+            run: Duration::from_millis(2),
+        };
+
+        assert_eq!(
+            timings.to_string(),
+            "time: lex 5.0µs, run 2.00ms, total 2.00ms"
+        );
+    }
+
+    #[test]
+    fn lex_errors_still_have_their_lexing_timed() {
+        let mut runner = Runner::new();
+        let (result, timings) = timed_run(Lexer::new(String::from("1.2.3")), &mut runner);
+
+        assert!(matches!(result, Err(ProgLangError::LexError(_))));
+        assert_eq!(timings.run, Duration::ZERO);
+    }
+
+    /// Input that takes a while to give each line.
+    struct Slow<R>(R);
+
+    impl<R: Read> Read for Slow<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl<R: BufRead> BufRead for Slow<R> {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            thread::sleep(Duration::from_millis(200));
+            self.0.fill_buf()
+        }
+
+        fn consume(&mut self, amount: usize) {
+            self.0.consume(amount)
+        }
+    }
+
+    #[test]
+    fn time_spent_waiting_for_gets_is_left_out() {
+        let mut runner = Runner::new()
+            .with_output(Box::new(io::sink()))
+            .with_input(Box::new(LineReader::new(Slow("line\n".as_bytes()))));
+        let (result, timings) = timed_run(Lexer::new(String::from("gets puts")), &mut runner);
+
+        assert!(result.is_ok());
+        assert!(runner.time_waiting() >= Duration::from_millis(200));
+        assert!(timings.run < Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::interner::Symbol;
+use crate::span::Span;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use strum_macros::EnumIter;
+
+/// The kind of a lexed token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TokenType {
+    // Types
+    Int,
+    Float,
+    String,
+    /// `true` or `false`, e.g. what comparisons push.
+    Bool,
+    /// The absence of a value, e.g. what `gets` pushes at the end of input.
+    Nil,
+    /// A sequence of values, e.g. what `collect` pushes.
+    List,
+    /// A word to call later, e.g. what `fn` pushes.
+    Function,
+    Keyword,
+    // Arithmetic Operators
+    Plus,
+    Multiply,
+    // Comparison Operators
+    Equal,
+    EqualEqual,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    // Bitwise Operators
+    ShiftLeft,
+    ShiftRight,
+    // Blocks
+    LeftBrace,
+    RightBrace,
+}
+
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A single token produced by the [`Lexer`](crate::lexer::Lexer), also used as
+/// a value by the [`Runner`](crate::runner::Runner).
+///
+/// The value is reference counted so copies of a token share one string. A
+/// [`List`](TokenType::List) keeps its elements in `items` and its printed
+/// form in `token_value`; every other token has no items. A
+/// [`Function`](TokenType::Function) keeps the word it calls in `symbol`, the
+/// variables it captured in `captured` and its printed form, like
+/// `<function double/1>`, in `token_value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub token_type: TokenType,
+    #[cfg_attr(feature = "serde", serde(rename = "value"))]
+    pub token_value: Rc<str>,
+    pub items: Rc<[Token]>,
+    #[cfg_attr(feature = "serde", serde(rename = "position"))]
+    pub span: Span,
+    /// The interned name of a keyword token, filled in by the runner that
+    /// first receives it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub symbol: Option<Symbol>,
+    /// The variables a function value took from the call that made it,
+    /// sorted by name. Copies of the value share them, and a `let` in the
+    /// word it calls changes them for every later call.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub captured: Rc<RefCell<Vec<(Symbol, Token)>>>,
+}
+
+impl Token {
+    pub fn new(t_type: TokenType, t_value: impl Into<Rc<str>>) -> Token {
+        Token {
+            token_type: t_type,
+            token_value: t_value.into(),
+            items: Rc::from([]),
+            span: Span::default(),
+            symbol: None,
+            captured: Rc::default(),
+        }
+    }
+
+    /// Creates a list of `items`, printed as `[1, 2, "three"]`.
+    pub fn list(items: Vec<Token>) -> Token {
+        let elements: Vec<String> = items
+            .iter()
+            .map(|item| match item.token_type {
+                TokenType::String => format!("\"{}\"", item.token_value),
+                _ => item.token_value.to_string(),
+            })
+            .collect();
+
+        Token {
+            items: Rc::from(items),
+            ..Token::new(TokenType::List, format!("[{}]", elements.join(", ")))
+        }
+    }
+
+    /// Sets where in the source the token came from.
+    pub fn at(mut self, span: Span) -> Token {
+        self.span = span;
+        self
+    }
+
+    /// Prints the token as `Type:value`, for debugging.
+    pub fn print(&self) {
+        println!("{:?}:{}", self.token_type, self.token_value);
+    }
+}
+
+/// A value the way `puts` prints it: strings without their quotes, and
+/// numbers, bools, nil and lists as a program would write them.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.token_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_stores_type_and_value() {
+        let token = Token::new(TokenType::Int, "12");
+
+        assert_eq!(token.token_type, TokenType::Int);
+        assert_eq!(&*token.token_value, "12");
+    }
+
+    #[test]
+    fn copies_of_a_value_share_one_string() {
+        let token = Token::new(TokenType::String, String::from("shared"));
+        let copy = Rc::clone(&token.token_value);
+
+        assert!(Rc::ptr_eq(&token.token_value, &copy));
+    }
+
+    #[test]
+    fn lists_print_their_elements_in_order() {
+        let list = Token::list(vec![
+            Token::new(TokenType::Int, "1"),
+            Token::new(TokenType::String, "two"),
+            Token::list(Vec::new()),
+        ]);
+
+        assert_eq!(list.token_type, TokenType::List);
+        assert_eq!(list.items.len(), 3);
+        assert_eq!(&*list.token_value, "[1, \"two\", []]");
+    }
+
+    #[test]
+    fn values_display_the_way_puts_prints_them() {
+        assert_eq!(Token::new(TokenType::Int, "12").to_string(), "12");
+        assert_eq!(Token::new(TokenType::Float, "2.5").to_string(), "2.5");
+        assert_eq!(Token::new(TokenType::String, "hi").to_string(), "hi");
+        assert_eq!(
+            Token::list(vec![Token::new(TokenType::String, "hi")]).to_string(),
+            "[\"hi\"]"
+        );
+    }
+
+    #[test]
+    fn token_type_displays_as_its_name() {
+        assert_eq!(TokenType::Plus.to_string(), "Plus");
+    }
+}
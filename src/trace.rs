@@ -0,0 +1,136 @@
+//! Describing each step a [`Runner`](crate::runner::Runner) takes, for
+//! `--trace`.
+
+use crate::help;
+use crate::token::{Token, TokenType};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// Which steps a runner [traces](crate::runner::Runner::with_trace).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceLevel {
+    /// Only calls to words and registered functions.
+    Calls,
+    /// Every token run, including those inside words.
+    All,
+}
+
+impl FromStr for TraceLevel {
+    type Err = String;
+
+    fn from_str(level: &str) -> Result<TraceLevel, String> {
+        match level {
+            "calls" => Ok(TraceLevel::Calls),
+            "all" => Ok(TraceLevel::All),
+            _ => Err(format!(
+                "Expected calls or all for the trace, not {:?}",
+                level
+            )),
+        }
+    }
+}
+
+/// How many values from the top of the stack a step shows.
+const SHOWN_VALUES: usize = 8;
+
+/// One line on step `step`, which is about to run `token` with `stack` as
+/// the stack and `variables` as the variables it can see:
+///
+/// ```text
+/// trace 4 | puts | stack: [5] | vars: {x: 5}
+/// ```
+///
+/// Only the top few values of a deep stack are shown, after a `...`.
+pub(crate) fn line(
+    step: usize,
+    token: &Token,
+    stack: &[Token],
+    variables: &BTreeMap<Rc<str>, &Token>,
+) -> String {
+    let hidden = stack.len().saturating_sub(SHOWN_VALUES);
+    let mut values: Vec<String> = stack[hidden..].iter().map(show).collect();
+    if hidden > 0 {
+        values.insert(0, String::from("..."));
+    }
+    let variables: Vec<String> = variables
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, show(value)))
+        .collect();
+
+    format!(
+        "trace {} | {} | stack: [{}] | vars: {{{}}}",
+        step,
+        operation(token),
+        values.join(", "),
+        variables.join(", ")
+    )
+}
+
+/// What running `token` does, as a program would write it: an operator,
+/// a name or a value to push.
+pub(crate) fn operation(token: &Token) -> String {
+    match help::operator(token.token_type) {
+        Some(operator) => String::from(operator),
+        None if token.token_type == TokenType::Keyword => token.token_value.to_string(),
+        None => show(token),
+    }
+}
+
+/// A value as a program would write it.
+pub(crate) fn show(value: &Token) -> String {
+    match value.token_type {
+        TokenType::String => format!("{:?}", value.token_value),
+        TokenType::LeftBrace => String::from("{"),
+        TokenType::RightBrace => String::from("}"),
+        _ => value.token_value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_the_step_operation_stack_and_variables() {
+        let five = Token::new(TokenType::Int, "5");
+        let variables = BTreeMap::from([(Rc::from("x"), &five)]);
+        let stack = [Token::new(TokenType::String, "hi"), five.clone()];
+
+        assert_eq!(
+            line(
+                4,
+                &Token::new(TokenType::Keyword, "puts"),
+                &stack,
+                &variables
+            ),
+            "trace 4 | puts | stack: [\"hi\", 5] | vars: {x: 5}"
+        );
+        assert_eq!(
+            line(
+                1,
+                &Token::new(TokenType::LessEqual, ""),
+                &[],
+                &BTreeMap::new()
+            ),
+            "trace 1 | <= | stack: [] | vars: {}"
+        );
+    }
+
+    #[test]
+    fn deep_stacks_show_only_their_top() {
+        let stack: Vec<Token> = (0..10)
+            .map(|n| Token::new(TokenType::Int, n.to_string()))
+            .collect();
+
+        assert!(line(1, &stack[0], &stack, &BTreeMap::new())
+            .ends_with("stack: [..., 2, 3, 4, 5, 6, 7, 8, 9] | vars: {}"));
+    }
+
+    #[test]
+    fn levels_parse_from_their_names() {
+        assert_eq!("calls".parse(), Ok(TraceLevel::Calls));
+        assert_eq!("all".parse(), Ok(TraceLevel::All));
+        assert!("some".parse::<TraceLevel>().is_err());
+    }
+}
@@ -0,0 +1,36 @@
+use crate::span::Span;
+use std::fmt;
+
+/// The kinds of suspicious-but-legal code that raise a [`Warning`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WarningKind {
+    /// A value was still on the stack when the program ended.
+    LeftoverValue,
+}
+
+/// A note about code that runs but probably doesn't do what was meant.
+///
+/// Warnings never stop a program; whoever runs it decides whether to show
+/// them and whether they should count as a failure.
+#[derive(Debug)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, message: &str, span: Span) -> Warning {
+        Warning {
+            kind,
+            message: String::from(message),
+            span,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "warning: {}", self.message)
+    }
+}
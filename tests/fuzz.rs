@@ -0,0 +1,154 @@
+//! Property tests that no input, however malformed, crashes the interpreter:
+//! lexing and running anything ends in either success or a structured error.
+
+use proglang::{ErrorKind, LineReader, ProgLangError, Runner};
+use proptest::prelude::*;
+use std::io;
+
+/// More steps than any generated program needs, so running out means a hang.
+const STEP_LIMIT: usize = 1_000;
+
+fn run(src: &str) -> Result<(), ProgLangError> {
+    let runner = Runner::new()
+        .with_output(Box::new(io::sink()))
+        .with_input(Box::new(LineReader::new(io::empty())))
+        .with_step_limit(STEP_LIMIT);
+
+    proglang::run_on(src, runner, Box::new(io::sink()))
+}
+
+fn kind_of(src: &str) -> ErrorKind {
+    run(src).unwrap_err().kind()
+}
+
+/// Pieces of source that mean something to the lexer or runner. Keywords that
+/// write files are left out.
+fn fragment() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(String::from("0")),
+        Just(String::from("1")),
+        Just(String::from("-1")),
+        Just(String::from("2.5")),
+        Just(String::from("-.5")),
+        Just(String::from("-")),
+        Just(String::from("1.")),
+        Just(String::from("1.2.3")),
+        Just(i64::MAX.to_string()),
+        Just(i64::MIN.to_string()),
+        Just(String::from("99999999999999999999999")),
+        Just(String::from("\"text\"")),
+        Just(String::from("\"")),
+        Just(String::from("+")),
+        Just(String::from("*")),
+        Just(String::from("==")),
+        Just(String::from("!=")),
+        Just(String::from("<")),
+        Just(String::from(">=")),
+        Just(String::from("<<")),
+        Just(String::from(">>")),
+        Just(String::from("!")),
+        Just(String::from("puts")),
+        Just(String::from("gets")),
+        Just(String::from("collect")),
+        Just(String::from("len")),
+        Just(String::from("true")),
+        Just(String::from("now")),
+        Just(String::from("bogus")),
+        Just(String::from("def")),
+        Just(String::from("let")),
+        Just(String::from("eval")),
+        Just(String::from("\"s eval\" let s s")),
+        Just(String::from("if")),
+        Just(String::from("else")),
+        Just(String::from("match")),
+        Just(String::from("_")),
+        Just(String::from("try")),
+        Just(String::from("catch")),
+        Just(String::from("end")),
+        Just(String::from("fn")),
+        Just(String::from("call")),
+        Just(String::from("map")),
+        Just(String::from("filter")),
+        Just(String::from("count")),
+        Just(String::from("time")),
+        Just(String::from("{")),
+        Just(String::from("}")),
+        Just(String::from("x")),
+        Just(String::from("\n")),
+        "[0-9]{1,25}",
+        "\\PC{1,3}",
+    ]
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_text_never_panics(src in "\\PC*") {
+        let _ = run(&src);
+    }
+
+    #[test]
+    fn arbitrary_bytes_never_panic(bytes in proptest::collection::vec(any::<u8>(), 0..64)) {
+        let _ = run(&String::from_utf8_lossy(&bytes));
+    }
+
+    #[test]
+    fn token_soup_never_panics_or_hangs(fragments in proptest::collection::vec(fragment(), 0..32)) {
+        let src = fragments.join(" ");
+
+        if let Err(error) = run(&src) {
+            prop_assert_ne!(error.kind(), ErrorKind::LimitExceeded);
+        }
+    }
+}
+
+#[test]
+fn empty_input_is_fine() {
+    assert!(run("").is_ok());
+}
+
+#[test]
+fn lone_plus_underflows() {
+    assert_eq!(kind_of("+"), ErrorKind::StackUnderflow);
+}
+
+#[test]
+fn puts_with_nothing_underflows() {
+    assert_eq!(kind_of("puts"), ErrorKind::StackUnderflow);
+}
+
+#[test]
+fn keyword_at_the_end_of_input_runs() {
+    assert!(run("1 puts").is_ok());
+}
+
+#[test]
+fn integer_literals_too_large_for_an_int_are_errors() {
+    assert_eq!(kind_of("99999999999999999999999"), ErrorKind::Overflow);
+}
+
+#[test]
+fn integer_overflow_is_an_error() {
+    let (max, min) = (i64::MAX, i64::MIN);
+
+    assert_eq!(kind_of(&format!("{} 1 +", max)), ErrorKind::Overflow);
+    assert_eq!(kind_of(&format!("{} 2 *", max)), ErrorKind::Overflow);
+    assert_eq!(kind_of(&format!("{} -1 +", min)), ErrorKind::Overflow);
+    assert_eq!(kind_of(&format!("{} -1 *", min)), ErrorKind::Overflow);
+}
+
+#[test]
+fn non_ascii_digits_are_not_numbers() {
+    assert_eq!(kind_of("² puts"), ErrorKind::NameError);
+}
+
+#[test]
+fn a_string_that_evals_itself_is_an_error() {
+    assert!(run("\"s eval\" let s s eval").is_err());
+}
+
+#[test]
+fn step_limit_stops_long_programs() {
+    let src = "1 ".repeat(STEP_LIMIT + 1);
+
+    assert_eq!(kind_of(&src), ErrorKind::LimitExceeded);
+}
@@ -0,0 +1,584 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run_repl(input: &str) -> Output {
+    run_repl_with(&[], input)
+}
+
+/// Runs a REPL session even though stdin is piped, which otherwise runs as
+/// one program.
+fn run_repl_with(args: &[&str], input: &str) -> Output {
+    run_with(&[&["--interactive"], args].concat(), input)
+}
+
+fn run_with(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ForthRust"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start the interpreter");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn stdout_of(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr_of(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn addition_without_puts_prints_nothing() {
+    // One prompt before the line and one more before end of input.
+    assert_eq!(stdout_of(&run_repl("5 5 +\n")), "> > \n");
+}
+
+#[test]
+fn nested_operators_feed_puts() {
+    assert_eq!(stdout_of(&run_repl("2 3 * 4 + puts\n")), "> 10\n> \n");
+}
+
+#[test]
+fn successful_sessions_exit_cleanly() {
+    let output = run_repl("1 2 + puts\n");
+
+    assert_eq!(stdout_of(&output), "> 3\n> \n");
+    assert_eq!(stderr_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn exit_after_an_error_exits_cleanly() {
+    let output = run_repl("bogus\n1.2.3\nexit\n");
+
+    assert!(stderr_of(&output).contains("No such keyword: bogus"));
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(run_repl("bogus\nquit\n").status.code(), Some(0));
+    assert_eq!(run_repl("bogus\n").status.code(), Some(1));
+}
+
+#[test]
+fn lex_errors_exit_with_code_2() {
+    let output = run_repl("1.2.3\n\"fine\" puts\n");
+
+    assert_eq!(stdout_of(&output), "> > fine\n> \n");
+    assert!(stderr_of(&output).starts_with("IllegalCharError: Found an extra dot\n"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn errors_go_to_stderr_and_fail_the_session() {
+    let output = run_repl("bogus\n");
+
+    assert_eq!(stdout_of(&output), "> > \n");
+    assert_eq!(
+        stderr_of(&output),
+        concat!(
+            "Unknown keyword error: No such keyword: bogus\n",
+            " --> line 1, column 1\n",
+            "  |\n",
+            "1 | bogus\n",
+            "  | ^^^^^\n",
+        )
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn leftover_values_are_warned_about_on_stderr() {
+    // Meant `1 2 + 3 + puts`; the 1 is never used.
+    let output = run_repl("1 2 3 + puts\n");
+
+    assert_eq!(stdout_of(&output), "> 5\n> \n");
+    assert_eq!(
+        stderr_of(&output),
+        concat!(
+            "warning: Value left on the stack when the program ended\n",
+            " --> line 1, column 1\n",
+            "  |\n",
+            "1 | 1 2 3 + puts\n",
+            "  | ^\n",
+        )
+    );
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn no_warnings_hides_them() {
+    let output = run_repl_with(&["--no-warnings"], "1 2\n");
+
+    assert_eq!(stderr_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn warnings_as_errors_fails_the_run() {
+    let output = run_repl_with(&["--warnings-as-errors"], "1 2\n");
+
+    assert!(stderr_of(&output).starts_with("warning: "));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn warnings_as_errors_passes_clean_runs() {
+    let output = run_repl_with(&["--warnings-as-errors"], "1 2 + puts\n");
+
+    assert_eq!(stderr_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn prelude_words_are_ready_from_the_first_line() {
+    let output = run_repl("3 7 max puts\n");
+
+    assert_eq!(stdout_of(&output), "> 7\n> \n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn no_prelude_leaves_prelude_words_undefined() {
+    let output = run_repl_with(&["--no-prelude"], "3 7 max puts\n");
+
+    assert!(stderr_of(&output).starts_with("Unknown keyword error: No such keyword: max\n"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn big_ints_are_exact() {
+    let output = run_repl_with(&["--big-ints"], "999999999999999999999 1 + puts\n");
+
+    assert_eq!(stdout_of(&output), "> 1000000000000000000000\n> \n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn max_steps_stops_a_runaway_line() {
+    let output = run_repl_with(
+        &["--max-steps", "50"],
+        "def forever { forever } forever\n1 puts\n",
+    );
+
+    assert!(stderr_of(&output).starts_with("LimitExceededError: Gave up after 50 steps"));
+    assert_eq!(stdout_of(&output), "> > 1\n> \n");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn limits_need_a_number() {
+    let output = run_repl_with(&["--max-stack", "lots"], "");
+
+    assert_eq!(
+        stderr_of(&output),
+        "--max-stack needs a whole number after it\n"
+    );
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn float_precision_sets_how_floats_print() {
+    let output = run_with(&["--float-precision", "3", "-e", "pi puts e puts"], "");
+
+    assert_eq!(stdout_of(&output), "3.142\n2.718\n");
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run_with(&["--float-precision", "101"], "");
+    assert_eq!(stderr_of(&output), "--float-precision can be at most 100\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn tokens_json_describes_stdin() {
+    let output = run_with(&["--tokens-json"], "1 puts\n");
+    let document: serde_json::Value = serde_json::from_str(&stdout_of(&output)).unwrap();
+
+    assert_eq!(document["version"], 2);
+    assert_eq!(document["tokens"][1]["value"], "puts");
+    assert_eq!(document["tokens"][1]["position"]["column"], 3);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn tokens_json_gives_each_token_a_type_value_and_position() {
+    let output = run_with(&["--tokens-json"], "5 5 +\n");
+    let document: serde_json::Value = serde_json::from_str(&stdout_of(&output)).unwrap();
+
+    let tokens = document["tokens"].as_array().unwrap();
+    let types: Vec<&str> = tokens
+        .iter()
+        .map(|token| token["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, ["Int", "Int", "Plus"]);
+    for token in tokens {
+        assert!(token["value"].is_string());
+        for field in ["start", "end", "line", "column"] {
+            assert!(token["position"][field].is_u64(), "{}", field);
+        }
+    }
+    assert_eq!(tokens[2]["position"]["start"], 4);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn ast_json_reports_parse_errors() {
+    let output = run_with(&["--ast-json"], "def f { 1\n");
+
+    assert_eq!(stdout_of(&output), "");
+    assert!(stderr_of(&output).starts_with("SyntaxError: This { is never closed\n"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+fn script(name: &str) -> String {
+    format!("{}/tests/scripts/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn version_names_the_interpreter_and_its_version() {
+    let output = run_with(&["--version"], "");
+    let stdout = stdout_of(&output);
+
+    let expected = format!("ProgLang {}", env!("CARGO_PKG_VERSION"));
+    assert!(stdout.starts_with(&expected), "{}", stdout);
+    // Then maybe the commit, like " (1a2b3c4)".
+    let rest = stdout[expected.len()..].trim_end_matches('\n');
+    assert!(
+        rest.is_empty() || (rest.starts_with(" (") && rest.ends_with(')')),
+        "{}",
+        stdout
+    );
+    assert_eq!(stdout.lines().count(), 1);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn piped_sessions_have_no_banner() {
+    for args in [&[][..], &["--quiet"][..]] {
+        let output = run_repl_with(args, "1 puts\n");
+
+        assert_eq!(stdout_of(&output), "> 1\n> \n");
+        assert_eq!(stderr_of(&output), "");
+    }
+}
+
+#[test]
+fn a_saved_session_runs_as_a_file() {
+    let path = env::temp_dir().join(format!("proglang-saved-{}.plang", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let session = run_repl(&format!(
+        "def double n {{ n 2 * }}\n3 double let x\nbogus\n:words\nx double puts\n:save {}\n",
+        path.display()
+    ));
+    let saved = run_with(&[path.to_str().unwrap()], "");
+    fs::remove_file(&path).unwrap();
+
+    assert!(stdout_of(&session).contains("12\n"));
+    assert_eq!(stdout_of(&saved), "12\n");
+    assert_eq!(stderr_of(&saved), "");
+    assert_eq!(saved.status.code(), Some(0));
+}
+
+#[test]
+fn arguments_after_the_file_are_the_programs() {
+    let output = run_with(
+        &[
+            "--no-warnings",
+            &script("args.plang"),
+            "foo",
+            "--time",
+            "bar",
+        ],
+        "",
+    );
+
+    assert_eq!(stdout_of(&output), "3\nfoo\n--time\nbar\n");
+    assert_eq!(stderr_of(&output), "");
+}
+
+#[test]
+fn arguments_after_a_double_dash_are_the_programs() {
+    let output = run_with(&["--", &script("args.plang"), "-x"], "");
+
+    assert_eq!(stdout_of(&output), "1\n-x\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn programs_without_a_file_have_no_arguments() {
+    assert_eq!(stdout_of(&run_with(&["-e", "args len puts"], "")), "0\n");
+    assert_eq!(stdout_of(&run_repl("args len puts\n")), "> 0\n> \n");
+}
+
+#[test]
+fn a_file_argument_runs_the_file_as_one_program() {
+    let output = run_with(&[&script("greeting.plang")], "");
+
+    assert_eq!(stdout_of(&output), "Hello,\nworld\n7\n");
+    assert_eq!(stderr_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn errors_in_a_file_stop_it_and_fail_the_run() {
+    let output = run_with(&[&script("failing.plang")], "");
+
+    assert_eq!(stdout_of(&output), "before\n");
+    assert!(stderr_of(&output).starts_with(concat!(
+        "Mismatched types: Cannot add on 2 values of different types\n",
+        " --> line 2, column 7\n",
+    )));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn files_that_cannot_be_read_fail_without_panicking() {
+    let output = run_with(&[&script("missing.plang")], "");
+
+    assert!(stderr_of(&output).starts_with("Could not read "));
+    assert!(!stderr_of(&output).contains("panicked"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn eval_runs_a_one_liner_without_the_repl() {
+    let output = run_with(&["-e", "1 2 + puts"], "");
+
+    assert_eq!(stdout_of(&output), "3\n");
+    assert_eq!(stderr_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn eval_flags_run_as_one_program_a_line_each() {
+    let output = run_with(&["-e", "5 let x", "--eval", "x x * puts"], "");
+
+    assert_eq!(stdout_of(&output), "25\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn eval_reports_lex_errors_like_a_file() {
+    let output = run_with(&["-e", "1 puts", "-e", "1.2.3"], "");
+
+    assert_eq!(stdout_of(&output), "");
+    assert!(stderr_of(&output).starts_with(concat!(
+        "IllegalCharError: Found an extra dot\n",
+        " --> line 2, column 4\n",
+    )));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn eval_needs_a_program() {
+    let output = run_with(&["-e"], "");
+
+    assert_eq!(stderr_of(&output), "-e needs a program after it\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn eval_can_be_dumped_as_json() {
+    let output = run_with(&["-e", "1 puts", "--tokens-json"], "2 puts\n");
+    let document: serde_json::Value = serde_json::from_str(&stdout_of(&output)).unwrap();
+
+    assert_eq!(document["tokens"][0]["value"], "1");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn piped_programs_run_as_a_whole_without_prompts() {
+    let output = run_with(&[], "def greet {\n    \"hi\" puts\n}\ngreet\n");
+
+    assert_eq!(stdout_of(&output), "hi\n");
+    assert_eq!(stderr_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn piped_programs_stop_at_their_first_error() {
+    let output = run_with(&[], "\"before\" puts\nbogus\n\"after\" puts\n");
+
+    assert_eq!(stdout_of(&output), "before\n");
+    assert!(stderr_of(&output).starts_with(concat!(
+        "Unknown keyword error: No such keyword: bogus\n",
+        " --> line 2, column 1\n",
+    )));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn empty_pipes_finish_straight_away() {
+    let output = run_with(&[], "");
+
+    assert_eq!(stdout_of(&output), "");
+    assert_eq!(stderr_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn exit_leaves_the_repl_cleanly() {
+    let output = run_repl("1 puts\nexit\n2 puts\n");
+
+    assert_eq!(stdout_of(&output), "> 1\n> ");
+    assert_eq!(stderr_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn check_passes_clean_files_without_running_them() {
+    let output = run_with(&["--check", &script("greeting.plang")], "");
+
+    assert_eq!(stdout_of(&output), "");
+    assert_eq!(stderr_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn check_reports_every_problem_it_finds() {
+    let output = run_with(&["--check", &script("two_errors.plang")], "");
+    let stderr = stderr_of(&output);
+
+    assert_eq!(stdout_of(&output), "");
+    assert!(stderr.starts_with("IllegalCharError: Found an extra dot\n --> line 2, column 4\n"));
+    assert!(stderr.contains("SyntaxError: Found a } with no {\n --> line 4, column 1\n"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn check_runs_nothing_that_reads_or_prints() {
+    let output = run_with(&["--check", "-e", "gets puts \"out\" puts"], "");
+
+    assert_eq!(stdout_of(&output), "");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn time_reports_after_the_programs_output() {
+    let output = run_with(&["--time", "-e", "\"done\" puts"], "");
+    let stderr = stderr_of(&output);
+
+    assert_eq!(stdout_of(&output), "done\n");
+    assert!(stderr.starts_with("time: lex "), "{}", stderr);
+    assert!(stderr.contains(", run ") && stderr.trim_end().contains(", total "));
+    assert_eq!(stderr.lines().count(), 1);
+}
+
+#[test]
+fn stats_are_only_counted_with_the_flag() {
+    let counted = run_with(&["--stats", "--no-prelude", "-e", "1 2 + puts"], "");
+    assert_eq!(stdout_of(&counted), "3\n");
+    assert_eq!(stderr_of(&counted), "Int: 2\nPlus: 1\nputs: 1\n");
+
+    let uncounted = run_with(&["--no-prelude", "-e", "1 2 + puts"], "");
+    assert_eq!(stderr_of(&uncounted), "");
+
+    let output = run_repl_with(&["--quiet"], ":stats\n");
+    assert!(stdout_of(&output).contains("Operations aren't being counted"));
+    let output = run_repl_with(&["--quiet", "--stats", "--no-prelude"], "1 puts\n:stats\n");
+    assert!(stdout_of(&output).contains("Int: 1\nputs: 1\n"));
+}
+
+#[test]
+fn trace_goes_to_stderr_in_order() {
+    let output = run_with(&["--trace=all", "--no-prelude", "-e", "1 2 + puts"], "");
+    let stderr = stderr_of(&output);
+
+    assert_eq!(stdout_of(&output), "3\n");
+    assert_eq!(
+        stderr.lines().collect::<Vec<_>>(),
+        [
+            "trace 1 | 1 | stack: [] | vars: {}",
+            "trace 2 | 2 | stack: [1] | vars: {}",
+            "trace 3 | + | stack: [1, 2] | vars: {}",
+            "trace 4 | puts | stack: [3] | vars: {}",
+        ]
+    );
+}
+
+#[test]
+fn debug_reads_commands_from_stdin() {
+    let output = run_with(
+        &["--debug", &script("greeting.plang")],
+        "b 3\nc\nstack\nq\n",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout_of(&output).contains(concat!(
+        "(debug) Breakpoint on line 3\n",
+        "(debug) Hello,\n",
+        "line 3, column 5: name\n",
+        "(debug) []\n",
+    )));
+    assert!(stderr_of(&output).starts_with("StoppedError: Stopped from the debugger\n"));
+}
+
+#[test]
+fn tracing_calls_skips_everything_else() {
+    let output = run_with(&["--trace=calls", "-e", "3 square 1 max puts"], "");
+
+    assert_eq!(stdout_of(&output), "9\n");
+    assert!(stderr_of(&output).starts_with("trace 2 | square | stack: [3] | vars: {}\n"));
+    assert!(stderr_of(&output).contains("| max | stack: [9, 1] |"));
+}
+
+#[test]
+fn errors_are_colored_only_when_asked_off_a_terminal() {
+    let plain = run_repl("bogus\n");
+    let colored = run_repl_with(&["--color=always"], "bogus\n");
+
+    assert!(!stderr_of(&plain).contains('\x1b'));
+    assert!(stderr_of(&colored).starts_with("\x1b[1;31mUnknown keyword error\x1b[0m: "));
+    assert!(
+        !stderr_of(&run_repl_with(&["--color=always", "--no-color"], "bogus\n")).contains('\x1b')
+    );
+}
+
+#[test]
+fn unknown_color_choices_are_usage_errors() {
+    let output = run_repl_with(&["--color=sometimes"], "");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr_of(&output).contains("always, never or auto"));
+}
+
+#[test]
+fn no_history_is_accepted_without_a_terminal() {
+    let output = run_repl_with(&["--no-history"], "1 puts\n");
+
+    assert_eq!(stdout_of(&output), "> 1\n> \n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[cfg(unix)]
+#[test]
+fn the_command_line_lets_programs_run_others() {
+    let output = run_repl("\"echo hi\" exec puts\n");
+
+    assert_eq!(stdout_of(&output), "> hi\n\n> \n");
+}
+
+#[test]
+fn uncaught_raises_are_reported_like_other_errors() {
+    let output = run_with(&["-e", "\"ConfigError\" \"config missing\" raise"], "");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr_of(&output).starts_with(concat!(
+        "ConfigError: config missing\n",
+        " --> line 1, column 32\n"
+    )));
+}
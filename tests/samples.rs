@@ -0,0 +1,133 @@
+//! Runs every sample program in `tests/samples` and compares what it printed
+//! with the files next to it:
+//!
+//! - `NAME.plang` is the program.
+//! - `NAME.out` is everything it should print.
+//! - `NAME.err`, if present, is the error report it should fail with.
+//!   Without one the program must succeed.
+//! - `NAME.in`, if present, is what `gets` reads.
+//! - `NAME.code`, if present, is the exit code the error should give the
+//!   process.
+//! - `NAME.flags`, if present, lists command-line flags to run it with, one
+//!   per line. Only `--big-ints` is understood.
+//!
+//! A sample includes files relative to itself, and `tests/samples/fixtures`
+//! holds the ones it can include. Where a report names a file, `NAME.err`
+//! writes `$SAMPLES` for the path of `tests/samples`.
+//!
+//! Adding a case is just adding files; nothing here needs to change.
+
+use proglang::{LineReader, Runner};
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// A writer whose contents can still be read after it's been handed over.
+#[derive(Clone, Default)]
+struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+impl Buffer {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn samples_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/samples")
+}
+
+fn samples() -> Vec<PathBuf> {
+    let mut programs: Vec<PathBuf> = fs::read_dir(samples_dir())
+        .expect("tests/samples is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "plang"))
+        .collect();
+    programs.sort();
+    programs
+}
+
+fn read_if_present(path: PathBuf) -> Option<String> {
+    fs::read_to_string(path).ok()
+}
+
+/// Runs one sample, describing how it went wrong if it did.
+fn check(program: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(program).unwrap();
+    let expected_out = read_if_present(program.with_extension("out"))
+        .ok_or_else(|| String::from("has no .out file"))?;
+    let expected_err = read_if_present(program.with_extension("err"));
+    let input = read_if_present(program.with_extension("in")).unwrap_or_default();
+    let expected_code = read_if_present(program.with_extension("code"));
+
+    let flags = read_if_present(program.with_extension("flags")).unwrap_or_default();
+
+    let out = Buffer::default();
+    let err = Buffer::default();
+    let mut runner = Runner::new()
+        .with_output(Box::new(out.clone()))
+        .with_input(Box::new(LineReader::new(io::Cursor::new(input))))
+        .with_source_file(program);
+    for flag in flags.lines() {
+        runner = match flag.trim() {
+            "--big-ints" => runner.with_big_ints(),
+            flag => return Err(format!("has an unknown flag {:?}", flag)),
+        };
+    }
+    let result = proglang::run_on(&source, runner, Box::new(err.clone()));
+
+    match (&expected_err, &result) {
+        (None, Err(error)) => return Err(format!("failed unexpectedly: {}", error)),
+        (Some(_), Ok(())) => return Err(String::from("succeeded but should have failed")),
+        _ => {}
+    }
+    if out.contents() != expected_out {
+        return Err(format!(
+            "printed {:?}, expected {:?}",
+            out.contents(),
+            expected_out
+        ));
+    }
+    let samples = fs::canonicalize(samples_dir()).unwrap();
+    let reported = err
+        .contents()
+        .replace(&*samples.to_string_lossy(), "$SAMPLES");
+    if reported != expected_err.unwrap_or_default() {
+        return Err(format!("reported {:?}", reported));
+    }
+    if let (Some(code), Err(error)) = (expected_code, &result) {
+        if code.trim() != error.exit_code().to_string() {
+            return Err(format!("exited with {}", error.exit_code()));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn sample_programs_print_what_they_should() {
+    let programs = samples();
+    assert!(!programs.is_empty(), "no samples found");
+
+    let failures: Vec<String> = programs
+        .iter()
+        .filter_map(|program| {
+            check(program)
+                .err()
+                .map(|why| format!("{}: {}", program.display(), why))
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}